@@ -0,0 +1,59 @@
+// SMHasher3 plugin for the Rust implementation of ChibiHash.
+//
+// Copy this file to smhasher3/hashes/ and link against the static library
+// built with `cargo rustc --release --lib --features smhasher --crate-type staticlib`,
+// which `make smhasher3-plugin` runs.
+#include "Platform.h"
+#include "Hashlib.h"
+
+extern "C" {
+void chibihash_rs_v1(const void * in, size_t len, uint64_t seed, void * out);
+void chibihash_rs_v2(const void * in, size_t len, uint64_t seed, void * out);
+}
+
+template <bool bswap>
+static void ChibiHashRs_v1( const void * in, const size_t len, const seed_t seed, void * out ) {
+    uint8_t buf[8];
+    chibihash_rs_v1(in, len, (uint64_t)seed, buf);
+    PUT_U64<bswap>(GET_U64<false>(buf, 0), (uint8_t *)out, 0);
+}
+
+template <bool bswap>
+static void ChibiHashRs_v2( const void * in, const size_t len, const seed_t seed, void * out ) {
+    uint8_t buf[8];
+    chibihash_rs_v2(in, len, (uint64_t)seed, buf);
+    PUT_U64<bswap>(GET_U64<false>(buf, 0), (uint8_t *)out, 0);
+}
+
+REGISTER_FAMILY(chibihash_rs,
+   $.src_url    = "https://github.com/thevilledev/ChibiHash-rs",
+   $.src_status = HashFamilyInfo::SRC_ACTIVE
+ );
+
+REGISTER_HASH(ChibiHash_rs_v1,
+   $.desc            = "ChibiHash v1 (Rust port)",
+   $.hash_flags      = 0,
+   $.impl_flags      =
+         FLAG_IMPL_MULTIPLY_64_64 |
+         FLAG_IMPL_ROTATE         |
+         FLAG_IMPL_LICENSE_MIT,
+   $.bits            = 64,
+   $.verification_LE = 0xB6D38567,
+   $.verification_BE = 0,
+   $.hashfn_native   = ChibiHashRs_v1<false>,
+   $.hashfn_bswap    = ChibiHashRs_v1<true>
+ );
+
+REGISTER_HASH(ChibiHash_rs_v2,
+   $.desc            = "ChibiHash v2 (Rust port)",
+   $.hash_flags      = 0,
+   $.impl_flags      =
+         FLAG_IMPL_MULTIPLY_64_64 |
+         FLAG_IMPL_ROTATE         |
+         FLAG_IMPL_LICENSE_MIT,
+   $.bits            = 64,
+   $.verification_LE = 0x65ED889A,
+   $.verification_BE = 0,
+   $.hashfn_native   = ChibiHashRs_v2<false>,
+   $.hashfn_bswap    = ChibiHashRs_v2<true>
+ );
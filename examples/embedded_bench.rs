@@ -0,0 +1,21 @@
+// Runs the `embedded_bench` harness on the host, using `Instant` as the
+// monotonic fallback counter (nanoseconds instead of cycles).
+//
+//     cargo run --release --example embedded_bench --features embedded-bench
+//
+// On a Cortex-M board, replace the counter with
+// `unsafe { chibihash::embedded_bench::Dwt::enable() }` and print the
+// results over RTT, semihosting or a UART instead.
+
+use chibihash::embedded_bench::{compare, KEY_LENS};
+use std::time::Instant;
+
+fn main() {
+    let start = Instant::now();
+    let mut counter = || start.elapsed().as_nanos() as u64;
+
+    println!("ns/hash (host fallback counter)");
+    for len in KEY_LENS {
+        println!("{}", compare(&mut counter, len, 100_000));
+    }
+}
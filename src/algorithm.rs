@@ -0,0 +1,159 @@
+//! The building blocks of an algorithm version, as a trait
+//!
+//! Every ChibiHash version has the same shape: four 64-bit lanes are seeded,
+//! 32-byte stripes are absorbed into them, and the tail and length are mixed
+//! into the result. [`ChibiAlgorithm`] names those steps, so code written
+//! against the trait works with [`V1`], [`V2`] and any later version. It is
+//! the supertrait of [`VersionMarker`], and
+//! [`generic::StreamingChibiHasher`](crate::generic::StreamingChibiHasher)
+//! streams any version that implements both.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::algorithm::ChibiAlgorithm;
+//! use chibihash::version::{V1, V2};
+//!
+//! // Hashes `prefix` once and reuses its lanes for every suffix; `prefix`
+//! // must be a whole number of stripes
+//! fn with_prefix<A: ChibiAlgorithm>(prefix: &[u8], suffix: &[u8]) -> u64 {
+//!     let mut h = A::init(0);
+//!     assert!(A::stripes(&mut h, prefix).is_empty());
+//!     let tail = A::stripes(&mut h, suffix);
+//!     A::finalize(h, tail, (prefix.len() + suffix.len()) as u64, 0)
+//! }
+//!
+//! let prefix = [7u8; 64];
+//! let key = [&prefix[..], b"hello"].concat();
+//! assert_eq!(with_prefix::<V1>(&prefix, b"hello"), chibihash::v1::chibi_hash64(&key, 0));
+//! assert_eq!(with_prefix::<V2>(&prefix, b"hello"), V2::hash64(&key, 0));
+//! ```
+//!
+//! [`VersionMarker`]: crate::version::VersionMarker
+
+use crate::version::{V1, V2};
+use crate::{v1, v2};
+
+/// Bytes absorbed per stripe
+pub const STRIPE_LEN: usize = 32;
+
+/// The steps of a ChibiHash algorithm version
+///
+/// `hash64(key, seed)` must equal `init(seed)`, then `stripes` over `key`,
+/// then `finalize` of the lanes, the remaining tail, `key.len()` and `seed`.
+pub trait ChibiAlgorithm {
+    /// Returns the initial lanes for `seed`
+    fn init(seed: u64) -> [u64; 4];
+
+    /// Absorbs one 32-byte stripe into `h`
+    fn stripe(h: &mut [u64; 4], stripe: &[u8; STRIPE_LEN]);
+
+    /// Absorbs every whole stripe of `input` into `h` and returns the
+    /// remaining bytes, fewer than [`STRIPE_LEN`]
+    ///
+    /// The default calls [`stripe`](Self::stripe) in a loop; versions with a
+    /// vectorized loop override it.
+    fn stripes<'a>(h: &mut [u64; 4], input: &'a [u8]) -> &'a [u8] {
+        let mut chunks = input.chunks_exact(STRIPE_LEN);
+        for chunk in &mut chunks {
+            Self::stripe(h, chunk.try_into().unwrap());
+        }
+        chunks.remainder()
+    }
+
+    /// Mixes `tail`, shorter than [`STRIPE_LEN`], the total input length
+    /// and `seed` into the lanes and returns the hash
+    fn finalize(h: [u64; 4], tail: &[u8], len: u64, seed: u64) -> u64;
+
+    /// Hashes `key` in one call
+    fn hash64(key: &[u8], seed: u64) -> u64 {
+        let mut h = Self::init(seed);
+        let tail = Self::stripes(&mut h, key);
+        Self::finalize(h, tail, key.len() as u64, seed)
+    }
+}
+
+impl ChibiAlgorithm for V1 {
+    fn init(seed: u64) -> [u64; 4] {
+        v1::initial_state(seed)
+    }
+
+    fn stripe(h: &mut [u64; 4], stripe: &[u8; STRIPE_LEN]) {
+        v1::process_stripes(h, stripe);
+    }
+
+    fn stripes<'a>(h: &mut [u64; 4], input: &'a [u8]) -> &'a [u8] {
+        v1::process_stripes(h, input)
+    }
+
+    fn finalize(h: [u64; 4], tail: &[u8], len: u64, seed: u64) -> u64 {
+        v1::finalize_state(h, tail, len, seed)
+    }
+
+    fn hash64(key: &[u8], seed: u64) -> u64 {
+        v1::chibi_hash64(key, seed)
+    }
+}
+
+impl ChibiAlgorithm for V2 {
+    fn init(seed: u64) -> [u64; 4] {
+        v2::initial_state(seed)
+    }
+
+    fn stripe(h: &mut [u64; 4], stripe: &[u8; STRIPE_LEN]) {
+        v2::process_stripes(h, stripe);
+    }
+
+    fn stripes<'a>(h: &mut [u64; 4], input: &'a [u8]) -> &'a [u8] {
+        v2::process_stripes(h, input)
+    }
+
+    fn finalize(h: [u64; 4], tail: &[u8], len: u64, seed: u64) -> u64 {
+        v2::finalize_state(h, tail, len, seed)
+    }
+
+    fn hash64(key: &[u8], seed: u64) -> u64 {
+        v2::chibi_hash64(key, seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A stand-in for a future version, using only the required methods
+    struct Scalar<A>(A);
+
+    impl<A: ChibiAlgorithm> ChibiAlgorithm for Scalar<A> {
+        fn init(seed: u64) -> [u64; 4] {
+            A::init(seed)
+        }
+
+        fn stripe(h: &mut [u64; 4], stripe: &[u8; STRIPE_LEN]) {
+            A::stripe(h, stripe)
+        }
+
+        fn finalize(h: [u64; 4], tail: &[u8], len: u64, seed: u64) -> u64 {
+            A::finalize(h, tail, len, seed)
+        }
+    }
+
+    fn check<A: ChibiAlgorithm>(expected: fn(&[u8], u64) -> u64) {
+        let data: [u8; 200] = core::array::from_fn(|i| (i * 37) as u8);
+        for len in [0, 1, 7, 8, 31, 32, 33, 100, 200] {
+            let key = &data[..len];
+            assert_eq!(A::hash64(key, 3), expected(key, 3), "len {}", len);
+            assert_eq!(Scalar::<A>::hash64(key, 3), expected(key, 3), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_v1_algorithm() {
+        check::<V1>(v1::chibi_hash64);
+    }
+
+    #[test]
+    fn test_v2_algorithm() {
+        check::<V2>(v2::chibi_hash64);
+    }
+}
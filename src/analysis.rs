@@ -0,0 +1,155 @@
+//! Tools for checking how ChibiHash distributes a concrete key set
+//!
+//! [`bucket_report`] hashes a set of keys into buckets and summarizes the
+//! resulting load, so the hash's behavior on real data can be checked before
+//! sizing a table or a shard count around it. Keys are hashed with `v2`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::analysis::bucket_report;
+//!
+//! let keys: Vec<String> = (0..10_000).map(|i| format!("user:{}", i)).collect();
+//! let report = bucket_report(&keys, 64, 0);
+//!
+//! assert_eq!(report.keys, 10_000);
+//! assert_eq!(report.hash_collisions, 0);
+//! assert!(report.max_load < 200);
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::mix::reduce;
+use crate::v2::chibi_hash64;
+
+/// Load distribution of a key set over a number of buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketReport {
+    /// Number of keys hashed
+    pub keys: usize,
+    /// Number of keys in each bucket
+    pub loads: Vec<usize>,
+    /// Smallest bucket load
+    pub min_load: usize,
+    /// Largest bucket load
+    pub max_load: usize,
+    /// Mean bucket load, `keys / loads.len()`
+    pub mean: f64,
+    /// Population variance of the bucket loads
+    pub variance: f64,
+    /// Number of buckets that received no key
+    pub empty_buckets: usize,
+    /// Keys that landed in an already occupied bucket
+    pub bucket_collisions: usize,
+    /// Keys whose full 64-bit hash equals that of an earlier key
+    ///
+    /// Duplicate keys are counted too, so pass distinct keys.
+    pub hash_collisions: usize,
+}
+
+impl BucketReport {
+    /// Pearson's chi-squared statistic of the loads against a uniform spread
+    ///
+    /// For a well-behaved hash this is close to `loads.len() - 1`.
+    pub fn chi_squared(&self) -> f64 {
+        if self.mean == 0.0 {
+            return 0.0;
+        }
+        self.variance * self.loads.len() as f64 / self.mean
+    }
+}
+
+/// Hashes `keys` with `seed` into `n_buckets` buckets and reports the load
+///
+/// A hash is mapped to a bucket with a multiply-shift reduction, which
+/// spreads the full 64-bit range evenly over `0..n_buckets`.
+///
+/// # Panics
+///
+/// Panics if `n_buckets` is zero.
+pub fn bucket_report<I>(keys: I, n_buckets: usize, seed: u64) -> BucketReport
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    assert!(n_buckets > 0, "n_buckets must be non-zero");
+
+    let mut loads = vec![0usize; n_buckets];
+    let mut hashes: Vec<u64> = keys
+        .into_iter()
+        .map(|key| chibi_hash64(key.as_ref(), seed))
+        .collect();
+    for &hash in &hashes {
+        loads[reduce(hash, n_buckets as u64) as usize] += 1;
+    }
+
+    hashes.sort_unstable();
+    let hash_collisions = hashes.windows(2).filter(|w| w[0] == w[1]).count();
+
+    let n_keys = hashes.len();
+    let mean = n_keys as f64 / n_buckets as f64;
+    let variance = loads
+        .iter()
+        .map(|&load| {
+            let d = load as f64 - mean;
+            d * d
+        })
+        .sum::<f64>()
+        / n_buckets as f64;
+    let empty_buckets = loads.iter().filter(|&&load| load == 0).count();
+
+    BucketReport {
+        keys: n_keys,
+        min_load: loads.iter().copied().min().unwrap_or(0),
+        max_load: loads.iter().copied().max().unwrap_or(0),
+        mean,
+        variance,
+        empty_buckets,
+        bucket_collisions: n_keys - (n_buckets - empty_buckets),
+        hash_collisions,
+        loads,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_report_counts() {
+        let keys: [&[u8]; 4] = [b"a", b"b", b"c", b"a"];
+        let report = bucket_report(keys, 1, 0);
+        assert_eq!(report.keys, 4);
+        assert_eq!(report.loads, [4]);
+        assert_eq!((report.min_load, report.max_load), (4, 4));
+        assert_eq!(report.variance, 0.0);
+        assert_eq!(report.bucket_collisions, 3);
+        assert_eq!(report.hash_collisions, 1);
+    }
+
+    #[test]
+    fn test_bucket_report_uniformity() {
+        let keys = (0u32..100_000).map(|i| i.to_le_bytes());
+        let report = bucket_report(keys, 256, 0);
+        assert_eq!(report.loads.iter().sum::<usize>(), 100_000);
+        assert_eq!(report.empty_buckets, 0);
+        assert_eq!(report.hash_collisions, 0);
+
+        // 255 degrees of freedom; the bounds are well outside p = 0.001
+        let chi2 = report.chi_squared();
+        assert!(chi2 > 150.0 && chi2 < 350.0, "chi2 = {}", chi2);
+    }
+
+    #[test]
+    fn test_bucket_report_empty() {
+        let report = bucket_report(core::iter::empty::<&[u8]>(), 8, 0);
+        assert_eq!(report.keys, 0);
+        assert_eq!(report.empty_buckets, 8);
+        assert_eq!(report.bucket_collisions, 0);
+        assert_eq!(report.chi_squared(), 0.0);
+    }
+}
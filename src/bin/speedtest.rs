@@ -0,0 +1,133 @@
+// Reproduces the speed figures quoted by the upstream ChibiHash author:
+// small-key latency in cycles/hash and bulk throughput in GB/s.
+//
+// Run with:
+//
+//     RUSTFLAGS="-C target-cpu=native" cargo run --release --bin speedtest
+
+use std::hint::black_box;
+use std::time::Instant;
+
+type HashFn = fn(&[u8], u64) -> u64;
+
+const VERSIONS: [(&str, HashFn); 2] = [
+    ("v1", chibihash::v1::chibi_hash64),
+    ("v2", chibihash::v2::chibi_hash64),
+];
+
+// Small keys are 1 to 31 bytes long, as in SMHasher's small key test
+const SMALL_KEY_MAX: usize = 31;
+const SMALL_KEY_ITERS: u64 = 100_000;
+const BULK_LEN: usize = 256 * 1024;
+const BULK_BYTES: usize = 1 << 30;
+const TRIALS: usize = 5;
+
+#[cfg(target_arch = "x86_64")]
+const COUNTER: Option<&str> = Some("rdtsc");
+#[cfg(target_arch = "aarch64")]
+const COUNTER: Option<&str> = Some("cntvct_el0");
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const COUNTER: Option<&str> = None;
+
+// Reads the cycle counter, if the target has one we can use
+#[cfg(target_arch = "x86_64")]
+fn cycles() -> u64 {
+    // SAFETY: rdtsc is available on every x86_64 CPU
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn cycles() -> u64 {
+    let ticks: u64;
+    // SAFETY: cntvct_el0 is readable from user space on Linux and macOS
+    unsafe { core::arch::asm!("isb", "mrs {}, cntvct_el0", out(reg) ticks) };
+    ticks
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cycles() -> u64 {
+    0
+}
+
+// Returns the best (cycles, nanoseconds) per hash over all trials
+fn small_keys(hash: HashFn) -> (f64, f64) {
+    let key = [0x5Au8; SMALL_KEY_MAX];
+    let mut best = (f64::MAX, f64::MAX);
+    for _ in 0..TRIALS {
+        let start = Instant::now();
+        let start_cycles = cycles();
+        let mut seed = 0;
+        for _ in 0..SMALL_KEY_ITERS {
+            for len in 1..=SMALL_KEY_MAX {
+                // Chain the result into the next seed to measure latency
+                seed = hash(black_box(&key[..len]), seed);
+            }
+        }
+        let end_cycles = cycles();
+        let elapsed = start.elapsed();
+        black_box(seed);
+
+        let n = (SMALL_KEY_ITERS * SMALL_KEY_MAX as u64) as f64;
+        let per_hash = (
+            end_cycles.wrapping_sub(start_cycles) as f64 / n,
+            elapsed.as_nanos() as f64 / n,
+        );
+        if per_hash.1 < best.1 {
+            best = per_hash;
+        }
+    }
+    best
+}
+
+// Returns the best throughput in GB/s over all trials
+fn bulk(hash: HashFn) -> f64 {
+    let data: Vec<u8> = (0..BULK_LEN).map(|i| (i * 31) as u8).collect();
+    let rounds = BULK_BYTES / BULK_LEN;
+    let mut best = 0.0f64;
+    for _ in 0..TRIALS {
+        let start = Instant::now();
+        let mut acc = 0;
+        for round in 0..rounds {
+            acc ^= hash(black_box(&data), round as u64);
+        }
+        let elapsed = start.elapsed();
+        black_box(acc);
+        best = best.max(BULK_BYTES as f64 / elapsed.as_secs_f64() / 1e9);
+    }
+    best
+}
+
+fn main() {
+    if cfg!(debug_assertions) {
+        eprintln!("warning: debug build, numbers are meaningless without --release");
+    }
+    match COUNTER {
+        Some(counter) => println!("cycle counter: {}", counter),
+        None => println!("cycle counter: unavailable, reporting time only"),
+    }
+    println!();
+    println!(
+        "{:<8}{:>18}{:>16}{:>14}",
+        "version", "small (cyc/hash)", "small (ns/hash)", "bulk (GB/s)"
+    );
+
+    for (name, hash) in VERSIONS {
+        let (cycles, nanos) = small_keys(hash);
+        let throughput = bulk(hash);
+        let cycles = match COUNTER {
+            Some(_) => format!("{:.1}", cycles),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<8}{:>18}{:>16.2}{:>14.2}",
+            name, cycles, nanos, throughput
+        );
+    }
+
+    println!();
+    println!(
+        "Small keys are 1..={} bytes. rdtsc counts reference cycles and \
+         cntvct_el0 counts timer ticks, which can differ from core cycles.",
+        SMALL_KEY_MAX
+    );
+}
@@ -0,0 +1,445 @@
+//! Block lists: per-block digests of a file for partial verification
+//!
+//! A [`BlockList`] splits its input into fixed-size blocks, typically
+//! [`DEFAULT_BLOCK_SIZE`] (1 MiB), and records the `v2` digest of each
+//! block plus a top-level digest over all of them. Comparing two block
+//! lists, or a block list and a stream, shows which blocks changed, so a
+//! resumable transfer fetches only those and a partial read checks only
+//! the blocks it touches with [`BlockList::read_block`].
+//!
+//! The top-level digest is [`tree::chibi_hash64_tree`] of the input with
+//! the block size as chunk size, so it can be computed from the data alone.
+//!
+//! [`tree::chibi_hash64_tree`]: crate::tree::chibi_hash64_tree
+//!
+//! A block list prints as text and parses back with [`str::parse`]:
+//!
+//! ```text
+//! # chibihash blocks seed=0000000000000000 block-size=1048576 len=1500000 root=5e1c3a9b7d2f4e60
+//! 8f3c2e5fa1b0d9c4
+//! 0d1e2f3a4b5c6d7e
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::blocks::BlockList;
+//! use std::io::Cursor;
+//!
+//! let mut data = vec![0u8; 10_000];
+//! let blocks = BlockList::from_data(&data, 4096, 0);
+//!
+//! data[5000] = 1;
+//! assert_eq!(blocks.verify(&data[..]).unwrap(), [1]);
+//!
+//! let mut file = Cursor::new(data);
+//! assert!(blocks.read_block(&mut file, 0).is_ok());
+//! assert!(blocks.read_block(&mut file, 1).is_err());
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::tree::root_hasher;
+use crate::v2::chibi_hash64;
+
+/// A block size suited to files of a few MiB and up, 1 MiB
+pub const DEFAULT_BLOCK_SIZE: usize = 1 << 20;
+
+/// The largest block size a parsed block list may declare, 64 MiB
+///
+/// [`BlockList::verify`] buffers one block, so parsing rejects larger sizes
+/// rather than let untrusted text pick the allocation.
+pub const MAX_BLOCK_SIZE: usize = 64 << 20;
+
+const HEADER: &str = "# chibihash blocks ";
+
+/// The digests of the fixed-size blocks of some input; see the [module
+/// documentation](self)
+///
+/// Every block holds `block_size` bytes except the last, which may be
+/// shorter. Empty input has no blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockList {
+    block_size: usize,
+    len: u64,
+    seed: u64,
+    digests: Vec<u64>,
+}
+
+impl BlockList {
+    /// Computes the block list of `data`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn from_data(data: &[u8], block_size: usize, seed: u64) -> Self {
+        assert!(block_size > 0, "block size must be non-zero");
+        Self {
+            block_size,
+            len: data.len() as u64,
+            seed,
+            digests: data
+                .chunks(block_size)
+                .map(|block| chibi_hash64(block, seed))
+                .collect(),
+        }
+    }
+
+    /// Computes the block list of everything `reader` yields, holding one
+    /// block in memory at a time
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn from_reader<R: Read>(mut reader: R, block_size: usize, seed: u64) -> io::Result<Self> {
+        assert!(block_size > 0, "block size must be non-zero");
+        let mut buf = vec![0; block_size];
+        let mut list = Self {
+            block_size,
+            len: 0,
+            seed,
+            digests: Vec::new(),
+        };
+        loop {
+            let n = fill(&mut reader, &mut buf)?;
+            if n == 0 {
+                return Ok(list);
+            }
+            list.len += n as u64;
+            list.digests.push(chibi_hash64(&buf[..n], seed));
+            if n < block_size {
+                return Ok(list);
+            }
+        }
+    }
+
+    /// Returns the top-level digest over the length and every block digest
+    pub fn root(&self) -> u64 {
+        let mut root = root_hasher(self.len, self.seed, self.block_size);
+        for digest in &self.digests {
+            root.update(&digest.to_le_bytes());
+        }
+        root.finalize()
+    }
+
+    /// Reads `reader` to the end and returns the indices of the blocks that
+    /// differ from this list, in order
+    ///
+    /// Blocks missing from the stream and blocks past the end of this list
+    /// both count as changed.
+    pub fn verify<R: Read>(&self, reader: R) -> io::Result<Vec<usize>> {
+        let current = Self::from_reader(reader, self.block_size, self.seed)?;
+        Ok(self.changed_blocks(&current))
+    }
+
+    /// Returns the indices of the blocks that differ between this list and
+    /// `other`, in order
+    ///
+    /// Lists with different block sizes or seeds have unrelated digests, so
+    /// every block is reported.
+    pub fn changed_blocks(&self, other: &BlockList) -> Vec<usize> {
+        let blocks = self.num_blocks().max(other.num_blocks());
+        if self.block_size != other.block_size || self.seed != other.seed {
+            return (0..blocks).collect();
+        }
+        (0..blocks)
+            .filter(|&i| self.digests.get(i) != other.digests.get(i))
+            .collect()
+    }
+
+    /// Returns `true` if `data` is block `index`
+    pub fn verify_block(&self, index: usize, data: &[u8]) -> bool {
+        self.block_range(index)
+            .is_some_and(|range| range.end - range.start == data.len() as u64)
+            && self.digests[index] == chibi_hash64(data, self.seed)
+    }
+
+    /// Reads block `index` from `reader` and checks it against its digest
+    ///
+    /// Returns [`io::ErrorKind::InvalidInput`] if there is no such block,
+    /// [`io::ErrorKind::UnexpectedEof`] if the input ends within the block
+    /// and [`io::ErrorKind::InvalidData`] if the digest does not match.
+    pub fn read_block<R: Read + Seek>(&self, reader: &mut R, index: usize) -> io::Result<Vec<u8>> {
+        let range = self.block_range(index).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "block index out of range")
+        })?;
+        reader.seek(SeekFrom::Start(range.start))?;
+        let len = range.end - range.start;
+        let mut block = Vec::with_capacity(len as usize);
+        reader.take(len).read_to_end(&mut block)?;
+        if block.len() as u64 != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated block",
+            ));
+        }
+        if self.digests[index] != chibi_hash64(&block, self.seed) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "block hash mismatch",
+            ));
+        }
+        Ok(block)
+    }
+
+    /// Returns the byte range of block `index` in the input
+    pub fn block_range(&self, index: usize) -> Option<Range<u64>> {
+        if index >= self.num_blocks() {
+            return None;
+        }
+        let start = index as u64 * self.block_size as u64;
+        Some(start..self.len.min(start + self.block_size as u64))
+    }
+
+    /// Returns the block digests, in order
+    pub fn digests(&self) -> &[u64] {
+        &self.digests
+    }
+
+    /// Returns the number of blocks
+    pub fn num_blocks(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Returns the block size in bytes
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the length of the input in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the input was empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the seed the digests were computed with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+// Reads until `buf` is full or the input ends, returning the bytes read
+fn fill<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+impl fmt::Display for BlockList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}seed={:016x} block-size={} len={} root={:016x}",
+            HEADER,
+            self.seed,
+            self.block_size,
+            self.len,
+            self.root()
+        )?;
+        for digest in &self.digests {
+            writeln!(f, "{:016x}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`BlockList`] fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBlockListError {
+    line: usize,
+}
+
+impl ParseBlockListError {
+    /// Returns the 1-based number of the offending line
+    ///
+    /// A header that does not match the digests that follow it, e.g. a wrong
+    /// root, is reported at line 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for ParseBlockListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid block list at line {}", self.line)
+    }
+}
+
+impl std::error::Error for ParseBlockListError {}
+
+impl FromStr for BlockList {
+    type Err = ParseBlockListError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let header_err = ParseBlockListError { line: 1 };
+        let mut lines = s.lines();
+        let mut fields = lines
+            .next()
+            .and_then(|header| header.strip_prefix(HEADER))
+            .ok_or(header_err.clone())?
+            .split(' ');
+        let mut field = |name: &str| {
+            fields
+                .next()
+                .and_then(|field| field.strip_prefix(name)?.strip_prefix('='))
+                .ok_or(header_err.clone())
+        };
+        let hex = |value: &str| u64::from_str_radix(value, 16).map_err(|_| header_err.clone());
+        let seed = hex(field("seed")?)?;
+        let block_size: usize = field("block-size")?
+            .parse()
+            .ok()
+            .filter(|&size| size > 0 && size <= MAX_BLOCK_SIZE)
+            .ok_or(header_err.clone())?;
+        let len: u64 = field("len")?.parse().map_err(|_| header_err.clone())?;
+        let root = hex(field("root")?)?;
+
+        let mut digests = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let digest = Some(line).filter(|line| line.len() == 16);
+            match digest.and_then(|digest| u64::from_str_radix(digest, 16).ok()) {
+                Some(digest) => digests.push(digest),
+                None => return Err(ParseBlockListError { line: i + 2 }),
+            }
+        }
+        let list = Self {
+            block_size,
+            len,
+            seed,
+            digests,
+        };
+        let blocks = len.div_ceil(block_size as u64);
+        if blocks != list.num_blocks() as u64 || list.root() != root {
+            return Err(header_err);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::tree::chibi_hash64_tree;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len as u32).map(|i| (i ^ (i >> 7)) as u8).collect()
+    }
+
+    #[test]
+    fn test_block_list() {
+        for len in [0, 1, 999, 1000, 1001, 5500] {
+            let data = data(len);
+            let list = BlockList::from_data(&data, 1000, 4);
+            assert_eq!(list, BlockList::from_reader(&data[..], 1000, 4).unwrap());
+            assert_eq!(list.root(), chibi_hash64_tree(&data, 4, 1000));
+            assert_eq!(list.num_blocks(), len.div_ceil(1000));
+            assert_eq!(list.len(), len as u64);
+            for (i, block) in data.chunks(1000).enumerate() {
+                assert_eq!(list.digests()[i], chibi_hash64(block, 4));
+                assert!(list.verify_block(i, block));
+                let range = list.block_range(i).unwrap();
+                assert_eq!(&data[range.start as usize..range.end as usize], block);
+            }
+            assert_eq!(list.block_range(list.num_blocks()), None);
+        }
+    }
+
+    #[test]
+    fn test_changed_blocks() {
+        let mut data = data(5500);
+        let list = BlockList::from_data(&data, 1000, 0);
+        assert!(list.verify(&data[..]).unwrap().is_empty());
+
+        data[1500] ^= 1;
+        data[4999] ^= 1;
+        assert_eq!(list.verify(&data[..]).unwrap(), [1, 4]);
+        assert_eq!(list.verify(&data[..3000]).unwrap(), [1, 3, 4, 5]);
+        data.extend_from_slice(&[0; 600]);
+        assert_eq!(list.verify(&data[..]).unwrap(), [1, 4, 5, 6]);
+
+        let other = BlockList::from_data(&data, 1000, 1);
+        assert_eq!(list.changed_blocks(&other), [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_read_block() {
+        let data = data(2500);
+        let list = BlockList::from_data(&data, 1000, 0);
+        let mut file = Cursor::new(data.clone());
+        assert_eq!(list.read_block(&mut file, 2).unwrap(), &data[2000..]);
+        assert_eq!(list.read_block(&mut file, 0).unwrap(), &data[..1000]);
+        let err = list.read_block(&mut file, 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut corrupted = data.clone();
+        corrupted[1234] ^= 1;
+        let err = list.read_block(&mut Cursor::new(corrupted), 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = list
+            .read_block(&mut Cursor::new(&data[..2400]), 2)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(!list.verify_block(2, &data[2000..2400]));
+    }
+
+    #[test]
+    fn test_text_roundtrip() {
+        let list = BlockList::from_data(&data(2500), 1000, 9);
+        let text = list.to_string();
+        assert!(text.starts_with(
+            "# chibihash blocks seed=0000000000000009 block-size=1000 len=2500 root="
+        ));
+        assert_eq!(text.lines().count(), 4);
+        assert_eq!(text.parse::<BlockList>().unwrap(), list);
+
+        let empty = BlockList::from_data(&[], 1000, 0);
+        assert_eq!(empty.to_string().parse::<BlockList>().unwrap(), empty);
+
+        assert_eq!("".parse::<BlockList>().unwrap_err().line(), 1);
+        let bad_digest = text.replacen('\n', "\nxyz\n", 1);
+        assert_eq!(bad_digest.parse::<BlockList>().unwrap_err().line(), 2);
+        let missing = text.rsplitn(3, '\n').nth(2).unwrap();
+        assert_eq!(missing.parse::<BlockList>().unwrap_err().line(), 1);
+        let edited = text.replacen("\n", "\n0000000000000000\n", 1);
+        let edited: String = edited.lines().take(4).map(|l| format!("{}\n", l)).collect();
+        assert_eq!(edited.parse::<BlockList>().unwrap_err().line(), 1);
+    }
+
+    #[test]
+    fn test_parse_block_size_limits() {
+        let list = BlockList::from_data(&data(100), MAX_BLOCK_SIZE, 0);
+        assert_eq!(list.to_string().parse::<BlockList>().unwrap(), list);
+
+        // Otherwise consistent lists, so only the size check can reject them
+        for block_size in [MAX_BLOCK_SIZE + 1, usize::MAX] {
+            let list = BlockList {
+                block_size,
+                len: 0,
+                seed: 0,
+                digests: Vec::new(),
+            };
+            let err = list.to_string().parse::<BlockList>().unwrap_err();
+            assert_eq!(err.line(), 1, "{}", block_size);
+        }
+        let text = BlockList::from_data(&[], 1, 0).to_string();
+        let zero = text.replacen("block-size=1 ", "block-size=0 ", 1);
+        assert_eq!(zero.parse::<BlockList>().unwrap_err().line(), 1);
+    }
+}
@@ -0,0 +1,523 @@
+//! Content-defined chunking
+//!
+//! Deduplicating storage splits data where the content says so, not at
+//! fixed offsets, so an insertion early in a file moves only the chunk
+//! boundaries near it. [`RollingHash`] is the primitive that finds those
+//! boundaries: a gear hash over the last [`WINDOW`] bytes, whose byte table
+//! is derived from ChibiHash. A position is a boundary when the rolling
+//! hash has its top bits clear, see [`RollingHash::is_boundary`].
+//!
+//! The rolling hash only locates boundaries and is not a content digest:
+//! identify each chunk by `v2::chibi_hash64` of its bytes.
+//!
+//! [`FastCdc`] (over a byte slice) and [`StreamCdc`] (over a reader, with
+//! the `std` feature) do both, splitting with FastCDC's normalized
+//! chunking between a minimum and maximum size and yielding a [`Chunk`]
+//! with each chunk's offset, length and digest.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::cdc::RollingHash;
+//! use chibihash::v2::chibi_hash64;
+//!
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8 ^ (i >> 9) as u8).collect();
+//! let mask = RollingHash::mask(12); // about one boundary per 4 KiB
+//!
+//! let mut hash = RollingHash::new();
+//! let mut start = 0;
+//! let mut digests = Vec::new();
+//! for (i, &byte) in data.iter().enumerate() {
+//!     hash.roll(byte);
+//!     if hash.is_boundary(mask) {
+//!         digests.push(chibi_hash64(&data[start..=i], 0));
+//!         start = i + 1;
+//!     }
+//! }
+//! digests.push(chibi_hash64(&data[start..], 0));
+//!
+//! // The same, with size limits
+//! use chibihash::cdc::FastCdc;
+//!
+//! for chunk in FastCdc::new(&data, 1024, 4096, 16384) {
+//!     assert!(chunk.len <= 16384);
+//! }
+//! ```
+
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+use crate::v2::{chibi_hash64, chibi_hash64_const};
+
+/// Number of most recent bytes the rolling hash depends on
+pub const WINDOW: usize = 64;
+
+const GEAR: [u64; 256] = gear_table(0);
+
+const fn gear_table(seed: u64) -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = chibi_hash64_const(&[i as u8], seed);
+        i += 1;
+    }
+    table
+}
+
+/// A gear rolling hash over the last [`WINDOW`] bytes
+///
+/// Each [`roll`](Self::roll) shifts the hash left by one and adds the table
+/// entry of the new byte, so bit `j` depends on the last `j + 1` bytes and
+/// the top bit on the whole window. Boundaries are tested on the top bits
+/// for that reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollingHash {
+    table: [u64; 256],
+    hash: u64,
+    seed: u64,
+}
+
+impl RollingHash {
+    /// Creates a rolling hash with seed 0
+    pub const fn new() -> Self {
+        Self {
+            table: GEAR,
+            hash: 0,
+            seed: 0,
+        }
+    }
+
+    /// Returns the hash reset and with its byte table derived from `seed`
+    ///
+    /// Different seeds give different boundaries for the same data, which
+    /// keeps chunk sizes from leaking content across tenants.
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.table = gear_table(seed);
+        self.hash = 0;
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the mask of the top `bits` bits, for [`is_boundary`]
+    ///
+    /// Boundaries then fall on average every `2^bits` bytes of random data.
+    /// `bits` above 64 is treated as 64.
+    ///
+    /// [`is_boundary`]: Self::is_boundary
+    pub const fn mask(bits: u32) -> u64 {
+        match u64::MAX.checked_shr(bits) {
+            Some(low) => !low,
+            None => u64::MAX,
+        }
+    }
+
+    /// Adds `byte` to the window and returns the new hash
+    #[inline]
+    pub fn roll(&mut self, byte: u8) -> u64 {
+        self.hash = (self.hash << 1).wrapping_add(self.table[byte as usize]);
+        self.hash
+    }
+
+    /// Returns `true` if the hash has every bit of `mask` clear
+    #[inline]
+    pub const fn is_boundary(&self, mask: u64) -> bool {
+        self.hash & mask == 0
+    }
+
+    /// Returns the current hash
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the seed
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Forgets every byte rolled in so far
+    pub fn reset(&mut self) {
+        self.hash = 0;
+    }
+}
+
+impl Default for RollingHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A chunk found by [`FastCdc`] or [`StreamCdc`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chunk {
+    /// Position of the first byte in the input
+    pub offset: u64,
+    /// Length in bytes
+    pub len: usize,
+    /// `v2::chibi_hash64` of the chunk's bytes under the chunker's seed
+    pub digest: u64,
+}
+
+// The boundary search shared by both chunkers
+#[derive(Debug, Clone)]
+struct Cutter {
+    hash: RollingHash,
+    min: usize,
+    avg: usize,
+    max: usize,
+    mask_small: u64,
+    mask_large: u64,
+}
+
+impl Cutter {
+    fn new(min: usize, avg: usize, max: usize) -> Self {
+        assert!(
+            0 < min && min <= avg && avg <= max,
+            "chunk sizes must satisfy 0 < min <= avg <= max"
+        );
+        let bits = avg.ilog2();
+        Self {
+            hash: RollingHash::new(),
+            min,
+            avg,
+            max,
+            // Harder to cut before `avg` and easier after, which narrows
+            // the size distribution around it
+            mask_small: RollingHash::mask(bits + 1),
+            mask_large: RollingHash::mask(bits.saturating_sub(1)),
+        }
+    }
+
+    // Length of the chunk at the start of `data`, which must hold `max`
+    // bytes unless it is the end of the input
+    fn cut(&mut self, data: &[u8]) -> usize {
+        if data.len() <= self.min {
+            return data.len();
+        }
+        let end = data.len().min(self.max);
+        let normal = end.min(self.avg);
+        self.hash.reset();
+        for (i, &byte) in data.iter().enumerate().take(normal).skip(self.min) {
+            self.hash.roll(byte);
+            if self.hash.is_boundary(self.mask_small) {
+                return i + 1;
+            }
+        }
+        for (i, &byte) in data.iter().enumerate().take(end).skip(normal) {
+            self.hash.roll(byte);
+            if self.hash.is_boundary(self.mask_large) {
+                return i + 1;
+            }
+        }
+        end
+    }
+}
+
+/// FastCDC chunking of a byte slice
+///
+/// Every chunk is at least `min` and at most `max` bytes, except that the
+/// last may be shorter than `min`. Sizes cluster around `avg`, rounded
+/// down to a power of two. The chunks cover the input in order and without
+/// gaps.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::cdc::FastCdc;
+///
+/// let data = vec![7u8; 10_000];
+/// let chunks: Vec<_> = FastCdc::new(&data, 512, 2048, 4096).collect();
+/// assert_eq!(chunks.iter().map(|c| c.len).sum::<usize>(), data.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastCdc<'a> {
+    data: &'a [u8],
+    offset: usize,
+    cutter: Cutter,
+    seed: u64,
+}
+
+impl<'a> FastCdc<'a> {
+    /// Creates a chunker over `data` with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min <= avg <= max`.
+    pub fn new(data: &'a [u8], min: usize, avg: usize, max: usize) -> Self {
+        Self {
+            data,
+            offset: 0,
+            cutter: Cutter::new(min, avg, max),
+            seed: 0,
+        }
+    }
+
+    /// Returns the chunker restarted, with boundaries and digests derived
+    /// from `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.offset = 0;
+        self.cutter.hash = RollingHash::new().with_seed(seed);
+        self.seed = seed;
+        self
+    }
+}
+
+impl Iterator for FastCdc<'_> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        let rest = &self.data[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = self.cutter.cut(rest);
+        let chunk = Chunk {
+            offset: self.offset as u64,
+            len,
+            digest: chibi_hash64(&rest[..len], self.seed),
+        };
+        self.offset += len;
+        Some(chunk)
+    }
+}
+
+impl core::iter::FusedIterator for FastCdc<'_> {}
+
+/// FastCDC chunking of a reader (feature `std`)
+///
+/// Cuts the same chunks as [`FastCdc`] over the same bytes, buffering at
+/// most `max` bytes, and yields each [`Chunk`] with its bytes so they can
+/// be stored. After a read error is returned, the bytes read before it are
+/// still chunked, and then the iteration ends.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::cdc::StreamCdc;
+///
+/// let file = std::io::Cursor::new(vec![1u8; 10_000]);
+/// for chunk in StreamCdc::new(file, 512, 2048, 4096) {
+///     let (chunk, bytes) = chunk?;
+///     assert_eq!(chunk.len, bytes.len());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct StreamCdc<R> {
+    reader: R,
+    buf: Vec<u8>,
+    offset: u64,
+    cutter: Cutter,
+    seed: u64,
+    eof: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> StreamCdc<R> {
+    /// Creates a chunker over `reader` with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min <= avg <= max`.
+    pub fn new(reader: R, min: usize, avg: usize, max: usize) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(max),
+            offset: 0,
+            cutter: Cutter::new(min, avg, max),
+            seed: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the chunker with boundaries and digests derived from `seed`
+    ///
+    /// Must be called before the first chunk is read.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.cutter.hash = RollingHash::new().with_seed(seed);
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the inner reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let max = self.cutter.max;
+        while !self.eof && self.buf.len() < max {
+            let len = self.buf.len();
+            self.buf.resize(max, 0);
+            let n = loop {
+                match self.reader.read(&mut self.buf[len..]) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        self.buf.truncate(len);
+                        self.eof = true;
+                        return Err(e);
+                    }
+                }
+            };
+            self.buf.truncate(len + n);
+            self.eof = n == 0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Iterator for StreamCdc<R> {
+    type Item = io::Result<(Chunk, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            return Some(Err(e));
+        }
+        if self.buf.is_empty() {
+            return None;
+        }
+        let len = self.cutter.cut(&self.buf);
+        let bytes: Vec<u8> = self.buf.drain(..len).collect();
+        let chunk = Chunk {
+            offset: self.offset,
+            len,
+            digest: chibi_hash64(&bytes, self.seed),
+        };
+        self.offset += len as u64;
+        Some(Ok((chunk, bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    extern crate alloc;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn test_rolling_hash_window() {
+        let data: [u8; 200] = core::array::from_fn(|i| (i * 31 % 256) as u8);
+        let mut full = RollingHash::new();
+        for &b in &data {
+            full.roll(b);
+        }
+        // Only the last WINDOW bytes matter
+        let mut tail = RollingHash::new();
+        for &b in &data[data.len() - WINDOW..] {
+            tail.roll(b);
+        }
+        assert_eq!(full.hash(), tail.hash());
+
+        let mut seeded = RollingHash::new().with_seed(1);
+        for &b in &data {
+            seeded.roll(b);
+        }
+        assert_ne!(seeded.hash(), full.hash());
+        assert_eq!(seeded.seed(), 1);
+
+        full.reset();
+        assert_eq!(full, RollingHash::default());
+    }
+
+    #[test]
+    fn test_boundaries() {
+        assert_eq!(RollingHash::mask(0), 0);
+        assert_eq!(RollingHash::mask(4), 0xF000_0000_0000_0000);
+        assert_eq!(RollingHash::mask(64), u64::MAX);
+        assert_eq!(RollingHash::mask(99), u64::MAX);
+
+        let mut hash = RollingHash::new();
+        let mut state = 1u64;
+        let mut boundaries = 0;
+        for _ in 0..1 << 20 {
+            state = crate::mix::moremur(state);
+            hash.roll(state as u8);
+            boundaries += hash.is_boundary(RollingHash::mask(10)) as u32;
+        }
+        // About one per KiB
+        assert!((900..1150).contains(&boundaries), "{}", boundaries);
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        let mut state = 3u64;
+        (0..len)
+            .map(|_| {
+                state = crate::mix::moremur(state);
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fast_cdc() {
+        let data = data(200_000);
+        let chunks: Vec<Chunk> = FastCdc::new(&data, 256, 1024, 4096).collect();
+        let mut offset = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.offset, offset as u64);
+            assert!(chunk.len <= 4096);
+            assert!(chunk.len >= 256 || i == chunks.len() - 1);
+            let bytes = &data[offset..offset + chunk.len];
+            assert_eq!(chunk.digest, chibi_hash64(bytes, 0));
+            offset += chunk.len;
+        }
+        assert_eq!(offset, data.len());
+        let mean = data.len() / chunks.len();
+        assert!((700..2000).contains(&mean), "{}", mean);
+
+        // Boundaries resynchronize after an insertion near the start
+        let mut shifted = data.clone();
+        shifted.insert(100, 0xAA);
+        let shared = FastCdc::new(&shifted, 256, 1024, 4096)
+            .filter(|c| chunks.iter().any(|d| d.digest == c.digest))
+            .count();
+        assert!(shared >= chunks.len() - 2);
+
+        let seeded: Vec<Chunk> = FastCdc::new(&data, 256, 1024, 4096).with_seed(5).collect();
+        assert_ne!(seeded, chunks);
+        assert_eq!(FastCdc::new(&[], 1, 1, 1).next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fast_cdc_bad_sizes() {
+        FastCdc::new(&[], 2048, 1024, 4096);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_stream_cdc() {
+        // Reads a few bytes at a time, interrupted now and then
+        struct Trickle<'a>(&'a [u8], usize);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                if self.1.is_multiple_of(5) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                let n = buf.len().min(self.0.len()).min(777);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let data = data(100_000);
+        let expected: Vec<Chunk> = FastCdc::new(&data, 256, 1024, 4096).with_seed(2).collect();
+        let mut joined = Vec::new();
+        let mut chunks = Vec::new();
+        for item in StreamCdc::new(Trickle(&data, 0), 256, 1024, 4096).with_seed(2) {
+            let (chunk, bytes) = item.unwrap();
+            joined.extend_from_slice(&bytes);
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks, expected);
+        assert_eq!(joined, data);
+    }
+}
@@ -0,0 +1,470 @@
+//! Seeded constructors for collections hashed with ChibiHash
+//!
+//! [`WithSeed`] adds `with_seed` and `with_capacity_and_seed` to the map and
+//! set aliases of both versions, e.g. `ChibiHashMap` and, with the
+//! `indexmap` and `dashmap` features, `ChibiIndexMap` and `ChibiDashMap`,
+//! so a seed can be given without naming the `BuildHasher`. With the `lru`
+//! feature, [`LruWithSeed`] does the same for `ChibiLruCache`.
+//!
+//! With the `random` feature, [`WithRandomSeed`] gives each new collection
+//! its own seed from [`random_seed`](crate::random::random_seed), which keeps
+//! keys chosen by an attacker from colliding on purpose. [`WithSeed`] stays
+//! deterministic, and so does `default()` unless the `random-seed` feature
+//! is enabled: it makes every alias hash with `ChibiRandomState`, so
+//! `default()` draws a fresh seed per collection. `ChibiHashMapRandom` and
+//! `ChibiHashSetRandom` do that without the feature.
+//!
+//! With the `hashbrown` feature, [`PrehashedMapExt`] looks up and inserts
+//! keys whose hash is already known, without hashing them again.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
+//! use chibihash::collections::WithSeed;
+//! use chibihash::v2::ChibiHashMap;
+//!
+//! let mut map: ChibiHashMap<&str, u32> = ChibiHashMap::with_seed(42);
+//! map.insert("a", 1);
+//! assert_eq!(map.get("a"), Some(&1));
+//! # }
+//! ```
+
+use core::hash::BuildHasher;
+
+use crate::{v1, v2};
+
+/// A `BuildHasher` that can be created from a seed
+pub trait SeedableBuildHasher: BuildHasher {
+    /// Returns a builder whose hashers start from `seed`
+    fn from_seed(seed: u64) -> Self;
+}
+
+impl SeedableBuildHasher for v1::ChibiBuildHasher {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl SeedableBuildHasher for v2::ChibiBuildHasher {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl SeedableBuildHasher for v1::ChibiHasher {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl SeedableBuildHasher for v2::ChibiHasher {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+#[cfg(feature = "random")]
+impl SeedableBuildHasher for v1::ChibiRandomState {
+    fn from_seed(seed: u64) -> Self {
+        Self::with_seed(seed)
+    }
+}
+
+#[cfg(feature = "random")]
+impl SeedableBuildHasher for v2::ChibiRandomState {
+    fn from_seed(seed: u64) -> Self {
+        Self::with_seed(seed)
+    }
+}
+
+/// Collections that can be created with a seeded ChibiHash builder
+pub trait WithSeed: Sized {
+    /// Creates an empty collection hashed with `seed`
+    fn with_seed(seed: u64) -> Self {
+        Self::with_capacity_and_seed(0, seed)
+    }
+
+    /// Creates an empty collection with room for `capacity` elements,
+    /// hashed with `seed`
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self;
+}
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::{HashMap as BaseHashMap, HashSet as BaseHashSet};
+#[cfg(all(feature = "std", not(feature = "hashbrown")))]
+use std::collections::{HashMap as BaseHashMap, HashSet as BaseHashSet};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K, V, S: SeedableBuildHasher> WithSeed for BaseHashMap<K, V, S> {
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T, S: SeedableBuildHasher> WithSeed for BaseHashSet<T, S> {
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K, V, S: SeedableBuildHasher> WithSeed for indexmap::IndexMap<K, V, S> {
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<T, S: SeedableBuildHasher> WithSeed for indexmap::IndexSet<T, S> {
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+#[cfg(feature = "dashmap")]
+impl<K, V, S> WithSeed for dashmap::DashMap<K, V, S>
+where
+    K: Eq + core::hash::Hash,
+    S: SeedableBuildHasher + Clone,
+{
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+#[cfg(feature = "dashmap")]
+impl<T, S> WithSeed for dashmap::DashSet<T, S>
+where
+    T: Eq + core::hash::Hash,
+    S: SeedableBuildHasher + Clone,
+{
+    fn with_capacity_and_seed(capacity: usize, seed: u64) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::from_seed(seed))
+    }
+}
+
+/// Seeded constructors for `lru::LruCache`, whose capacity is a bound
+/// rather than a preallocation
+#[cfg(feature = "lru")]
+pub trait LruWithSeed: Sized {
+    /// Creates a cache holding at most `cap` entries, hashed with `seed`
+    fn with_seed(cap: core::num::NonZeroUsize, seed: u64) -> Self;
+
+    /// Creates a cache with no capacity limit, hashed with `seed`
+    fn unbounded_with_seed(seed: u64) -> Self;
+}
+
+#[cfg(feature = "lru")]
+impl<K, V, S> LruWithSeed for lru::LruCache<K, V, S>
+where
+    K: Eq + core::hash::Hash,
+    S: SeedableBuildHasher,
+{
+    fn with_seed(cap: core::num::NonZeroUsize, seed: u64) -> Self {
+        Self::with_hasher(cap, S::from_seed(seed))
+    }
+
+    fn unbounded_with_seed(seed: u64) -> Self {
+        Self::unbounded_with_hasher(S::from_seed(seed))
+    }
+}
+
+/// Lookup and insertion with a precomputed hash, for `hashbrown` maps
+///
+/// `hash` must be the hash the map's own builder gives the key, e.g.
+/// `map.hasher().hash_one(&key)` or, for `ChibiHashMap`, `hash_one(&key,
+/// seed)` of the same version. With any other value lookups miss and
+/// inserted entries cannot be found by the ordinary methods.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "hashbrown")]
+/// # {
+/// use chibihash::collections::{PrehashedMapExt, WithSeed};
+/// use chibihash::v2::{hash_one, ChibiHashMap};
+///
+/// let mut map: ChibiHashMap<u64, &str> = ChibiHashMap::with_seed(7);
+/// let hash = hash_one(&42u64, 7);
+/// map.insert_prehashed(hash, 42, "answer");
+/// assert_eq!(map.get_prehashed(hash, &42), Some(&"answer"));
+/// assert_eq!(map.get(&42), Some(&"answer"));
+/// # }
+/// ```
+#[cfg(feature = "hashbrown")]
+pub trait PrehashedMapExt<K, V> {
+    /// Returns the value for `key`, whose hash is `hash`
+    fn get_prehashed<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized;
+
+    /// Returns the value for `key` mutably, whose hash is `hash`
+    fn get_mut_prehashed<Q>(&mut self, hash: u64, key: &Q) -> Option<&mut V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized;
+
+    /// Inserts `key` with hash `hash`, returning the previous value
+    ///
+    /// `K: Hash` is only used to rehash existing keys when the map grows.
+    fn insert_prehashed(&mut self, hash: u64, key: K, value: V) -> Option<V>
+    where
+        K: Eq + core::hash::Hash;
+
+    /// Removes `key`, whose hash is `hash`, returning its value
+    fn remove_prehashed<Q>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized;
+}
+
+/// Collections that can be created with a random seed
+///
+/// Implemented for every [`WithSeed`] collection. Each call draws a new seed,
+/// so two collections never share one.
+///
+/// ```rust
+/// # #[cfg(all(feature = "getrandom", feature = "std"))]
+/// # {
+/// use chibihash::collections::WithRandomSeed;
+/// use chibihash::v2::ChibiHashSet;
+///
+/// let mut set: ChibiHashSet<String> = ChibiHashSet::with_random_seed();
+/// set.insert("untrusted key".to_string());
+/// # }
+/// ```
+#[cfg(feature = "random")]
+pub trait WithRandomSeed: WithSeed {
+    /// Creates an empty collection with a fresh random seed
+    fn with_random_seed() -> Self {
+        Self::with_seed(crate::random::random_seed())
+    }
+
+    /// Creates an empty collection with room for `capacity` elements and a
+    /// fresh random seed
+    fn with_capacity_and_random_seed(capacity: usize) -> Self {
+        Self::with_capacity_and_seed(capacity, crate::random::random_seed())
+    }
+}
+
+#[cfg(feature = "random")]
+impl<T: WithSeed> WithRandomSeed for T {}
+
+#[cfg(feature = "hashbrown")]
+impl<K, V, S: BuildHasher> PrehashedMapExt<K, V> for hashbrown::HashMap<K, V, S> {
+    fn get_prehashed<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.raw_entry()
+            .from_hash(hash, |k| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    fn get_mut_prehashed<Q>(&mut self, hash: u64, key: &Q) -> Option<&mut V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        use hashbrown::hash_map::RawEntryMut;
+
+        match self.raw_entry_mut().from_hash(hash, |k| k.borrow() == key) {
+            RawEntryMut::Occupied(entry) => Some(entry.into_mut()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+
+    fn insert_prehashed(&mut self, hash: u64, key: K, value: V) -> Option<V>
+    where
+        K: Eq + core::hash::Hash,
+    {
+        use hashbrown::hash_map::RawEntryMut;
+
+        match self.raw_entry_mut().from_hash(hash, |k| *k == key) {
+            RawEntryMut::Occupied(mut entry) => Some(entry.insert(value)),
+            RawEntryMut::Vacant(entry) => {
+                entry.insert_hashed_nocheck(hash, key, value);
+                None
+            }
+        }
+    }
+
+    fn remove_prehashed<Q>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: core::borrow::Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        use hashbrown::hash_map::RawEntryMut;
+
+        match self.raw_entry_mut().from_hash(hash, |k| k.borrow() == key) {
+            RawEntryMut::Occupied(entry) => Some(entry.remove()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    #[test]
+    fn test_hash_map_with_seed() {
+        let map: v2::ChibiHashMap<u32, u32> = WithSeed::with_capacity_and_seed(10, 7);
+        assert!(map.capacity() >= 10);
+        assert_eq!(
+            map.hasher().hash_one(1u32),
+            v2::ChibiHasher::new(7).hash_one(1u32)
+        );
+
+        let set: v1::ChibiHashSet<u32> = WithSeed::with_seed(7);
+        assert_eq!(
+            set.hasher().hash_one(1u32),
+            v1::ChibiHasher::new(7).hash_one(1u32)
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_index_map_with_seed() {
+        let mut map = v2::ChibiIndexMap::with_seed(7);
+        map.insert("b", 2);
+        map.insert("a", 1);
+        assert_eq!(map.get_index(0), Some((&"b", &2)));
+        assert_eq!(
+            map.hasher().hash_one("a"),
+            v2::ChibiHasher::new(7).hash_one("a")
+        );
+
+        let mut set = v1::ChibiIndexSet::with_capacity_and_seed(4, 3);
+        assert!(set.insert(5u8));
+        assert!(!set.insert(5u8));
+        assert!(set.capacity() >= 4);
+        assert_eq!(
+            set.hasher().hash_one(5u8),
+            v1::ChibiHasher::new(3).hash_one(5u8)
+        );
+    }
+
+    #[cfg(feature = "dashmap")]
+    #[test]
+    fn test_dash_map_with_seed() {
+        let map = v2::ChibiDashMap::with_seed(7);
+        std::thread::scope(|s| {
+            for i in 0..4u32 {
+                let map = &map;
+                s.spawn(move || map.insert(i, i * 10));
+            }
+        });
+        assert_eq!(map.len(), 4);
+        assert_eq!(*map.get(&3).unwrap(), 30);
+        assert_eq!(
+            map.hasher().hash_one(3u32),
+            v2::ChibiHasher::new(7).hash_one(3u32)
+        );
+
+        let set = v1::ChibiDashSet::with_capacity_and_seed(8, 3);
+        assert!(set.insert("a"));
+        assert!(!set.insert("a"));
+        assert!(set.capacity() >= 8);
+    }
+
+    #[cfg(feature = "lru")]
+    #[test]
+    fn test_lru_with_seed() {
+        use core::num::NonZeroUsize;
+
+        let mut cache = v2::ChibiLruCache::with_seed(NonZeroUsize::new(2).unwrap(), 7);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.get(&"a");
+        cache.put("c", 3);
+        assert_eq!(cache.peek(&"b"), None);
+        assert_eq!(cache.peek(&"a"), Some(&1));
+
+        let cache: v1::ChibiLruCache<u32, u32> = LruWithSeed::unbounded_with_seed(3);
+        assert_eq!(cache.cap(), NonZeroUsize::MAX);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn test_heapless_map() {
+        // The builder a map looks keys up with, by the map's own type
+        fn build_hasher<K, V, S: Default, const N: usize>(_: &heapless::IndexMap<K, V, S, N>) -> S {
+            S::default()
+        }
+
+        static EMPTY: v1::ChibiHeaplessSet<u32, 4> = v1::ChibiHeaplessSet::new();
+        assert!(EMPTY.is_empty());
+
+        let mut map = v2::ChibiHeaplessMap::<&str, u32, 4>::new();
+        for (i, key) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            assert_eq!(map.insert(key, i as u32), Ok(None));
+        }
+        assert_eq!(map.insert("e", 4), Err(("e", 4)));
+        assert_eq!(map.get("c"), Some(&2));
+
+        let ids = v2::ChibiHeaplessMap::<u32, u32, 4>::new();
+        assert_eq!(
+            build_hasher(&ids).hash_one(7u32),
+            u64::from(v2::chibi_hash32(&7u32.to_le_bytes(), 0))
+        );
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn test_prehashed_map() {
+        let mut map: v1::ChibiHashMap<&str, u32> = WithSeed::with_seed(7);
+        let hash = |key: &str| map_hash(key, 7);
+        assert_eq!(map.insert_prehashed(hash("a"), "a", 1), None);
+        assert_eq!(map.insert_prehashed(hash("a"), "a", 2), Some(1));
+        map.insert("b", 3);
+
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get_prehashed(hash("b"), "b"), Some(&3));
+        assert_eq!(map.get_prehashed(hash("c"), "c"), None);
+        *map.get_mut_prehashed(hash("b"), "b").unwrap() += 1;
+        assert_eq!(map.remove_prehashed(hash("b"), "b"), Some(4));
+        assert_eq!(map.remove_prehashed(hash("b"), "b"), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[cfg(feature = "hashbrown")]
+    fn map_hash(key: &str, seed: u64) -> u64 {
+        v1::ChibiHasher::new(seed).hash_one(key)
+    }
+
+    #[cfg(all(feature = "random", any(feature = "std", feature = "hashbrown")))]
+    #[test]
+    fn test_with_random_seed() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+        fn counting_source() -> u64 {
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        }
+        crate::random::set_entropy_source(counting_source);
+
+        let a: v2::ChibiHashMap<u32, u32> = WithRandomSeed::with_random_seed();
+        let b: v2::ChibiHashMap<u32, u32> = WithRandomSeed::with_capacity_and_random_seed(8);
+        assert!(b.capacity() >= 8);
+        assert_ne!(a.hasher().hash_one(1u32), b.hasher().hash_one(1u32));
+    }
+
+    #[cfg(all(feature = "random-seed", feature = "std"))]
+    #[test]
+    fn test_random_seed_default() {
+        let a = v1::ChibiHashMap::<u32, u32>::default();
+        let b = v1::ChibiHashMap::<u32, u32>::default();
+        assert_ne!(a.hasher().seed(), b.hasher().seed());
+
+        // Seeded constructors stay deterministic
+        let c: v2::ChibiHashSet<u32> = WithSeed::with_seed(7);
+        assert_eq!(c.hasher().seed(), 7);
+    }
+}
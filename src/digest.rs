@@ -0,0 +1,115 @@
+//! [RustCrypto `digest`](https://docs.rs/digest) trait implementations
+//!
+//! [`ChibiHash64Core`] implements [`Update`], [`FixedOutput`],
+//! [`FixedOutputReset`] and [`Reset`] with an 8-byte output, so ChibiHash can
+//! be used by code generic over those traits, such as checksum pipelines.
+//!
+//! The output is the `v2` hash in big-endian byte order, so its hex encoding
+//! reads the same as `format!("{:016x}", hash)`.
+//!
+//! It deliberately does not implement `HashMarker`, and therefore not
+//! `Digest`: that marker is reserved for cryptographic hash functions, and
+//! ChibiHash is not one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::digest::ChibiHash64Core;
+//! use digest::{FixedOutput, Update};
+//!
+//! fn checksum<D: Update + FixedOutput + Default>(data: &[u8]) -> Vec<u8> {
+//!     let mut hasher = D::default();
+//!     hasher.update(data);
+//!     hasher.finalize_fixed().to_vec()
+//! }
+//!
+//! let out = checksum::<ChibiHash64Core>(b"abcdefgh");
+//! assert_eq!(out, chibihash::v2::chibi_hash64(b"abcdefgh", 0).to_be_bytes());
+//! ```
+
+use ::digest::consts::U8;
+use ::digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+
+use crate::v2::StreamingChibiHasher;
+
+/// `v2` ChibiHash behind the RustCrypto `digest` traits
+///
+/// `Default` uses seed 0. [`Reset`] returns to the initial state for the
+/// same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChibiHash64Core {
+    hasher: StreamingChibiHasher,
+}
+
+impl ChibiHash64Core {
+    /// Creates a hasher using `seed`
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            hasher: StreamingChibiHasher::new(seed),
+        }
+    }
+}
+
+impl Default for ChibiHash64Core {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl OutputSizeUser for ChibiHash64Core {
+    type OutputSize = U8;
+}
+
+impl Update for ChibiHash64Core {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+}
+
+impl FixedOutput for ChibiHash64Core {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.hasher.finalize().to_be_bytes());
+    }
+}
+
+impl FixedOutputReset for ChibiHash64Core {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.hasher.finalize().to_be_bytes());
+        Reset::reset(self);
+    }
+}
+
+impl Reset for ChibiHash64Core {
+    fn reset(&mut self) {
+        self.hasher = StreamingChibiHasher::new(self.hasher.seed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::v2::chibi_hash64;
+
+    #[test]
+    fn test_matches_v2() {
+        let mut hasher = ChibiHash64Core::new(5);
+        Update::update(&mut hasher, b"hello ");
+        Update::update(&mut hasher, b"world");
+        let out = hasher.finalize_fixed();
+        assert_eq!(out[..], chibi_hash64(b"hello world", 5).to_be_bytes());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut hasher = ChibiHash64Core::new(5);
+        Update::update(&mut hasher, b"first");
+        let first = hasher.finalize_fixed_reset();
+        assert_eq!(first[..], chibi_hash64(b"first", 5).to_be_bytes());
+        assert_eq!(hasher, ChibiHash64Core::new(5));
+
+        Update::update(&mut hasher, b"second");
+        Reset::reset(&mut hasher);
+        assert_eq!(hasher, ChibiHash64Core::new(5));
+    }
+}
@@ -0,0 +1,214 @@
+//! `no_std` harness for measuring hashing cost on microcontrollers
+//!
+//! The harness times `v1` and `v2` with a [`CycleCounter`]. On Cortex-M3 and
+//! later, `Dwt` reads the core's DWT cycle counter. On other targets any
+//! monotonic counter can be used: every `FnMut() -> u64` is a
+//! [`CycleCounter`], so a hardware timer or `std::time::Instant` on a host
+//! works too.
+//!
+//! Nothing here allocates. Results implement [`Display`](core::fmt::Display)
+//! so they can be printed over RTT, semihosting or a UART.
+//!
+//! # Examples
+//!
+//! On a Cortex-M target:
+//!
+//! ```rust,ignore
+//! use chibihash::embedded_bench::{compare, Dwt, KEY_LENS};
+//!
+//! // SAFETY: runs once at startup in privileged mode
+//! let mut dwt = unsafe { Dwt::enable() };
+//! for len in KEY_LENS {
+//!     rprintln!("{}", compare(&mut dwt, len, 1000));
+//! }
+//! ```
+//!
+//! On any target, with a custom counter:
+//!
+//! ```rust
+//! use chibihash::embedded_bench::compare;
+//!
+//! let mut ticks = 0u64;
+//! let mut counter = || {
+//!     ticks += 1;
+//!     ticks
+//! };
+//! let result = compare(&mut counter, 16, 10);
+//! assert_eq!(result.key_len, 16);
+//! ```
+
+use core::fmt;
+
+/// Key lengths covered by a typical run, in bytes
+pub const KEY_LENS: [usize; 7] = [4, 8, 16, 32, 64, 128, 256];
+
+/// Longest key [`compare`] accepts
+pub const MAX_KEY_LEN: usize = 256;
+
+/// A monotonic counter used to time hashing
+pub trait CycleCounter {
+    /// Returns the current count
+    fn cycles(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> CycleCounter for F {
+    fn cycles(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// The Cortex-M DWT cycle counter
+///
+/// The hardware counter is 32 bits wide. It is extended to 64 bits in
+/// software, which stays correct as long as it is read at least once per
+/// wrap (about 26 seconds at 168 MHz).
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+#[derive(Debug)]
+pub struct Dwt {
+    last: u32,
+    high: u64,
+}
+
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+impl Dwt {
+    const DEMCR: *mut u32 = 0xE000_EDFC as *mut u32;
+    const DWT_CTRL: *mut u32 = 0xE000_1000 as *mut u32;
+    const DWT_CYCCNT: *mut u32 = 0xE000_1004 as *mut u32;
+    const DEMCR_TRCENA: u32 = 1 << 24;
+    const CTRL_CYCCNTENA: u32 = 1;
+
+    /// Enables the DWT cycle counter
+    ///
+    /// # Safety
+    ///
+    /// Must run in privileged mode on a core that implements the DWT
+    /// (Cortex-M3 and later, not Cortex-M0/M0+). Other code must not be
+    /// reconfiguring the DWT or debug monitor at the same time.
+    pub unsafe fn enable() -> Self {
+        // SAFETY: the caller guarantees the registers exist and are ours
+        unsafe {
+            let demcr = core::ptr::read_volatile(Self::DEMCR);
+            core::ptr::write_volatile(Self::DEMCR, demcr | Self::DEMCR_TRCENA);
+            core::ptr::write_volatile(Self::DWT_CYCCNT, 0);
+            let ctrl = core::ptr::read_volatile(Self::DWT_CTRL);
+            core::ptr::write_volatile(Self::DWT_CTRL, ctrl | Self::CTRL_CYCCNTENA);
+        }
+        Self { last: 0, high: 0 }
+    }
+}
+
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+impl CycleCounter for Dwt {
+    fn cycles(&mut self) -> u64 {
+        // SAFETY: `enable` established that the register is readable
+        let now = unsafe { core::ptr::read_volatile(Self::DWT_CYCCNT) };
+        if now < self.last {
+            self.high += 1 << 32;
+        }
+        self.last = now;
+        self.high | u64::from(now)
+    }
+}
+
+/// Returns the average count per call of `hash(key, seed)`
+///
+/// Each call feeds the previous result back in as the seed, so the figure
+/// is latency rather than throughput. Counter overhead is included once per
+/// run, not per call.
+pub fn measure<C: CycleCounter + ?Sized>(
+    counter: &mut C,
+    hash: fn(&[u8], u64) -> u64,
+    key: &[u8],
+    iterations: u32,
+) -> u64 {
+    let iterations = iterations.max(1);
+    let mut seed = 0;
+    let start = counter.cycles();
+    for _ in 0..iterations {
+        seed = hash(core::hint::black_box(key), seed);
+    }
+    let end = counter.cycles();
+    core::hint::black_box(seed);
+    end.wrapping_sub(start) / u64::from(iterations)
+}
+
+/// Per-hash cost of `v1` and `v2` for one key length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    /// Key length in bytes
+    pub key_len: usize,
+    /// Average count per `v1` hash
+    pub v1: u64,
+    /// Average count per `v2` hash
+    pub v2: u64,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>4} B: v1 {:>6} per hash, v2 {:>6} per hash",
+            self.key_len, self.v1, self.v2
+        )
+    }
+}
+
+/// Measures `v1` and `v2` on a `key_len`-byte key
+///
+/// # Panics
+///
+/// Panics if `key_len` exceeds [`MAX_KEY_LEN`].
+pub fn compare<C: CycleCounter + ?Sized>(
+    counter: &mut C,
+    key_len: usize,
+    iterations: u32,
+) -> Comparison {
+    assert!(key_len <= MAX_KEY_LEN, "key length exceeds MAX_KEY_LEN");
+    let mut key = [0u8; MAX_KEY_LEN];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let key = &key[..key_len];
+    Comparison {
+        key_len,
+        v1: measure(counter, crate::v1::chibi_hash64, key, iterations),
+        v2: measure(counter, crate::v2::chibi_hash64, key, iterations),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_counts_per_call() {
+        // A fake counter that advances by 100 on every read
+        let mut now = 0u64;
+        let mut counter = || {
+            now += 100;
+            now
+        };
+        assert_eq!(
+            measure(&mut counter, crate::v2::chibi_hash64, b"key", 4),
+            25
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut now = 0u64;
+        let mut counter = || {
+            now += 10;
+            now
+        };
+        let result = compare(&mut counter, 32, 10);
+        assert_eq!(
+            result,
+            Comparison {
+                key_len: 32,
+                v1: 1,
+                v2: 1
+            }
+        );
+    }
+}
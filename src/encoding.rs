@@ -0,0 +1,565 @@
+//! Text encodings for 64-bit digests
+//!
+//! All encoders return an [`Encoded`] value backed by a fixed-size stack
+//! buffer, so they work without an allocator.
+//!
+//! - [`to_proquint`] / [`from_proquint`]: pronounceable five-letter words,
+//!   e.g. `lusab-babad-gutih-tugad`, for reading a digest over the phone.
+//! - [`to_base58`] / [`from_base58`]: 11 characters from the Bitcoin base58
+//!   alphabet, URL-safe and free of look-alike characters.
+//! - [`to_crockford32`] / [`from_crockford32`]: 13 characters of Crockford's
+//!   base32. Fixed width, so the text sorts like the number it encodes.
+//! - [`to_hex`] / [`from_hex`]: 16 lowercase hex digits.
+//! - [`to_base64`] / [`from_base64`]: 11 characters of unpadded URL-safe
+//!   base64 (RFC 4648 section 5).
+//! - [`to_base32`] / [`from_base32`]: 13 characters of unpadded lowercase
+//!   RFC 4648 base32.
+//!
+//! The last three encode the big-endian bytes of the digest. All but the
+//! proquints are also available on the [`ChibiHash64`](crate::ChibiHash64)
+//! and [`ChibiHash128`](crate::ChibiHash128) digest types, which use 22 and
+//! 26 characters for base58 and Crockford base32 at 128 bits.
+//!
+//! The short forms are handy as log correlation IDs and URL slugs derived
+//! from content hashes.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::encoding::{from_proquint, to_proquint};
+//!
+//! let hash = chibihash::v2::chibi_hash64(b"ticket #4711", 0);
+//! let words = to_proquint(hash);
+//! assert_eq!(words.len(), 23);
+//! assert_eq!(from_proquint(&words), Ok(hash));
+//!
+//! let slug = chibihash::encoding::to_base58(hash);
+//! assert_eq!(slug.len(), 11);
+//! ```
+
+use core::fmt;
+use core::ops::Deref;
+
+/// Text produced by one of the encoders, stored inline
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Encoded<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Encoded<N> {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    // Only ever called with ASCII bytes
+    pub(crate) fn push(&mut self, byte: u8) {
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+
+    /// Returns the encoded text
+    pub fn as_str(&self) -> &str {
+        // The encoders only push ASCII
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+impl<const N: usize> Deref for Encoded<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for Encoded<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Display for Encoded<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for Encoded<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for Encoded<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for Encoded<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Error returned when decoding text back into a digest fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input has the wrong number of characters or groups
+    InvalidLength,
+    /// The input contains a character outside the encoding's alphabet
+    InvalidCharacter(char),
+    /// The input encodes a value that does not fit in 64 bits
+    Overflow,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength => f.write_str("invalid encoded digest length"),
+            DecodeError::InvalidCharacter(c) => {
+                write!(f, "invalid character {:?} in encoded digest", c)
+            }
+            DecodeError::Overflow => f.write_str("encoded digest does not fit in 64 bits"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+const PROQUINT_CONSONANTS: &[u8; 16] = b"bdfghjklmnprstvz";
+const PROQUINT_VOWELS: &[u8; 4] = b"aiou";
+
+/// Encodes `hash` as four proquint words separated by `-`
+///
+/// Each word encodes 16 bits, most significant word first.
+pub fn to_proquint(hash: u64) -> Encoded<23> {
+    let mut out = Encoded::empty();
+    for word in 0..4 {
+        if word > 0 {
+            out.push(b'-');
+        }
+        let bits = (hash >> (48 - word * 16)) as u16;
+        out.push(PROQUINT_CONSONANTS[(bits >> 12) as usize & 0xF]);
+        out.push(PROQUINT_VOWELS[(bits >> 10) as usize & 0x3]);
+        out.push(PROQUINT_CONSONANTS[(bits >> 6) as usize & 0xF]);
+        out.push(PROQUINT_VOWELS[(bits >> 4) as usize & 0x3]);
+        out.push(PROQUINT_CONSONANTS[bits as usize & 0xF]);
+    }
+    out
+}
+
+/// Decodes four dash-separated proquint words back into a digest
+///
+/// Letters are accepted in either case.
+pub fn from_proquint(s: &str) -> Result<u64, DecodeError> {
+    let mut hash = 0u64;
+    let mut words = 0;
+    for word in s.split('-') {
+        if word.len() != 5 || words == 4 {
+            return Err(DecodeError::InvalidLength);
+        }
+        for (i, c) in word.chars().enumerate() {
+            let lower = c.to_ascii_lowercase() as u8;
+            let (alphabet, bits): (&[u8], u32) = if i % 2 == 0 {
+                (PROQUINT_CONSONANTS, 4)
+            } else {
+                (PROQUINT_VOWELS, 2)
+            };
+            let value = alphabet
+                .iter()
+                .position(|&a| c.is_ascii() && a == lower)
+                .ok_or(DecodeError::InvalidCharacter(c))?;
+            hash = (hash << bits) | value as u64;
+        }
+        words += 1;
+    }
+    if words != 4 {
+        return Err(DecodeError::InvalidLength);
+    }
+    Ok(hash)
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes `hash` as exactly 11 base58 characters
+///
+/// Short values are left-padded with `1`, the base58 zero digit.
+pub fn to_base58(hash: u64) -> Encoded<11> {
+    encode_base58(u128::from(hash))
+}
+
+/// Decodes base58 text of at most 11 characters back into a digest
+pub fn from_base58(s: &str) -> Result<u64, DecodeError> {
+    let hash = decode_base58(s, 11)?;
+    u64::try_from(hash).map_err(|_| DecodeError::Overflow)
+}
+
+// `hash` as exactly `N` base58 digits, left-padded with `1`
+pub(crate) fn encode_base58<const N: usize>(hash: u128) -> Encoded<N> {
+    let mut digits = [BASE58_ALPHABET[0]; N];
+    let mut n = hash;
+    for digit in digits.iter_mut().rev() {
+        *digit = BASE58_ALPHABET[(n % 58) as usize];
+        n /= 58;
+    }
+    let mut out = Encoded::empty();
+    for digit in digits {
+        out.push(digit);
+    }
+    out
+}
+
+// Decodes 1 to `max_len` base58 digits
+pub(crate) fn decode_base58(s: &str, max_len: usize) -> Result<u128, DecodeError> {
+    if s.is_empty() || s.len() > max_len {
+        return Err(DecodeError::InvalidLength);
+    }
+    let mut hash = 0u128;
+    for c in s.chars() {
+        let value = BASE58_ALPHABET
+            .iter()
+            .position(|&a| c.is_ascii() && a == c as u8)
+            .ok_or(DecodeError::InvalidCharacter(c))?;
+        hash = hash
+            .checked_mul(58)
+            .and_then(|h| h.checked_add(value as u128))
+            .ok_or(DecodeError::Overflow)?;
+    }
+    Ok(hash)
+}
+
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Encodes `hash` as exactly 13 Crockford base32 characters
+pub fn to_crockford32(hash: u64) -> Encoded<13> {
+    encode_crockford32(u128::from(hash))
+}
+
+/// Decodes Crockford base32 text back into a digest
+///
+/// Decoding is case-insensitive, treats `I`/`L` as `1` and `O` as `0`, and
+/// ignores `-` separators, as the Crockford specification recommends.
+pub fn from_crockford32(s: &str) -> Result<u64, DecodeError> {
+    // At most 64 bits, so the value always fits
+    decode_crockford32(s, 64).map(|hash| hash as u64)
+}
+
+// `hash` as exactly `N` Crockford digits; the first carries whatever bits
+// are left over from the other 5-bit digits
+pub(crate) fn encode_crockford32<const N: usize>(hash: u128) -> Encoded<N> {
+    let mut out = Encoded::empty();
+    for i in (0..N).rev() {
+        out.push(CROCKFORD_ALPHABET[(hash >> (i * 5)) as usize & 0x1F]);
+    }
+    out
+}
+
+// Decodes Crockford digits of a value of at most `bits` bits
+pub(crate) fn decode_crockford32(s: &str, bits: u32) -> Result<u128, DecodeError> {
+    let mut hash = 0u128;
+    let mut digits = 0;
+    for c in s.chars() {
+        let value = match c.to_ascii_uppercase() {
+            '-' => continue,
+            'I' | 'L' => 1,
+            'O' => 0,
+            upper => CROCKFORD_ALPHABET
+                .iter()
+                .position(|&a| upper.is_ascii() && a == upper as u8)
+                .ok_or(DecodeError::InvalidCharacter(c))? as u128,
+        };
+        if hash >> (bits - 5) != 0 {
+            return Err(DecodeError::Overflow);
+        }
+        hash = (hash << 5) | value;
+        digits += 1;
+    }
+    if digits == 0 || digits > bits.div_ceil(5) {
+        return Err(DecodeError::InvalidLength);
+    }
+    Ok(hash)
+}
+
+// A power-of-two alphabet for the RFC 4648 style encodings
+pub(crate) struct Alphabet {
+    symbols: &'static [u8],
+    bits: u32,
+    case_insensitive: bool,
+}
+
+pub(crate) const HEX: Alphabet = Alphabet {
+    symbols: b"0123456789abcdef",
+    bits: 4,
+    case_insensitive: true,
+};
+
+pub(crate) const BASE64_URL: Alphabet = Alphabet {
+    symbols: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+    bits: 6,
+    case_insensitive: false,
+};
+
+pub(crate) const BASE32: Alphabet = Alphabet {
+    symbols: b"abcdefghijklmnopqrstuvwxyz234567",
+    bits: 5,
+    case_insensitive: true,
+};
+
+// Encodes `bytes` most significant bit first, without padding. `N` must be
+// the number of characters the input needs.
+pub(crate) fn encode_bytes<const N: usize>(bytes: &[u8], alphabet: &Alphabet) -> Encoded<N> {
+    let mask = (1u32 << alphabet.bits) - 1;
+    let mut out = Encoded::empty();
+    let (mut acc, mut n) = (0u32, 0u32);
+    for &byte in bytes {
+        acc = (acc << 8) | u32::from(byte);
+        n += 8;
+        while n >= alphabet.bits {
+            n -= alphabet.bits;
+            out.push(alphabet.symbols[((acc >> n) & mask) as usize]);
+        }
+    }
+    if n > 0 {
+        out.push(alphabet.symbols[((acc << (alphabet.bits - n)) & mask) as usize]);
+    }
+    out
+}
+
+// Inverse of `encode_bytes`. Padding bits in the last character must be
+// zero, so every value has exactly one encoding.
+pub(crate) fn decode_bytes<const M: usize>(
+    s: &str,
+    alphabet: &Alphabet,
+) -> Result<[u8; M], DecodeError> {
+    let bits = alphabet.bits as usize;
+    if s.len() != (M * 8).div_ceil(bits) {
+        return Err(DecodeError::InvalidLength);
+    }
+    let mut out = [0u8; M];
+    let (mut acc, mut n, mut pos) = (0u32, 0u32, 0);
+    let mut last = ' ';
+    for c in s.chars() {
+        let lookup = if alphabet.case_insensitive {
+            c.to_ascii_lowercase()
+        } else {
+            c
+        };
+        let value = alphabet
+            .symbols
+            .iter()
+            .position(|&a| lookup.is_ascii() && a == lookup as u8)
+            .ok_or(DecodeError::InvalidCharacter(c))?;
+        acc = (acc << alphabet.bits) | value as u32;
+        n += alphabet.bits;
+        if n >= 8 {
+            n -= 8;
+            out[pos] = (acc >> n) as u8;
+            pos += 1;
+        }
+        last = c;
+    }
+    if acc & ((1 << n) - 1) != 0 {
+        return Err(DecodeError::InvalidCharacter(last));
+    }
+    Ok(out)
+}
+
+/// Encodes `hash` as 16 lowercase hex digits
+pub fn to_hex(hash: u64) -> Encoded<16> {
+    encode_bytes(&hash.to_be_bytes(), &HEX)
+}
+
+/// Decodes exactly 16 hex digits, in either case, back into a digest
+pub fn from_hex(s: &str) -> Result<u64, DecodeError> {
+    decode_bytes(s, &HEX).map(u64::from_be_bytes)
+}
+
+/// Encodes `hash` as 11 characters of unpadded URL-safe base64
+pub fn to_base64(hash: u64) -> Encoded<11> {
+    encode_bytes(&hash.to_be_bytes(), &BASE64_URL)
+}
+
+/// Decodes 11 characters of unpadded URL-safe base64 back into a digest
+pub fn from_base64(s: &str) -> Result<u64, DecodeError> {
+    decode_bytes(s, &BASE64_URL).map(u64::from_be_bytes)
+}
+
+/// Encodes `hash` as 13 characters of unpadded lowercase base32
+pub fn to_base32(hash: u64) -> Encoded<13> {
+    encode_bytes(&hash.to_be_bytes(), &BASE32)
+}
+
+/// Decodes 13 characters of unpadded base32, in either case, back into a
+/// digest
+pub fn from_base32(s: &str) -> Result<u64, DecodeError> {
+    decode_bytes(s, &BASE32).map(u64::from_be_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proquint_known_values() {
+        // Examples from the proquint spec, 127.0.0.1 and 63.84.220.193
+        assert_eq!(
+            to_proquint(0x7F00_0001_3F54_DCC1),
+            "lusab-babad-gutih-tugad"
+        );
+        assert_eq!(to_proquint(0), "babab-babab-babab-babab");
+        assert_eq!(to_proquint(u64::MAX), "zuzuz-zuzuz-zuzuz-zuzuz");
+    }
+
+    #[test]
+    fn test_proquint_roundtrip() {
+        for seed in 0..64 {
+            let hash = crate::v2::chibi_hash64(b"roundtrip", seed);
+            assert_eq!(from_proquint(&to_proquint(hash)), Ok(hash));
+        }
+        assert_eq!(
+            from_proquint("LUSAB-babad-gutih-tugad"),
+            Ok(0x7F00_0001_3F54_DCC1)
+        );
+    }
+
+    #[test]
+    fn test_proquint_errors() {
+        assert_eq!(from_proquint(""), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            from_proquint("lusab-babad-gutih"),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            from_proquint("lusab-babad-gutih-tugad-babab"),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            from_proquint("lusab-babad-gutih-tugae"),
+            Err(DecodeError::InvalidCharacter('e'))
+        );
+        assert_eq!(
+            from_proquint("lusab-babad-gutih-tugäd"),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_base58_known_values() {
+        assert_eq!(to_base58(0), "11111111111");
+        assert_eq!(to_base58(57), "1111111111z");
+        assert_eq!(to_base58(58), "11111111121");
+        assert_eq!(to_base58(u64::MAX), "jpXCZedGfVQ");
+    }
+
+    #[test]
+    fn test_base58_roundtrip() {
+        for seed in 0..64 {
+            let hash = crate::v2::chibi_hash64(b"roundtrip", seed);
+            assert_eq!(from_base58(&to_base58(hash)), Ok(hash));
+        }
+        assert_eq!(from_base58("z"), Ok(57));
+    }
+
+    #[test]
+    fn test_base58_errors() {
+        assert_eq!(from_base58(""), Err(DecodeError::InvalidLength));
+        assert_eq!(from_base58("111111111111"), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            from_base58("1111111111O"),
+            Err(DecodeError::InvalidCharacter('O'))
+        );
+        assert_eq!(from_base58("zzzzzzzzzzz"), Err(DecodeError::Overflow));
+    }
+
+    #[test]
+    fn test_crockford32_known_values() {
+        assert_eq!(to_crockford32(0), "0000000000000");
+        assert_eq!(to_crockford32(31), "000000000000Z");
+        assert_eq!(to_crockford32(u64::MAX), "FZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_crockford32_roundtrip() {
+        for seed in 0..64 {
+            let hash = crate::v2::chibi_hash64(b"roundtrip", seed);
+            let encoded = to_crockford32(hash);
+            assert_eq!(from_crockford32(&encoded), Ok(hash));
+            assert_eq!(from_crockford32(&encoded.to_ascii_lowercase()), Ok(hash));
+        }
+        assert_eq!(from_crockford32("o-I-l"), Ok(0b00000_00001_00001));
+    }
+
+    #[test]
+    fn test_crockford32_sorts_numerically() {
+        let (a, b) = (0x0FFF_FFFF_FFFF_FFFF, 0x1000_0000_0000_0000);
+        assert!(to_crockford32(a).as_str() < to_crockford32(b).as_str());
+    }
+
+    #[test]
+    fn test_crockford32_errors() {
+        assert_eq!(from_crockford32(""), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            from_crockford32("U"),
+            Err(DecodeError::InvalidCharacter('U'))
+        );
+        assert_eq!(
+            from_crockford32("G000000000000"),
+            Err(DecodeError::Overflow)
+        );
+        assert_eq!(
+            from_crockford32("00000000000000"),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_rfc4648_known_values() {
+        // RFC 4648 test vector "foobar" padded to 8 bytes with two zeros
+        let hash = u64::from_be_bytes(*b"foobar\0\0");
+        assert_eq!(to_hex(hash), "666f6f6261720000");
+        assert_eq!(to_base64(hash), "Zm9vYmFyAAA");
+        assert_eq!(to_base32(hash), "mzxw6ytboiaaa");
+        assert_eq!(to_base64(u64::MAX), "__________8");
+    }
+
+    #[test]
+    fn test_rfc4648_roundtrip() {
+        for seed in 0..64 {
+            let hash = crate::v2::chibi_hash64(b"roundtrip", seed);
+            assert_eq!(from_hex(&to_hex(hash)), Ok(hash));
+            assert_eq!(from_hex(&to_hex(hash).to_ascii_uppercase()), Ok(hash));
+            assert_eq!(from_base64(&to_base64(hash)), Ok(hash));
+            assert_eq!(from_base32(&to_base32(hash)), Ok(hash));
+            assert_eq!(from_base32(&to_base32(hash).to_ascii_uppercase()), Ok(hash));
+        }
+    }
+
+    #[test]
+    fn test_rfc4648_errors() {
+        assert_eq!(from_hex("abc"), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            from_hex("000000000000000g"),
+            Err(DecodeError::InvalidCharacter('g'))
+        );
+        assert_eq!(from_base64("AAAAAAAAAA"), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            from_base64("AAAAAAAAAA+"),
+            Err(DecodeError::InvalidCharacter('+'))
+        );
+        // The last character carries two padding bits, which must be zero
+        assert_eq!(
+            from_base64("AAAAAAAAAAB"),
+            Err(DecodeError::InvalidCharacter('B'))
+        );
+        assert_eq!(
+            from_base32("aaaaaaaaaaaab"),
+            Err(DecodeError::InvalidCharacter('b'))
+        );
+    }
+}
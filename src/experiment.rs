@@ -0,0 +1,108 @@
+//! Deterministic assignment for experiments and feature flags
+//!
+//! [`bucket`] splits units (users, devices, sessions) evenly into the
+//! variants of an experiment, and [`in_rollout`] admits a given percentage
+//! of them to a feature. Both depend only on their arguments, so every
+//! server, client and batch job computes the same assignment without
+//! coordination.
+//!
+//! Each experiment name hashes into its own domain: a unit's bucket in one
+//! experiment says nothing about its bucket in another. Bucketing and
+//! rollout use separate domains as well, so the units in a partial rollout
+//! are spread evenly over the variants of the same experiment. All hashes
+//! use `v2`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::experiment::{bucket, in_rollout};
+//!
+//! let user = "user-8812";
+//! if in_rollout(user, "new-checkout", 20.0) {
+//!     let variants = ["control", "one-page", "express"];
+//!     let variant = variants[bucket(user, "new-checkout", 3) as usize];
+//!     println!("{} sees {}", user, variant);
+//! }
+//! ```
+
+use crate::range::{bounded, unit_interval};
+use crate::v2::{chibi_hash64, domain_seed};
+
+const BUCKET_DOMAIN: &[u8] = b"chibihash-experiment-bucket";
+const ROLLOUT_DOMAIN: &[u8] = b"chibihash-experiment-rollout";
+
+/// Returns the bucket of `unit_id` in `experiment`, in `0..num_buckets`
+///
+/// # Panics
+///
+/// Panics if `num_buckets` is zero.
+pub fn bucket<U, E>(unit_id: U, experiment: E, num_buckets: u32) -> u32
+where
+    U: AsRef<[u8]>,
+    E: AsRef<[u8]>,
+{
+    let hash = unit_hash(BUCKET_DOMAIN, unit_id.as_ref(), experiment.as_ref());
+    bounded(hash, u64::from(num_buckets)) as u32
+}
+
+/// Returns `true` if `unit_id` is among the `percent` percent of units
+/// admitted to `experiment`
+///
+/// Raising `percent` only ever adds units: a unit admitted at 10% stays
+/// admitted at 20%. Values of 0 or less and NaN admit no one, and values of
+/// 100 or more admit everyone.
+pub fn in_rollout<U, E>(unit_id: U, experiment: E, percent: f64) -> bool
+where
+    U: AsRef<[u8]>,
+    E: AsRef<[u8]>,
+{
+    let hash = unit_hash(ROLLOUT_DOMAIN, unit_id.as_ref(), experiment.as_ref());
+    unit_interval(hash) * 100.0 < percent
+}
+
+fn unit_hash(domain: &[u8], unit_id: &[u8], experiment: &[u8]) -> u64 {
+    chibi_hash64(unit_id, domain_seed(experiment, domain_seed(domain, 0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let mut counts = [0u32; 4];
+        for i in 0..8000u32 {
+            let unit = i.to_le_bytes();
+            let b = bucket(unit, "exp", 4);
+            assert_eq!(b, bucket(&unit[..], b"exp", 4));
+            counts[b as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| (1800..2200).contains(&c)));
+
+        // Other experiments are independent
+        let same = (0..1000u32)
+            .filter(|i| bucket(i.to_le_bytes(), "a", 2) == bucket(i.to_le_bytes(), "b", 2))
+            .count();
+        assert!((400..600).contains(&same));
+    }
+
+    #[test]
+    fn test_in_rollout() {
+        let admitted = |percent: f64| {
+            (0..10_000u32).filter(move |i| in_rollout(i.to_le_bytes(), "flag", percent))
+        };
+        assert_eq!(admitted(0.0).count(), 0);
+        assert_eq!(admitted(f64::NAN).count(), 0);
+        assert_eq!(admitted(100.0).count(), 10_000);
+        assert_eq!(admitted(150.0).count(), 10_000);
+        let ten = admitted(10.0).count();
+        assert!((900..1100).contains(&ten));
+        assert!(admitted(10.0).all(|i| in_rollout(i.to_le_bytes(), "flag", 20.0)));
+
+        // Rolled-out units are spread over the buckets
+        let in_bucket_0 = admitted(10.0)
+            .filter(|i| bucket(i.to_le_bytes(), "flag", 2) == 0)
+            .count();
+        assert!(in_bucket_0 * 10 > ten * 4 && in_bucket_0 * 10 < ten * 6);
+    }
+}
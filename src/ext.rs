@@ -0,0 +1,182 @@
+//! Extension traits for hashing without naming a hash function
+//!
+//! - [`ChibiHashExt`] adds `.chibi_hash(seed)` to every byte-like type, i.e.
+//!   anything implementing `AsRef<[u8]>`: `str`, `String`, `[u8]`,
+//!   `Vec<u8>`, `Cow<[u8]>`, `bytes::Bytes` and so on.
+//! - [`IteratorHashExt`] hashes all items of an iterator into one digest,
+//!   e.g. the fields of a record, without concatenating them first.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::ChibiHashExt;
+//!
+//! assert_eq!("key".chibi_hash(0), chibihash::chibi_hash64(b"key", 0));
+//! assert_eq!(
+//!     String::from("key").chibi_hash_v2(0),
+//!     chibihash::v2::chibi_hash64(b"key", 0)
+//! );
+//! ```
+
+use core::hash::{Hash, Hasher};
+
+/// Hashes byte-like values directly
+pub trait ChibiHashExt {
+    /// Hashes the bytes with the crate-root
+    /// [`chibi_hash64`](crate::chibi_hash64): `v1`, or `v2` with the
+    /// `default-v2` feature
+    fn chibi_hash(&self, seed: u64) -> u64;
+
+    /// Hashes the bytes with `v2`
+    fn chibi_hash_v2(&self, seed: u64) -> u64;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> ChibiHashExt for T {
+    fn chibi_hash(&self, seed: u64) -> u64 {
+        crate::chibi_hash64(self.as_ref(), seed)
+    }
+
+    fn chibi_hash_v2(&self, seed: u64) -> u64 {
+        crate::v2::chibi_hash64(self.as_ref(), seed)
+    }
+}
+
+/// Hashes the items of an iterator, in order, into one digest
+///
+/// Both methods feed every item through a single streaming hasher of the
+/// crate-root version: `v1`, or `v2` with the `default-v2` feature. Item
+/// boundaries are part of the input, so `["ab", "c"]` and
+/// `["a", "bc"]` hash differently, as do different orders of the same
+/// items.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::IteratorHashExt;
+///
+/// let fields = ["alice", "admin", "2024-01-01"];
+/// let a = fields.iter().hash_byte_items(0);
+/// let b = ["alice", "admin", "2024-01-01"].into_iter().hash_byte_items(0);
+/// assert_eq!(a, b);
+/// assert_ne!(a, ["alice", "admin2024-01-01"].iter().hash_byte_items(0));
+///
+/// let ids = vec![3u32, 1, 2];
+/// assert_ne!(ids.iter().hash_items(0), [1u32, 2, 3].iter().hash_items(0));
+/// ```
+pub trait IteratorHashExt: Iterator + Sized {
+    /// Hashes each item with its [`Hash`] impl
+    ///
+    /// Equal to calling `item.hash(&mut hasher)` for every item on one
+    /// [`ChibiHasher`](crate::ChibiHasher), so boundaries are whatever the
+    /// items' `Hash` impls encode, e.g. the `0xFF` after each `str`.
+    fn hash_items(self, seed: u64) -> u64
+    where
+        Self::Item: Hash,
+    {
+        let mut hasher = crate::ChibiHasher::new(seed);
+        for item in self {
+            item.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Hashes the bytes of each item, prefixed with its length
+    ///
+    /// Each item is written as its length as a little-endian `u64`
+    /// followed by its bytes, so results are the same on every platform.
+    fn hash_byte_items(self, seed: u64) -> u64
+    where
+        Self::Item: AsRef<[u8]>,
+    {
+        let mut hasher = crate::StreamingChibiHasher::new(seed);
+        for item in self {
+            let bytes = item.as_ref();
+            hasher.update(&(bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        }
+        hasher.finalize()
+    }
+}
+
+impl<I: Iterator> IteratorHashExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    extern crate alloc;
+    #[cfg(not(feature = "std"))]
+    use alloc::{borrow::Cow, string::String, vec::Vec};
+    #[cfg(feature = "std")]
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_byte_like_types() {
+        let expected = crate::chibi_hash64(b"key", 3);
+        assert_eq!("key".chibi_hash(3), expected);
+        assert_eq!(String::from("key").chibi_hash(3), expected);
+        assert_eq!(b"key".chibi_hash(3), expected);
+        assert_eq!(b"key"[..].chibi_hash(3), expected);
+        assert_eq!(Vec::from(&b"key"[..]).chibi_hash(3), expected);
+        assert_eq!(Cow::Borrowed(&b"key"[..]).chibi_hash(3), expected);
+
+        assert_eq!("key".chibi_hash_v2(3), crate::v2::chibi_hash64(b"key", 3));
+    }
+
+    #[test]
+    fn test_hash_items() {
+        let mut hasher = crate::ChibiHasher::new(1);
+        for item in ["a", "b"] {
+            item.hash(&mut hasher);
+        }
+        assert_eq!(["a", "b"].iter().hash_items(1), hasher.finish());
+        assert_ne!(
+            ["ab", "c"].iter().hash_items(1),
+            ["a", "bc"].iter().hash_items(1)
+        );
+    }
+
+    #[test]
+    fn test_hash_byte_items() {
+        let mut expected = Vec::new();
+        for item in [&b"ab"[..], b"c"] {
+            expected.extend_from_slice(&(item.len() as u64).to_le_bytes());
+            expected.extend_from_slice(item);
+        }
+        let items = [&b"ab"[..], b"c"];
+        assert_eq!(
+            items.iter().hash_byte_items(2),
+            crate::chibi_hash64(&expected, 2)
+        );
+        assert_ne!(
+            items.iter().hash_byte_items(2),
+            [&b"a"[..], b"bc"].iter().hash_byte_items(2)
+        );
+        assert_ne!(
+            core::iter::empty::<&[u8]>().hash_byte_items(2),
+            [&b""[..]].iter().hash_byte_items(2)
+        );
+    }
+
+    #[test]
+    fn test_follows_crate_default() {
+        #[cfg(not(feature = "default-v2"))]
+        use crate::v1 as default;
+        #[cfg(feature = "default-v2")]
+        use crate::v2 as default;
+
+        assert_eq!("x".chibi_hash(4), default::chibi_hash64(b"x", 4));
+
+        let mut hasher = default::ChibiHasher::new(4);
+        7u32.hash(&mut hasher);
+        assert_eq!([7u32].iter().hash_items(4), hasher.finish());
+
+        let mut expected = 1u64.to_le_bytes().to_vec();
+        expected.push(b'x');
+        assert_eq!(
+            ["x"].iter().hash_byte_items(4),
+            default::chibi_hash64(&expected, 4)
+        );
+    }
+}
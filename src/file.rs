@@ -0,0 +1,212 @@
+//! Hashing files on disk
+//!
+//! - [`hash_file_mmap`]: maps the file into memory and hashes it in one
+//!   pass, without copying it through `read` calls.
+//! - [`hash_file_parallel`] (feature `rayon`): maps the file and hashes it
+//!   in [tree mode](crate::tree), with chunks spread over a thread pool.
+//!
+//! `hash_file_mmap` returns `v2::chibi_hash64` of the file contents and
+//! `hash_file_parallel` returns `tree::chibi_hash64_tree` of them, so neither
+//! value depends on how the file was read.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::v2::{chibi_hash64, StreamingChibiHasher};
+
+/// Hashes the file at `path` through a memory map
+///
+/// Returns `v2::chibi_hash64(contents, seed)`. Files that cannot be mapped,
+/// such as pipes or files on some network file systems, are read and hashed
+/// in a stream instead, with the same result.
+///
+/// The file must not be modified while it is hashed: the result is then
+/// unspecified, and on some platforms truncating a mapped file terminates
+/// the process with `SIGBUS`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use chibihash::file::hash_file_mmap;
+///
+/// let hash = hash_file_mmap("video.mp4", 0)?;
+/// println!("{:016x}", hash);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_file_mmap<P: AsRef<Path>>(path: P, seed: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    // SAFETY: the map is only read while `file` is open, and modifying the
+    // file meanwhile is documented as unsupported above
+    match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(map) => Ok(chibi_hash64(&map, seed)),
+        Err(_) => hash_stream(&mut file, seed),
+    }
+}
+
+/// Options for [`hash_file_parallel`]
+///
+/// ```rust
+/// use chibihash::file::ParallelOptions;
+///
+/// let opts = ParallelOptions {
+///     chunk_size: 4 << 20,
+///     ..ParallelOptions::default()
+/// };
+/// ```
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelOptions {
+    /// Tree mode chunk size in bytes; part of the hash value. Defaults to
+    /// [`DEFAULT_CHUNK_SIZE`].
+    pub chunk_size: usize,
+    /// Number of threads, or `None` for the global rayon pool
+    pub threads: Option<usize>,
+}
+
+/// Default [`ParallelOptions::chunk_size`], 1 MiB
+#[cfg(feature = "rayon")]
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+#[cfg(feature = "rayon")]
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            threads: None,
+        }
+    }
+}
+
+/// Hashes the file at `path` in tree mode, with chunks hashed in parallel
+///
+/// Returns `tree::chibi_hash64_tree(contents, seed, opts.chunk_size)`, which
+/// is not the sequential `chibi_hash64`; see the [`tree`](crate::tree)
+/// module. The file is mapped into memory, or read into memory when it
+/// cannot be mapped, and must not be modified while it is hashed, as with
+/// [`hash_file_mmap`].
+///
+/// # Panics
+///
+/// Panics if `opts.chunk_size` is zero.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use chibihash::file::{hash_file_parallel, ParallelOptions};
+///
+/// let opts = ParallelOptions {
+///     threads: Some(8),
+///     ..ParallelOptions::default()
+/// };
+/// let hash = hash_file_parallel("video.mp4", 0, opts)?;
+/// println!("{:016x}", hash);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "rayon")]
+pub fn hash_file_parallel<P: AsRef<Path>>(
+    path: P,
+    seed: u64,
+    opts: ParallelOptions,
+) -> io::Result<u64> {
+    use std::io::Read;
+
+    use crate::tree::chibi_hash64_parallel;
+
+    let mut file = File::open(path)?;
+    // SAFETY: as in `hash_file_mmap`
+    let map = unsafe { memmap2::Mmap::map(&file) };
+    let mut buf = Vec::new();
+    let data: &[u8] = match &map {
+        Ok(map) => map,
+        Err(_) => {
+            file.read_to_end(&mut buf)?;
+            &buf
+        }
+    };
+
+    match opts.threads {
+        None => Ok(chibi_hash64_parallel(data, seed, opts.chunk_size)),
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(io::Error::other)?;
+            Ok(pool.install(|| chibi_hash64_parallel(data, seed, opts.chunk_size)))
+        }
+    }
+}
+
+// Streaming fallback, equal to hashing the contents in one call
+fn hash_stream(file: &mut File, seed: u64) -> io::Result<u64> {
+    let mut hasher = StreamingChibiHasher::new(seed);
+    io::copy(file, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    // A file under the temp directory, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("chibihash-{}-{}", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_hash_stream() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7) as u8).collect();
+        let file = TempFile::new("stream", &data);
+        let mut f = File::open(&file.0).unwrap();
+        assert_eq!(hash_stream(&mut f, 5).unwrap(), chibi_hash64(&data, 5));
+    }
+
+    #[test]
+    fn test_hash_file_mmap() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7) as u8).collect();
+        let file = TempFile::new("mmap", &data);
+        assert_eq!(hash_file_mmap(&file.0, 5).unwrap(), chibi_hash64(&data, 5));
+
+        let empty = TempFile::new("mmap-empty", b"");
+        assert_eq!(hash_file_mmap(&empty.0, 5).unwrap(), chibi_hash64(b"", 5));
+
+        assert!(hash_file_mmap(std::env::temp_dir().join("chibihash-missing"), 0).is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_hash_file_parallel() {
+        use crate::tree::chibi_hash64_tree;
+
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7) as u8).collect();
+        let file = TempFile::new("parallel", &data);
+        assert_eq!(
+            hash_file_parallel(&file.0, 5, ParallelOptions::default()).unwrap(),
+            chibi_hash64_tree(&data, 5, DEFAULT_CHUNK_SIZE)
+        );
+
+        let opts = ParallelOptions {
+            chunk_size: 4096,
+            threads: Some(2),
+        };
+        assert_eq!(
+            hash_file_parallel(&file.0, 5, opts).unwrap(),
+            chibi_hash64_tree(&data, 5, 4096)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::f64::consts::LN_2;
+use core::hash::Hash;
+
+use crate::math::ln;
+use crate::mix::{moremur, reduce};
+use crate::v2::hash_one;
+
+/// A Bloom filter with `k` probes derived from one ChibiHash
+///
+/// Probe `i` of an item with hash `h` is bit `g(i) = h + i * h'` reduced to
+/// the filter size, where `h'` is `h` remixed (Kirsch-Mitzenmacher double
+/// hashing), so an item is hashed once however many probes are used.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::filters::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_capacity_and_fpr(1000, 0.01);
+/// filter.insert("alice");
+/// assert!(filter.contains("alice"));
+/// assert!(!filter.contains("bob")); // or, with probability ~1%, true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    seed: u64,
+}
+
+impl BloomFilter {
+    /// Creates an empty filter of `num_bits` bits probed `num_hashes` times
+    /// per item, with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` or `num_hashes` is zero.
+    pub fn new(num_bits: usize, num_hashes: u32) -> Self {
+        assert!(num_bits > 0, "number of bits must be non-zero");
+        assert!(num_hashes > 0, "number of hashes must be non-zero");
+        Self {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_bits: num_bits as u64,
+            num_hashes,
+            seed: 0,
+        }
+    }
+
+    /// Creates an empty filter sized to keep the false-positive rate near
+    /// `fpr` for up to `capacity` items
+    ///
+    /// Uses the optimal `m = -n ln(p) / ln(2)^2` bits and `k = m/n ln(2)`
+    /// probes.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < fpr < 1`, or if the filter would need more than
+    /// `usize::MAX` bits.
+    pub fn with_capacity_and_fpr(capacity: usize, fpr: f64) -> Self {
+        let (num_bits, num_hashes) = optimal_params(capacity, fpr);
+        Self::new(num_bits, num_hashes)
+    }
+
+    /// Returns the filter emptied and hashing with `seed`
+    ///
+    /// A secret seed keeps others from predicting which items collide.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Adds `item`
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.insert_hash(hash_one(item, self.seed));
+    }
+
+    /// Returns `false` if `item` was definitely never inserted
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.contains_hash(hash_one(item, self.seed))
+    }
+
+    /// Adds an item by its hash
+    ///
+    /// `hash` stands for the item: any well-mixed 64-bit hash works, but
+    /// lookups must use the same one, and [`insert`](Self::insert) and
+    /// [`contains`](Self::contains) use `v2::hash_one(item, seed)`.
+    pub fn insert_hash(&mut self, hash: u64) {
+        for bit in self.probes(hash) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns `false` if no item with `hash` was inserted
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.probes(hash)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Removes every item
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    /// Returns `true` if nothing was inserted since creation or the last
+    /// [`clear`](Self::clear)
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&word| word == 0)
+    }
+
+    /// Returns the number of bits
+    pub fn num_bits(&self) -> usize {
+        self.num_bits as usize
+    }
+
+    /// Returns the number of probes per item
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn probes(&self, hash: u64) -> impl Iterator<Item = u64> {
+        let num_bits = self.num_bits;
+        let step = moremur(hash) | 1;
+        (0..u64::from(self.num_hashes))
+            .map(move |i| reduce(hash.wrapping_add(i.wrapping_mul(step)), num_bits))
+    }
+}
+
+// Bits and probes for `capacity` items at false-positive rate `fpr`
+fn optimal_params(capacity: usize, fpr: f64) -> (usize, u32) {
+    assert!(
+        fpr > 0.0 && fpr < 1.0,
+        "false-positive rate must be between 0 and 1"
+    );
+    let n = capacity.max(1) as f64;
+    let bits = -n * ln(fpr) / (LN_2 * LN_2);
+    assert!(
+        bits < usize::MAX as f64,
+        "{} items at false-positive rate {} need more than usize::MAX bits",
+        capacity,
+        fpr
+    );
+    let num_bits = (bits as usize).max(1).saturating_add(1);
+    let num_hashes = (num_bits as f64 / n * LN_2 + 0.5) as u32;
+    (num_bits, num_hashes.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimal_params() {
+        assert_eq!(optimal_params(1000, 0.01), (9586, 7));
+        assert_eq!(optimal_params(0, 0.5), (2, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "need more than usize::MAX bits")]
+    fn test_optimal_params_too_large() {
+        optimal_params(usize::MAX, 1e-300);
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = BloomFilter::with_capacity_and_fpr(1000, 0.01).with_seed(3);
+        assert!(filter.is_empty());
+        for i in 0..1000u32 {
+            filter.insert(&i);
+        }
+        assert!((0..1000u32).all(|i| filter.contains(&i)));
+
+        let false_positives = (1000..11_000u32).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+
+        filter.clear();
+        assert!(!filter.contains(&1u32));
+    }
+
+    #[test]
+    fn test_bloom_filter_hash() {
+        let mut filter = BloomFilter::new(64, 3);
+        filter.insert_hash(0xDEAD_BEEF);
+        assert!(filter.contains_hash(0xDEAD_BEEF));
+        assert!(filter.contains(&"x") == filter.contains_hash(hash_one("x", 0)));
+        assert_eq!(filter.bits[0].count_ones(), 3);
+    }
+}
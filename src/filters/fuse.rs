@@ -0,0 +1,457 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::fmt;
+use core::hash::Hash;
+use core::ops::BitXor;
+
+use crate::math::ln;
+use crate::mix::moremur;
+use crate::v2::hash_one;
+
+const FORMAT_VERSION: u8 = 1;
+
+// Version, fingerprint width, seed, segment length and segment count
+const HEADER_LEN: usize = 18;
+
+// Construction fails with probability well below 1% per attempt
+const MAX_ATTEMPTS: u32 = 100;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+}
+
+/// Fingerprint type of a [`FuseFilter`], `u8` or `u16`; sealed
+pub trait Fingerprint:
+    sealed::Sealed + Copy + Default + Eq + BitXor<Output = Self> + fmt::Debug
+{
+    /// Size in bytes
+    const BYTES: usize;
+
+    #[doc(hidden)]
+    fn from_hash(hash: u64) -> Self;
+
+    #[doc(hidden)]
+    fn write_le(self, out: &mut Vec<u8>);
+
+    #[doc(hidden)]
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl Fingerprint for u8 {
+    const BYTES: usize = 1;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u8
+    }
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.push(self);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl Fingerprint for u16 {
+    const BYTES: usize = 2;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u16
+    }
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        u16::from_le_bytes([bytes[0], bytes[1]])
+    }
+}
+
+/// A binary fuse filter with 8-bit fingerprints, false-positive rate ~0.4%
+pub type FuseFilter8 = FuseFilter<u8>;
+
+/// A binary fuse filter with 16-bit fingerprints, false-positive rate
+/// ~0.0015%
+pub type FuseFilter16 = FuseFilter<u16>;
+
+/// A static binary fuse filter (Graf and Lemire, "Binary Fuse Filters")
+///
+/// Built once from a fixed set of keys, it takes about 1.13 fingerprints
+/// per key for large sets and somewhat more for small ones, much less than
+/// a Bloom filter with the same false-positive rate, and answers a lookup
+/// with three memory reads.
+/// Every key is hashed with `v2::hash_one(key, seed)`; if construction
+/// fails for a seed, it is retried with a new one.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::filters::FuseFilter8;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filter = FuseFilter8::build(&keys).unwrap();
+/// assert!(filter.contains(&42u64));
+///
+/// let restored = FuseFilter8::from_bytes(&filter.to_bytes()).unwrap();
+/// assert_eq!(restored, filter);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuseFilter<F: Fingerprint> {
+    seed: u64,
+    segment_length: u32,
+    segment_count: u32,
+    fingerprints: Vec<F>,
+}
+
+/// Error returned when building a [`FuseFilter`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuseBuildError {
+    /// More than `u32::MAX` distinct keys
+    TooManyKeys,
+    /// No seed worked within the retry limit, which in practice does not
+    /// happen with distinct keys
+    TooManyAttempts,
+}
+
+impl fmt::Display for FuseBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuseBuildError::TooManyKeys => f.write_str("too many keys for a fuse filter"),
+            FuseBuildError::TooManyAttempts => {
+                f.write_str("fuse filter construction failed for every seed")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FuseBuildError {}
+
+/// Error returned when decoding an encoded [`FuseFilter`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterDecodeError {
+    /// The input is too short or its length does not match the header
+    InvalidLength,
+    /// The input uses a format version this crate does not know
+    UnsupportedFormat(u8),
+    /// The input has fingerprints of a different width
+    WrongFingerprint {
+        /// Fingerprint bytes of the filter being restored
+        expected: u8,
+        /// Fingerprint bytes recorded in the input
+        found: u8,
+    },
+    /// The segment layout is invalid
+    Inconsistent,
+}
+
+impl fmt::Display for FilterDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterDecodeError::InvalidLength => f.write_str("invalid encoded filter length"),
+            FilterDecodeError::UnsupportedFormat(version) => {
+                write!(f, "unsupported filter format version {}", version)
+            }
+            FilterDecodeError::WrongFingerprint { expected, found } => write!(
+                f,
+                "filter has {}-byte fingerprints, expected {}",
+                found, expected
+            ),
+            FilterDecodeError::Inconsistent => f.write_str("inconsistent encoded filter"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FilterDecodeError {}
+
+impl<F: Fingerprint> FuseFilter<F> {
+    /// Builds a filter containing `keys`, starting from seed 0
+    ///
+    /// Duplicate keys are allowed.
+    pub fn build<T: Hash>(keys: &[T]) -> Result<Self, FuseBuildError> {
+        Self::build_with_seed(keys, 0)
+    }
+
+    /// Builds a filter containing `keys`, starting from `seed`
+    ///
+    /// The filter may end up with another seed if construction has to be
+    /// retried; see [`seed`](Self::seed).
+    pub fn build_with_seed<T: Hash>(keys: &[T], seed: u64) -> Result<Self, FuseBuildError> {
+        let mut seed = seed;
+        let mut hashes = Vec::with_capacity(keys.len());
+        for _ in 0..MAX_ATTEMPTS {
+            hashes.clear();
+            hashes.extend(keys.iter().map(|key| hash_one(key, seed)));
+            // Equal keys have equal hashes and would never peel
+            hashes.sort_unstable();
+            hashes.dedup();
+            if u32::try_from(hashes.len()).is_err() {
+                return Err(FuseBuildError::TooManyKeys);
+            }
+
+            let (segment_length, segment_count) = layout(hashes.len());
+            let mut filter = Self {
+                seed,
+                segment_length,
+                segment_count,
+                fingerprints: vec![
+                    F::default();
+                    (segment_count as usize + 2) * segment_length as usize
+                ],
+            };
+            if filter.populate(&hashes) {
+                return Ok(filter);
+            }
+            seed = moremur(seed.wrapping_add(0x9E3779B97F4A7C15));
+        }
+        Err(FuseBuildError::TooManyAttempts)
+    }
+
+    /// Returns `false` if `item` is definitely not one of the keys
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let hash = hash_one(item, self.seed);
+        let [h0, h1, h2] = self.positions(hash);
+        F::from_hash(hash) ^ self.fingerprints[h0] ^ self.fingerprints[h1] ^ self.fingerprints[h2]
+            == F::default()
+    }
+
+    /// Returns the seed the keys were hashed with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the size of the fingerprint table in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        self.fingerprints.len() * F::BYTES
+    }
+
+    /// Encodes the filter for storage or transfer
+    ///
+    /// The layout, with integers little-endian, is a format version byte
+    /// (1), the fingerprint width in bytes, the seed as `u64`, the segment
+    /// length and segment count as `u32`, and the fingerprints.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.size_in_bytes());
+        out.push(FORMAT_VERSION);
+        out.push(F::BYTES as u8);
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.extend_from_slice(&self.segment_length.to_le_bytes());
+        out.extend_from_slice(&self.segment_count.to_le_bytes());
+        for &fingerprint in &self.fingerprints {
+            fingerprint.write_le(&mut out);
+        }
+        out
+    }
+
+    /// Restores a filter encoded by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FilterDecodeError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(FilterDecodeError::InvalidLength);
+        }
+        if bytes[0] != FORMAT_VERSION {
+            return Err(FilterDecodeError::UnsupportedFormat(bytes[0]));
+        }
+        if bytes[1] as usize != F::BYTES {
+            return Err(FilterDecodeError::WrongFingerprint {
+                expected: F::BYTES as u8,
+                found: bytes[1],
+            });
+        }
+        let seed = u64::from_le_bytes(bytes[2..10].try_into().unwrap());
+        let segment_length = u32::from_le_bytes(bytes[10..14].try_into().unwrap());
+        let segment_count = u32::from_le_bytes(bytes[14..18].try_into().unwrap());
+        if !segment_length.is_power_of_two() || segment_count == 0 {
+            return Err(FilterDecodeError::Inconsistent);
+        }
+        let len = (segment_count as usize + 2)
+            .checked_mul(segment_length as usize)
+            .ok_or(FilterDecodeError::Inconsistent)?;
+        let body = &bytes[HEADER_LEN..];
+        if len.checked_mul(F::BYTES) != Some(body.len()) {
+            return Err(FilterDecodeError::InvalidLength);
+        }
+        Ok(Self {
+            seed,
+            segment_length,
+            segment_count,
+            fingerprints: body.chunks_exact(F::BYTES).map(F::read_le).collect(),
+        })
+    }
+
+    // One position in each of three consecutive segments
+    fn positions(&self, hash: u64) -> [usize; 3] {
+        let mask = u64::from(self.segment_length - 1);
+        let span = u64::from(self.segment_count) * u64::from(self.segment_length);
+        let h0 = ((u128::from(hash) * u128::from(span)) >> 64) as u64;
+        let h1 = (h0 + u64::from(self.segment_length)) ^ ((hash >> 18) & mask);
+        let h2 = (h0 + 2 * u64::from(self.segment_length)) ^ (hash & mask);
+        [h0 as usize, h1 as usize, h2 as usize]
+    }
+
+    // Peels the 3-hypergraph of `hashes` and assigns the fingerprints;
+    // returns `false` if it has a 2-core
+    fn populate(&mut self, hashes: &[u64]) -> bool {
+        let len = self.fingerprints.len();
+        let mut count = vec![0u32; len];
+        let mut xor = vec![0u64; len];
+        for &hash in hashes {
+            for h in self.positions(hash) {
+                count[h] += 1;
+                xor[h] ^= hash;
+            }
+        }
+
+        let mut queue: Vec<usize> = (0..len).filter(|&i| count[i] == 1).collect();
+        let mut stack = Vec::with_capacity(hashes.len());
+        while let Some(i) = queue.pop() {
+            if count[i] != 1 {
+                continue;
+            }
+            let hash = xor[i];
+            stack.push((i, hash));
+            for h in self.positions(hash) {
+                count[h] -= 1;
+                xor[h] ^= hash;
+                if count[h] == 1 {
+                    queue.push(h);
+                }
+            }
+        }
+        if stack.len() != hashes.len() {
+            return false;
+        }
+
+        // In reverse peeling order, each key's free position makes the
+        // fingerprints at its three positions xor to its fingerprint
+        for &(i, hash) in stack.iter().rev() {
+            let [h0, h1, h2] = self.positions(hash);
+            let fingerprints = &mut self.fingerprints;
+            fingerprints[i] = F::default();
+            fingerprints[i] =
+                F::from_hash(hash) ^ fingerprints[h0] ^ fingerprints[h1] ^ fingerprints[h2];
+        }
+        true
+    }
+}
+
+// Segment length and segment count for `n` keys, as in the reference
+// implementation for three hashes
+fn layout(n: usize) -> (u32, u32) {
+    const MAX_SEGMENT_LENGTH: u32 = 1 << 18;
+
+    let segment_length = if n <= 1 {
+        4
+    } else {
+        let exponent = (ln(n as f64) / ln(3.33) + 2.25) as u32;
+        (1u32 << exponent.min(18)).min(MAX_SEGMENT_LENGTH)
+    };
+    let capacity = if n <= 1 {
+        0
+    } else {
+        let size_factor = (0.875 + 0.25 * ln(1_000_000.0) / ln(n as f64)).max(1.125);
+        (n as f64 * size_factor + 0.5) as u64
+    };
+    let segments = capacity.div_ceil(u64::from(segment_length));
+    let segment_count = segments.saturating_sub(2).max(1);
+    (segment_length, segment_count as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(layout(0), (4, 1));
+        assert_eq!(layout(1), (4, 1));
+        // As computed by the reference `binary_fuse8_allocate`
+        assert_eq!(layout(3), (8, 1));
+        assert_eq!(layout(1000), (128, 9));
+        assert_eq!(layout(50_000), (2048, 28));
+        assert_eq!(layout(1_000_000), (8192, 136));
+    }
+
+    fn check<F: Fingerprint>(max_false_positives: usize) {
+        let keys: Vec<u64> = (0..50_000).map(|i| i * 3).collect();
+        let filter = FuseFilter::<F>::build(&keys).unwrap();
+        assert!(keys.iter().all(|key| filter.contains(key)));
+        assert!(filter.size_in_bytes() < keys.len() * F::BYTES * 5 / 4);
+
+        let false_positives = (0..100_000u64)
+            .map(|i| i * 3 + 1)
+            .filter(|key| filter.contains(key))
+            .count();
+        assert!(
+            false_positives <= max_false_positives,
+            "{} false positives",
+            false_positives
+        );
+
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + filter.size_in_bytes());
+        assert_eq!(FuseFilter::<F>::from_bytes(&bytes), Ok(filter));
+    }
+
+    #[test]
+    fn test_fuse_filter8() {
+        check::<u8>(800);
+    }
+
+    #[test]
+    fn test_fuse_filter16() {
+        check::<u16>(10);
+    }
+
+    #[test]
+    fn test_fuse_filter_small() {
+        for n in [0u32, 1, 2, 3, 10, 100] {
+            let keys: Vec<u32> = (0..n).collect();
+            let filter = FuseFilter8::build(&keys).unwrap();
+            assert!(keys.iter().all(|key| filter.contains(key)), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_fuse_filter_duplicates() {
+        let keys = ["a", "b", "a", "c", "b"];
+        let filter = FuseFilter16::build(&keys).unwrap();
+        assert!(keys.iter().all(|key| filter.contains(key)));
+    }
+
+    #[test]
+    fn test_fuse_filter_decode_errors() {
+        let bytes = FuseFilter8::build(&[1u8, 2, 3]).unwrap().to_bytes();
+        assert_eq!(
+            FuseFilter16::from_bytes(&bytes),
+            Err(FilterDecodeError::WrongFingerprint {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            FuseFilter8::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FilterDecodeError::InvalidLength)
+        );
+        let mut bad = bytes.clone();
+        bad[0] = 9;
+        assert_eq!(
+            FuseFilter8::from_bytes(&bad),
+            Err(FilterDecodeError::UnsupportedFormat(9))
+        );
+        let mut bad = bytes;
+        bad[10] = 3;
+        assert_eq!(
+            FuseFilter8::from_bytes(&bad),
+            Err(FilterDecodeError::Inconsistent)
+        );
+    }
+}
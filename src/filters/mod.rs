@@ -0,0 +1,24 @@
+//! Approximate membership filters built on ChibiHash
+//!
+//! - [`BloomFilter`]: a classic Bloom filter sized from the expected number
+//!   of items and a target false-positive rate.
+//! - [`ScalableBloomFilter`]: a chain of Bloom filters that grows with the
+//!   number of items while keeping the false-positive rate bounded.
+//! - [`FuseFilter8`] and [`FuseFilter16`]: binary fuse filters for a fixed
+//!   set of keys, much smaller than a Bloom filter.
+//!
+//! Filters answer "possibly in the set" or "definitely not in the set": a
+//! lookup can return a false positive, never a false negative. Items are
+//! hashed once with `v2` and every probe position is derived from that
+//! hash, so code that already has a hash can use the `*_hash` methods of
+//! the Bloom filters and skip hashing altogether. Only `alloc` is needed.
+
+mod bloom;
+mod fuse;
+mod scalable;
+
+pub use bloom::BloomFilter;
+pub use fuse::{
+    FilterDecodeError, Fingerprint, FuseBuildError, FuseFilter, FuseFilter16, FuseFilter8,
+};
+pub use scalable::ScalableBloomFilter;
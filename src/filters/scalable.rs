@@ -0,0 +1,177 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::hash::Hash;
+
+use super::BloomFilter;
+use crate::v2::hash_one;
+
+/// Capacity multiplier from one sub-filter to the next
+const GROWTH: usize = 2;
+
+/// False-positive rate multiplier from one sub-filter to the next
+const TIGHTENING: f64 = 0.85;
+
+/// A Bloom filter that grows as items are added
+///
+/// Starts with one [`BloomFilter`] for `initial_capacity` items; when it is
+/// full, a sub-filter with twice the capacity and a tighter false-positive
+/// rate is added, and so on. The rates form a geometric series, so the
+/// overall false-positive rate stays below `fpr` however many items are
+/// inserted (Almeida et al., "Scalable Bloom Filters").
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::filters::ScalableBloomFilter;
+///
+/// let mut seen = ScalableBloomFilter::new(100, 0.01);
+/// for id in 0..10_000u32 {
+///     seen.insert(&id);
+/// }
+/// assert!(seen.contains(&42u32));
+/// assert!(seen.num_filters() > 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalableBloomFilter {
+    filters: Vec<BloomFilter>,
+    initial_capacity: usize,
+    fpr: f64,
+    // Capacity, false-positive rate and items of the last sub-filter
+    capacity: usize,
+    last_fpr: f64,
+    last_len: usize,
+    len: usize,
+    seed: u64,
+}
+
+impl ScalableBloomFilter {
+    /// Creates an empty filter whose first sub-filter holds
+    /// `initial_capacity` items, keeping the overall false-positive rate
+    /// below `fpr`
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < fpr < 1`.
+    pub fn new(initial_capacity: usize, fpr: f64) -> Self {
+        assert!(
+            fpr > 0.0 && fpr < 1.0,
+            "false-positive rate must be between 0 and 1"
+        );
+        let capacity = initial_capacity.max(1);
+        let last_fpr = fpr * (1.0 - TIGHTENING);
+        Self {
+            filters: vec![BloomFilter::with_capacity_and_fpr(capacity, last_fpr)],
+            initial_capacity: capacity,
+            fpr,
+            capacity,
+            last_fpr,
+            last_len: 0,
+            len: 0,
+            seed: 0,
+        }
+    }
+
+    /// Returns the filter emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.clear();
+        self
+    }
+
+    /// Adds `item`, returning `false` if it was possibly present already
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        self.insert_hash(hash_one(item, self.seed))
+    }
+
+    /// Returns `false` if `item` was definitely never inserted
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.contains_hash(hash_one(item, self.seed))
+    }
+
+    /// Adds an item by its hash, as [`BloomFilter::insert_hash`]
+    ///
+    /// Returns `false` if the hash was possibly present already, in which
+    /// case nothing is added.
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        if self.contains_hash(hash) {
+            return false;
+        }
+        if self.last_len >= self.capacity {
+            self.capacity = self.capacity.saturating_mul(GROWTH);
+            self.last_fpr *= TIGHTENING;
+            self.last_len = 0;
+            self.filters.push(
+                BloomFilter::with_capacity_and_fpr(self.capacity, self.last_fpr)
+                    .with_seed(self.seed),
+            );
+        }
+        self.filters.last_mut().unwrap().insert_hash(hash);
+        self.last_len += 1;
+        self.len += 1;
+        true
+    }
+
+    /// Returns `false` if no item with `hash` was inserted
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.filters.iter().any(|filter| filter.contains_hash(hash))
+    }
+
+    /// Removes every item and drops the grown sub-filters
+    pub fn clear(&mut self) {
+        *self = Self {
+            seed: self.seed,
+            ..Self::new(self.initial_capacity, self.fpr)
+        };
+    }
+
+    /// Returns the number of distinct items inserted, not counting items
+    /// rejected as possibly present
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing was inserted
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of sub-filters
+    pub fn num_filters(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the total size of the sub-filters in bits
+    pub fn num_bits(&self) -> usize {
+        self.filters.iter().map(BloomFilter::num_bits).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalable_bloom_filter() {
+        let mut filter = ScalableBloomFilter::new(100, 0.01).with_seed(9);
+        for i in 0..5000u32 {
+            filter.insert(&i);
+        }
+        assert!((0..5000u32).all(|i| filter.contains(&i)));
+        // 100 + 200 + 400 + 800 + 1600 + 3200 items
+        assert_eq!(filter.num_filters(), 6);
+        assert!(filter.len() <= 5000 && filter.len() > 4950);
+
+        let false_positives = (5000..25_000u32).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+
+        assert!(!filter.insert(&7u32));
+        filter.clear();
+        assert!(filter.is_empty());
+        assert_eq!(filter.num_filters(), 1);
+        assert!(filter.insert(&7u32));
+    }
+}
@@ -0,0 +1,430 @@
+//! Hash-framed payloads for cheap corruption detection
+//!
+//! A frame is the payload wrapped with its length and a seeded `v2` digest:
+//!
+//! ```text
+//! +----------------+-------------------+----------------+
+//! | len: u64 (LE)  | payload (len B)   | hash: u64 (LE) |
+//! +----------------+-------------------+----------------+
+//! ```
+//!
+//! The trailer hash covers both the length field and the payload, so a
+//! corrupted length is detected as well as corrupted data. Frames can be
+//! written back to back, which suits durable queues and snapshot files.
+//!
+//! [`write`] and [`read`] handle one frame at a time. [`FramedWriter`] and
+//! [`FramedReader`] wrap a stream such as a `TcpStream` for a whole
+//! conversation: the reader bounds the frame length, tells a clean end of
+//! stream from a cut-off frame, and reports failures as a [`FrameError`].
+//!
+//! ChibiHash is not a MAC: framing detects accidental corruption, not
+//! tampering.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::framed;
+//!
+//! let mut file = Vec::new();
+//! framed::write(&mut file, b"snapshot #1", 42).unwrap();
+//! framed::write(&mut file, b"snapshot #2", 42).unwrap();
+//!
+//! let mut reader = &file[..];
+//! assert_eq!(framed::read(&mut reader, 42).unwrap(), b"snapshot #1");
+//! assert_eq!(framed::read(&mut reader, 42).unwrap(), b"snapshot #2");
+//!
+//! file[10] ^= 1;
+//! assert!(framed::read(&mut &file[..], 42).is_err());
+//! ```
+//!
+//! ```rust
+//! use chibihash::framed::{FrameError, FramedReader, FramedWriter};
+//!
+//! let mut writer = FramedWriter::new(Vec::new(), 42);
+//! writer.write_frame(b"hello").unwrap();
+//! writer.write_frame(b"world").unwrap();
+//! let mut wire = writer.into_inner();
+//!
+//! let mut reader = FramedReader::new(&wire[..], 42);
+//! assert_eq!(reader.read_frame().unwrap().unwrap(), b"hello");
+//! assert_eq!(reader.read_frame().unwrap().unwrap(), b"world");
+//! assert!(reader.read_frame().unwrap().is_none());
+//!
+//! wire[9] ^= 1;
+//! let mut reader = FramedReader::new(&wire[..], 42);
+//! assert!(matches!(reader.read_frame(), Err(FrameError::Corrupt { .. })));
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::v2::StreamingChibiHasher;
+
+/// Size of the length header and the hash trailer combined
+pub const FRAME_OVERHEAD: usize = 16;
+
+pub(crate) fn frame_hash(len: &[u8; 8], payload: &[u8], seed: u64) -> u64 {
+    let mut hasher = StreamingChibiHasher::new(seed);
+    hasher.update(len);
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+/// Writes `payload` as a single frame
+pub fn write<W: Write + ?Sized>(writer: &mut W, payload: &[u8], seed: u64) -> io::Result<()> {
+    let len = (payload.len() as u64).to_le_bytes();
+    writer.write_all(&len)?;
+    writer.write_all(payload)?;
+    writer.write_all(&frame_hash(&len, payload, seed).to_le_bytes())
+}
+
+/// Reads and verifies a single frame, returning its payload
+///
+/// Returns [`io::ErrorKind::UnexpectedEof`] if the frame is truncated and
+/// [`io::ErrorKind::InvalidData`] if the trailer hash does not match.
+pub fn read<R: Read + ?Sized>(reader: &mut R, seed: u64) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    Ok(read_rest(reader, len, seed, u64::MAX)?)
+}
+
+// Reads the payload and trailer of a frame whose length field was `len`
+fn read_rest<R: Read + ?Sized>(
+    reader: &mut R,
+    len: [u8; 8],
+    seed: u64,
+    max_len: u64,
+) -> Result<Vec<u8>, FrameError> {
+    let expected_len = u64::from_le_bytes(len);
+    if expected_len > max_len {
+        return Err(FrameError::TooLong {
+            len: expected_len,
+            max: max_len,
+        });
+    }
+
+    // Grow as data arrives instead of trusting a possibly corrupted length
+    let mut payload = Vec::new();
+    reader.take(expected_len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != expected_len {
+        return Err(FrameError::Io(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "truncated frame payload",
+        )));
+    }
+
+    let mut trailer = [0u8; 8];
+    reader.read_exact(&mut trailer)?;
+    let expected = u64::from_le_bytes(trailer);
+    let actual = frame_hash(&len, &payload, seed);
+    if expected != actual {
+        return Err(FrameError::Corrupt { expected, actual });
+    }
+    Ok(payload)
+}
+
+/// Error returned by [`FramedReader`]
+#[derive(Debug)]
+pub enum FrameError {
+    /// The underlying stream failed, or ended within a frame
+    /// ([`io::ErrorKind::UnexpectedEof`])
+    Io(io::Error),
+    /// The trailer hash does not match the length and payload
+    Corrupt {
+        /// Hash in the trailer
+        expected: u64,
+        /// Hash of the length and payload as received
+        actual: u64,
+    },
+    /// The length field exceeds the reader's maximum frame length
+    TooLong {
+        /// Length in the frame header
+        len: u64,
+        /// Maximum the reader accepts
+        max: u64,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "frame I/O error: {}", e),
+            FrameError::Corrupt { expected, actual } => write!(
+                f,
+                "frame hash mismatch: expected {:016x}, got {:016x}",
+                expected, actual
+            ),
+            FrameError::TooLong { len, max } => {
+                write!(f, "frame of {} bytes exceeds the maximum of {}", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrameError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FrameError {
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+/// Converts corruption and oversized frames to [`io::ErrorKind::InvalidData`]
+impl From<FrameError> for io::Error {
+    fn from(e: FrameError) -> Self {
+        match e {
+            FrameError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Writes each payload to the inner writer as a frame
+///
+/// Frames are written with [`Write::write_all`] and not flushed; wrap a
+/// socket in a [`BufWriter`](std::io::BufWriter) and call
+/// [`flush`](Self::flush) once a batch of frames is written.
+#[derive(Debug)]
+pub struct FramedWriter<W> {
+    inner: W,
+    seed: u64,
+}
+
+impl<W: Write> FramedWriter<W> {
+    /// Creates a writer hashing frames with `seed`
+    ///
+    /// Both ends of a link must use the same seed.
+    pub fn new(inner: W, seed: u64) -> Self {
+        Self { inner, seed }
+    }
+
+    /// Writes `payload` as a single frame
+    pub fn write_frame(&mut self, payload: &[u8]) -> io::Result<()> {
+        write(&mut self.inner, payload, self.seed)
+    }
+
+    /// Flushes the inner writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer
+    ///
+    /// Writing to it directly breaks the framing.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads and verifies frames from the inner reader
+///
+/// Iterating yields frames until the stream ends cleanly between frames.
+/// After an error the stream position is unknown, so the connection should
+/// be dropped rather than read further.
+#[derive(Debug)]
+pub struct FramedReader<R> {
+    inner: R,
+    seed: u64,
+    max_frame_length: u64,
+}
+
+impl<R: Read> FramedReader<R> {
+    /// Default maximum payload length, 8 MiB
+    pub const DEFAULT_MAX_FRAME_LENGTH: u64 = 8 * 1024 * 1024;
+
+    /// Creates a reader checking frames with `seed`
+    pub fn new(inner: R, seed: u64) -> Self {
+        Self {
+            inner,
+            seed,
+            max_frame_length: Self::DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+
+    /// Sets the maximum accepted payload length
+    ///
+    /// Longer frames fail with [`FrameError::TooLong`] before any of the
+    /// payload is read, which bounds the memory a peer can make the reader
+    /// allocate.
+    pub fn with_max_frame_length(mut self, max_frame_length: u64) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Returns the maximum accepted payload length
+    pub fn max_frame_length(&self) -> u64 {
+        self.max_frame_length
+    }
+
+    /// Reads and verifies the next frame, returning its payload, or `None`
+    /// if the stream ended cleanly before it
+    pub fn read_frame(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let mut len = [0u8; 8];
+        let mut filled = 0;
+        while filled < len.len() {
+            match self.inner.read(&mut len[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => {
+                    return Err(FrameError::Io(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "truncated frame header",
+                    )))
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        read_rest(&mut self.inner, len, self.seed, self.max_frame_length).map(Some)
+    }
+
+    /// Returns a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader
+    ///
+    /// Reading from it directly breaks the framing.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for FramedReader<R> {
+    type Item = Result<Vec<u8>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut buf = Vec::new();
+        write(&mut buf, b"", 1).unwrap();
+        write(&mut buf, b"hello", 1).unwrap();
+        assert_eq!(buf.len(), 2 * FRAME_OVERHEAD + 5);
+
+        let mut reader = &buf[..];
+        assert_eq!(read(&mut reader, 1).unwrap(), b"");
+        assert_eq!(read(&mut reader, 1).unwrap(), b"hello");
+        assert_eq!(
+            read(&mut reader, 1).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let mut buf = Vec::new();
+        write(&mut buf, b"hello world", 1).unwrap();
+        for i in 0..buf.len() {
+            let mut corrupted = buf.clone();
+            corrupted[i] ^= 0x10;
+            assert!(read(&mut &corrupted[..], 1).is_err(), "byte {}", i);
+        }
+    }
+
+    #[test]
+    fn test_wrong_seed() {
+        let mut buf = Vec::new();
+        write(&mut buf, b"hello", 1).unwrap();
+        let err = read(&mut &buf[..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut buf = Vec::new();
+        write(&mut buf, b"hello", 1).unwrap();
+        let err = read(&mut &buf[..10], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_framed_writer_reader() {
+        let mut writer = FramedWriter::new(Vec::new(), 3);
+        for payload in [&b"one"[..], b"", b"three"] {
+            writer.write_frame(payload).unwrap();
+        }
+        writer.flush().unwrap();
+        let wire = writer.into_inner();
+
+        // Same wire format as `write` and `read`
+        let mut reader = &wire[..];
+        assert_eq!(read(&mut reader, 3).unwrap(), b"one");
+
+        let frames: Vec<Vec<u8>> = FramedReader::new(&wire[..], 3)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(frames, [&b"one"[..], b"", b"three"]);
+    }
+
+    #[test]
+    fn test_framed_reader_errors() {
+        let mut wire = Vec::new();
+        write(&mut wire, b"hello world", 1).unwrap();
+
+        let mut corrupted = wire.clone();
+        corrupted[12] ^= 1;
+        let err = FramedReader::new(&corrupted[..], 1)
+            .read_frame()
+            .unwrap_err();
+        match err {
+            FrameError::Corrupt { expected, actual } => assert_ne!(expected, actual),
+            e => panic!("unexpected error: {}", e),
+        }
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidData);
+
+        for cut in [3, 10, wire.len() - 1] {
+            let err = FramedReader::new(&wire[..cut], 1).read_frame().unwrap_err();
+            assert_eq!(io::Error::from(err).kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        let mut reader = FramedReader::new(&wire[..], 1).with_max_frame_length(10);
+        assert_eq!(reader.max_frame_length(), 10);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(FrameError::TooLong { len: 11, max: 10 })
+        ));
+
+        let mut reader = FramedReader::new(&wire[..], 1);
+        assert_eq!(reader.next().unwrap().unwrap(), b"hello world");
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_huge_length_does_not_allocate() {
+        let mut buf = u64::MAX.to_le_bytes().to_vec();
+        buf.extend_from_slice(b"short");
+        let err = read(&mut &buf[..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
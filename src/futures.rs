@@ -0,0 +1,188 @@
+//! `futures-io` adapters that hash data as it streams through
+//!
+//! Runtime-agnostic counterparts of [`io::HashReader`](crate::io::HashReader)
+//! and [`io::HashWriter`](crate::io::HashWriter), implementing
+//! [`AsyncRead`] and [`AsyncWrite`]. They work with any executor; Tokio
+//! types can be adapted with `tokio-util`'s `compat` module.
+//!
+//! The inner reader or writer must be [`Unpin`]; wrap others in
+//! `Box::pin`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::futures::HashReader;
+//! use futures_util::io::AsyncReadExt;
+//!
+//! futures_executor::block_on(async {
+//!     let mut reader = HashReader::new(&b"downloaded bytes"[..], 0);
+//!     let mut out = Vec::new();
+//!     reader.read_to_end(&mut out).await.unwrap();
+//!     assert_eq!(reader.finalize(), chibihash::v2::chibi_hash64(&out, 0));
+//! });
+//! ```
+
+use std::hash::Hasher;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use crate::v2;
+
+/// An async reader that hashes every byte it reads from the inner reader
+#[derive(Debug)]
+pub struct HashReader<R, H = v2::StreamingChibiHasher> {
+    inner: R,
+    hasher: H,
+}
+
+impl<R: AsyncRead + Unpin> HashReader<R> {
+    /// Wraps `inner`, hashing with `v2` under `seed`
+    pub fn new(inner: R, seed: u64) -> Self {
+        Self::with_hasher(inner, v2::StreamingChibiHasher::new(seed))
+    }
+}
+
+impl<R: AsyncRead + Unpin, H: Hasher + Unpin> HashReader<R, H> {
+    /// Wraps `inner`, feeding read bytes to `hasher`
+    pub fn with_hasher(inner: R, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Returns the digest of the bytes read so far
+    pub fn finalize(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin, H: Hasher + Unpin> AsyncRead for HashReader<R, H> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+        this.hasher.write(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// An async writer that hashes every byte it passes to the inner writer
+///
+/// Only bytes the inner writer accepts are hashed.
+#[derive(Debug)]
+pub struct HashWriter<W, H = v2::StreamingChibiHasher> {
+    inner: W,
+    hasher: H,
+}
+
+impl<W: AsyncWrite + Unpin> HashWriter<W> {
+    /// Wraps `inner`, hashing with `v2` under `seed`
+    pub fn new(inner: W, seed: u64) -> Self {
+        Self::with_hasher(inner, v2::StreamingChibiHasher::new(seed))
+    }
+}
+
+impl<W: AsyncWrite + Unpin, H: Hasher + Unpin> HashWriter<W, H> {
+    /// Wraps `inner`, feeding written bytes to `hasher`
+    pub fn with_hasher(inner: W, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Returns the digest of the bytes written so far
+    pub fn finalize(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwraps the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin, H: Hasher + Unpin> AsyncWrite for HashWriter<W, H> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = match Pin::new(&mut this.inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+        this.hasher.write(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_executor::block_on;
+    use futures_util::io::{copy, AsyncReadExt, AsyncWriteExt, Cursor};
+
+    #[test]
+    fn test_hash_reader() {
+        let data = b"hello world".repeat(10);
+        block_on(async {
+            let mut reader = HashReader::new(&data[..], 5);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).await.unwrap();
+            assert_eq!(out, data);
+            assert_eq!(reader.finalize(), v2::chibi_hash64(&data, 5));
+        });
+    }
+
+    #[test]
+    fn test_hash_writer() {
+        let data = b"hello world".repeat(10);
+        block_on(async {
+            let mut writer = HashWriter::new(Cursor::new(Vec::new()), 5);
+            copy(&mut &data[..], &mut writer).await.unwrap();
+            writer.close().await.unwrap();
+            assert_eq!(writer.finalize(), v2::chibi_hash64(&data, 5));
+            assert_eq!(writer.into_inner().into_inner(), data);
+        });
+    }
+
+    #[test]
+    fn test_custom_hasher() {
+        block_on(async {
+            let hasher = crate::v1::StreamingChibiHasher::new(0);
+            let mut writer = HashWriter::with_hasher(Cursor::new(Vec::new()), hasher);
+            writer.write_all(b"hello").await.unwrap();
+            assert_eq!(writer.finalize(), crate::v1::chibi_hash64(b"hello", 0));
+        });
+    }
+}
@@ -0,0 +1,239 @@
+//! Hashers and collections generic over the algorithm version
+//!
+//! [`ChibiHasher<V>`](ChibiHasher), [`StreamingChibiHasher<V>`](StreamingChibiHasher)
+//! and [`ChibiHashMap<K, T, V>`](ChibiHashMap) take a [`V1`](crate::version::V1)
+//! or [`V2`](crate::version::V2) marker and produce exactly the hashes of
+//! that version's module, so code can be written once for both versions. The
+//! marker has no default and must always be named.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::generic::StreamingChibiHasher;
+//! use chibihash::version::{VersionMarker, V1, V2};
+//!
+//! fn digest<V: VersionMarker>(parts: &[&[u8]]) -> u64 {
+//!     let mut hasher = StreamingChibiHasher::<V>::new(0);
+//!     for part in parts {
+//!         hasher.update(part);
+//!     }
+//!     hasher.finalize()
+//! }
+//!
+//! assert_eq!(digest::<V1>(&[b"ab", b"cd"]), chibihash::v1::chibi_hash64(b"abcd", 0));
+//! assert_eq!(digest::<V2>(&[b"ab", b"cd"]), chibihash::v2::chibi_hash64(b"abcd", 0));
+//! ```
+
+#[cfg(feature = "hashbrown")]
+use hashbrown::{HashMap as BaseHashMap, HashSet as BaseHashSet};
+#[cfg(all(feature = "std", not(feature = "hashbrown")))]
+use std::collections::{HashMap as BaseHashMap, HashSet as BaseHashSet};
+
+use core::hash::Hasher;
+
+use crate::version::sealed::Streaming;
+use crate::version::VersionMarker;
+
+// Forwards the integer writes so the per-version fast paths are kept
+macro_rules! forward_int_writes {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            #[inline]
+            fn $method(&mut self, i: $ty) {
+                self.inner.$method(i);
+            }
+        )*
+    };
+}
+
+/// Streaming hasher for version `V`
+///
+/// Equal to `v1::StreamingChibiHasher` or `v2::StreamingChibiHasher`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StreamingChibiHasher<V: VersionMarker> {
+    inner: V::Streaming,
+}
+
+impl<V: VersionMarker> StreamingChibiHasher<V> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            inner: V::Streaming::new(seed),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
+    }
+
+    pub fn finalize(&self) -> u64 {
+        self.inner.finalize()
+    }
+}
+
+impl<V: VersionMarker> Default for StreamingChibiHasher<V> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<V: VersionMarker> Hasher for StreamingChibiHasher<V> {
+    fn finish(&self) -> u64 {
+        self.inner.finalize()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    forward_int_writes! {
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_usize: usize
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V: VersionMarker> std::io::Write for StreamingChibiHasher<V> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `Hasher` and `BuildHasher` for version `V`
+///
+/// Equal to `v1::ChibiHasher` or `v2::ChibiHasher`: the result is
+/// `chibi_hash64` of all written bytes concatenated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChibiHasher<V: VersionMarker> {
+    inner: StreamingChibiHasher<V>,
+    seed: u64,
+}
+
+impl<V: VersionMarker> ChibiHasher<V> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            inner: StreamingChibiHasher::new(seed),
+            seed,
+        }
+    }
+
+    /// Hashes `input` in one call, ignoring anything written so far
+    pub fn hash(&self, input: &[u8]) -> u64 {
+        V::hash64(input, self.seed)
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl<V: VersionMarker> Default for ChibiHasher<V> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<V: VersionMarker> Hasher for ChibiHasher<V> {
+    fn finish(&self) -> u64 {
+        self.inner.finalize()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    forward_int_writes! {
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_usize: usize
+    }
+}
+
+impl<V: VersionMarker> core::hash::BuildHasher for ChibiHasher<V> {
+    type Hasher = ChibiHasher<V>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        ChibiHasher::new(self.seed)
+    }
+}
+
+/// A `HashMap` hashed with version `Ver`
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type ChibiHashMap<K, T, Ver> = BaseHashMap<K, T, ChibiHasher<Ver>>;
+
+/// A `HashSet` hashed with version `Ver`
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type ChibiHashSet<T, Ver> = BaseHashSet<T, ChibiHasher<Ver>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::{V1, V2};
+    use crate::{v1, v2};
+    use core::hash::{BuildHasher, Hash};
+
+    fn hash_one_v2<T: Hash>(value: T) -> u64 {
+        let mut hasher = v2::ChibiHasher::new(9);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_generic_matches_modules() {
+        let data = [0x3Cu8; 77];
+        let mut s1 = StreamingChibiHasher::<V1>::new(9);
+        let mut s2 = StreamingChibiHasher::<V2>::new(9);
+        s1.update(&data);
+        s2.update(&data);
+        assert_eq!(s1.finalize(), v1::chibi_hash64(&data, 9));
+        assert_eq!(s2.finalize(), v2::chibi_hash64(&data, 9));
+
+        fn hash<H: Hasher>(mut hasher: H) -> u64 {
+            ("key", 42u32, [1u8, 2, 3]).hash(&mut hasher);
+            hasher.finish()
+        }
+        assert_eq!(
+            hash(ChibiHasher::<V1>::new(9)),
+            hash(v1::ChibiHasher::new(9))
+        );
+        assert_eq!(
+            hash(ChibiHasher::<V2>::new(9)),
+            hash(v2::ChibiHasher::new(9))
+        );
+        assert_eq!(ChibiHasher::<V2>::new(9).hash_one(7u8), hash_one_v2(7u8));
+        assert_eq!(
+            ChibiHasher::<V1>::new(9).hash(&data),
+            v1::chibi_hash64(&data, 9)
+        );
+    }
+
+    #[test]
+    fn test_generic_hasher_writes() {
+        fn hash<V: VersionMarker, T: Hash>(value: T) -> u64 {
+            let mut hasher = ChibiHasher::<V>::new(1);
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        let mut bytes = StreamingChibiHasher::<V2>::new(1);
+        bytes.write(&7u64.to_ne_bytes());
+        assert_eq!(hash::<V2, _>(7u64), bytes.finish());
+        assert_ne!(hash::<V1, _>(7u64), hash::<V2, _>(7u64));
+    }
+
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    #[test]
+    fn test_generic_hash_map() {
+        let mut map: ChibiHashMap<&str, i32, V1> = ChibiHashMap::default();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        assert_eq!(map.get("b"), Some(&2));
+
+        let mut set: ChibiHashSet<u32, V2> = ChibiHashSet::with_hasher(ChibiHasher::new(5));
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+    }
+}
@@ -0,0 +1,390 @@
+//! Typed digests that print as hex and remember their algorithm version
+//!
+//! [`ChibiHash64`] and [`ChibiHash128`] wrap a digest together with a
+//! [`V1`] or [`V2`] marker, so a `v1` digest cannot be compared with or
+//! passed where a `v2` digest is expected. The marker has no default and
+//! must always be named. Both are `#[repr(transparent)]`
+//! over the integer and print as zero-padded lowercase hex.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::hash::ChibiHash64;
+//! use chibihash::version::V2;
+//!
+//! let hash = ChibiHash64::<V2>::of(b"abcdefgh", 0);
+//! assert_eq!(hash.to_string(), "a2e39be0a0689b32");
+//! assert_eq!("a2e39be0a0689b32".parse::<ChibiHash64<V2>>(), Ok(hash));
+//! assert_eq!(hash.get(), chibihash::v2::chibi_hash64(b"abcdefgh", 0));
+//! ```
+//!
+//! [`V1`]: crate::version::V1
+//! [`V2`]: crate::version::V2
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+use crate::encoding::{self, DecodeError, Encoded, BASE32, BASE64_URL, HEX};
+use crate::version::VersionMarker;
+
+/// A 64-bit digest produced by version `V`
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChibiHash64<V>(u64, PhantomData<V>);
+
+impl<V: VersionMarker> ChibiHash64<V> {
+    /// Wraps a digest known to come from version `V`
+    pub const fn new(hash: u64) -> Self {
+        Self(hash, PhantomData)
+    }
+
+    /// Hashes `key` with version `V`
+    pub fn of(key: &[u8], seed: u64) -> Self {
+        Self::new(V::hash64(key, seed))
+    }
+
+    /// Returns the digest as an integer
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Encodes the digest as 16 lowercase hex digits
+    pub fn to_hex(self) -> Encoded<16> {
+        encoding::to_hex(self.0)
+    }
+
+    /// Decodes 16 hex digits, in either case
+    pub fn from_hex(s: &str) -> Result<Self, DecodeError> {
+        encoding::from_hex(s).map(Self::new)
+    }
+
+    /// Encodes the digest as 11 characters of unpadded URL-safe base64
+    pub fn to_base64(self) -> Encoded<11> {
+        encoding::to_base64(self.0)
+    }
+
+    /// Decodes 11 characters of unpadded URL-safe base64
+    pub fn from_base64(s: &str) -> Result<Self, DecodeError> {
+        encoding::from_base64(s).map(Self::new)
+    }
+
+    /// Encodes the digest as 13 characters of unpadded lowercase base32
+    pub fn to_base32(self) -> Encoded<13> {
+        encoding::to_base32(self.0)
+    }
+
+    /// Decodes 13 characters of unpadded base32, in either case
+    pub fn from_base32(s: &str) -> Result<Self, DecodeError> {
+        encoding::from_base32(s).map(Self::new)
+    }
+
+    /// Encodes the digest as 11 base58 characters, for short IDs and slugs
+    pub fn to_base58(self) -> Encoded<11> {
+        encoding::to_base58(self.0)
+    }
+
+    /// Decodes at most 11 base58 characters
+    pub fn from_base58(s: &str) -> Result<Self, DecodeError> {
+        encoding::from_base58(s).map(Self::new)
+    }
+
+    /// Encodes the digest as 13 Crockford base32 characters, which sort like
+    /// the digest
+    pub fn to_crockford(self) -> Encoded<13> {
+        encoding::to_crockford32(self.0)
+    }
+
+    /// Decodes Crockford base32 as [`encoding::from_crockford32`] does
+    pub fn from_crockford(s: &str) -> Result<Self, DecodeError> {
+        encoding::from_crockford32(s).map(Self::new)
+    }
+}
+
+impl<V: VersionMarker> From<ChibiHash64<V>> for u64 {
+    fn from(hash: ChibiHash64<V>) -> Self {
+        hash.0
+    }
+}
+
+impl<V: VersionMarker> fmt::Display for ChibiHash64<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl<V: VersionMarker> fmt::Debug for ChibiHash64<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChibiHash64<V{}>({:016x})", V::VERSION, self.0)
+    }
+}
+
+impl<V: VersionMarker> fmt::LowerHex for ChibiHash64<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl<V: VersionMarker> fmt::UpperHex for ChibiHash64<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl<V: VersionMarker> FromStr for ChibiHash64<V> {
+    type Err = DecodeError;
+
+    /// Parses exactly 16 hex digits, in either case
+    fn from_str(s: &str) -> Result<Self, DecodeError> {
+        Self::from_hex(s)
+    }
+}
+
+/// A 128-bit digest produced by version `V`
+///
+/// [`of`](Self::of) takes the first 16 bytes of the version's `ChibiXof`
+/// output, read as a big-endian integer so the hex form lists the bytes in
+/// output order.
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChibiHash128<V>(u128, PhantomData<V>);
+
+impl<V: VersionMarker> ChibiHash128<V> {
+    /// Wraps a digest known to come from version `V`
+    pub const fn new(hash: u128) -> Self {
+        Self(hash, PhantomData)
+    }
+
+    /// Hashes `key` with version `V`
+    pub fn of(key: &[u8], seed: u64) -> Self {
+        let mut out = [0u8; 16];
+        V::xof(key, seed, &mut out);
+        Self::new(u128::from_be_bytes(out))
+    }
+
+    /// Returns the digest as an integer
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    /// Encodes the digest as 32 lowercase hex digits
+    pub fn to_hex(self) -> Encoded<32> {
+        encoding::encode_bytes(&self.0.to_be_bytes(), &HEX)
+    }
+
+    /// Decodes 32 hex digits, in either case
+    pub fn from_hex(s: &str) -> Result<Self, DecodeError> {
+        encoding::decode_bytes(s, &HEX).map(|bytes| Self::new(u128::from_be_bytes(bytes)))
+    }
+
+    /// Encodes the digest as 22 characters of unpadded URL-safe base64
+    pub fn to_base64(self) -> Encoded<22> {
+        encoding::encode_bytes(&self.0.to_be_bytes(), &BASE64_URL)
+    }
+
+    /// Decodes 22 characters of unpadded URL-safe base64
+    pub fn from_base64(s: &str) -> Result<Self, DecodeError> {
+        encoding::decode_bytes(s, &BASE64_URL).map(|bytes| Self::new(u128::from_be_bytes(bytes)))
+    }
+
+    /// Encodes the digest as 26 characters of unpadded lowercase base32
+    pub fn to_base32(self) -> Encoded<26> {
+        encoding::encode_bytes(&self.0.to_be_bytes(), &BASE32)
+    }
+
+    /// Decodes 26 characters of unpadded base32, in either case
+    pub fn from_base32(s: &str) -> Result<Self, DecodeError> {
+        encoding::decode_bytes(s, &BASE32).map(|bytes| Self::new(u128::from_be_bytes(bytes)))
+    }
+
+    /// Encodes the digest as 22 base58 characters, for short IDs and slugs
+    pub fn to_base58(self) -> Encoded<22> {
+        encoding::encode_base58(self.0)
+    }
+
+    /// Decodes at most 22 base58 characters
+    pub fn from_base58(s: &str) -> Result<Self, DecodeError> {
+        encoding::decode_base58(s, 22).map(Self::new)
+    }
+
+    /// Encodes the digest as 26 Crockford base32 characters, which sort like
+    /// the digest
+    pub fn to_crockford(self) -> Encoded<26> {
+        encoding::encode_crockford32(self.0)
+    }
+
+    /// Decodes Crockford base32 as [`encoding::from_crockford32`] does
+    pub fn from_crockford(s: &str) -> Result<Self, DecodeError> {
+        encoding::decode_crockford32(s, 128).map(Self::new)
+    }
+}
+
+impl<V: VersionMarker> From<ChibiHash128<V>> for u128 {
+    fn from(hash: ChibiHash128<V>) -> Self {
+        hash.0
+    }
+}
+
+impl<V: VersionMarker> fmt::Display for ChibiHash128<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl<V: VersionMarker> fmt::Debug for ChibiHash128<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChibiHash128<V{}>({:032x})", V::VERSION, self.0)
+    }
+}
+
+impl<V: VersionMarker> fmt::LowerHex for ChibiHash128<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl<V: VersionMarker> fmt::UpperHex for ChibiHash128<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl<V: VersionMarker> FromStr for ChibiHash128<V> {
+    type Err = DecodeError;
+
+    /// Parses exactly 32 hex digits, in either case
+    fn from_str(s: &str) -> Result<Self, DecodeError> {
+        Self::from_hex(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::version::{V1, V2};
+
+    #[cfg(not(feature = "std"))]
+    extern crate alloc;
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::ToString};
+
+    #[test]
+    fn test_hash64_format_and_parse() {
+        let hash = ChibiHash64::<V1>::of(b"", 0);
+        assert_eq!(hash.get(), 0x9EA80F3B18E26CFB);
+        assert_eq!(hash.to_string(), "9ea80f3b18e26cfb");
+        assert_eq!(format!("{:X}", hash), "9EA80F3B18E26CFB");
+        assert_eq!(format!("{:?}", hash), "ChibiHash64<V1>(9ea80f3b18e26cfb)");
+        assert_eq!("9EA80F3B18E26CFB".parse(), Ok(hash));
+
+        let small = ChibiHash64::<V2>::new(0xAB);
+        assert_eq!(small.to_string(), "00000000000000ab");
+        assert_eq!(small.to_string().parse(), Ok(small));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "abc".parse::<ChibiHash64<V2>>(),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            "000000000000000g".parse::<ChibiHash64<V2>>(),
+            Err(DecodeError::InvalidCharacter('g'))
+        );
+    }
+
+    #[test]
+    fn test_hash128() {
+        let hash = ChibiHash128::<V2>::of(b"key", 3);
+        let mut out = [0u8; 16];
+        crate::v2::ChibiXof::new(3).update(b"key").squeeze(&mut out);
+        assert_eq!(hash.get().to_be_bytes(), out);
+        assert_eq!(hash.to_string().len(), 32);
+        assert_eq!(hash.to_string().parse(), Ok(hash));
+        assert_ne!(ChibiHash128::<V1>::of(b"key", 3).get(), hash.get());
+    }
+
+    #[test]
+    fn test_encodings() {
+        let hash = ChibiHash64::<V2>::of(b"manifest", 0);
+        assert_eq!(hash.to_hex(), hash.to_string().as_str());
+        assert_eq!(ChibiHash64::from_hex(&hash.to_hex()), Ok(hash));
+        assert_eq!(ChibiHash64::from_base64(&hash.to_base64()), Ok(hash));
+        assert_eq!(ChibiHash64::from_base32(&hash.to_base32()), Ok(hash));
+
+        let wide = ChibiHash128::<V2>::of(b"manifest", 0);
+        assert_eq!(wide.to_hex(), wide.to_string().as_str());
+        assert_eq!(wide.to_base64().len(), 22);
+        assert_eq!(wide.to_base32().len(), 26);
+        assert_eq!(ChibiHash128::from_hex(&wide.to_hex()), Ok(wide));
+        assert_eq!(ChibiHash128::from_base64(&wide.to_base64()), Ok(wide));
+        assert_eq!(ChibiHash128::from_base32(&wide.to_base32()), Ok(wide));
+    }
+
+    #[test]
+    fn test_short_encodings() {
+        for value in [0, 1, 0x0123_4567_89AB_CDEF, u64::MAX] {
+            let hash = ChibiHash64::<V2>::new(value);
+            assert_eq!(hash.to_base58().len(), 11);
+            assert_eq!(hash.to_crockford().len(), 13);
+            assert_eq!(ChibiHash64::from_base58(&hash.to_base58()), Ok(hash));
+            assert_eq!(ChibiHash64::from_crockford(&hash.to_crockford()), Ok(hash));
+        }
+        assert_eq!(ChibiHash64::<V2>::new(u64::MAX).to_base58(), "jpXCZedGfVQ");
+
+        for value in [0, 1, u128::from(u64::MAX) + 1, u128::MAX] {
+            let wide = ChibiHash128::<V2>::new(value);
+            assert_eq!(wide.to_base58().len(), 22);
+            assert_eq!(wide.to_crockford().len(), 26);
+            assert_eq!(ChibiHash128::from_base58(&wide.to_base58()), Ok(wide));
+            assert_eq!(ChibiHash128::from_crockford(&wide.to_crockford()), Ok(wide));
+        }
+        assert_eq!(
+            ChibiHash128::<V2>::new(u128::MAX).to_crockford(),
+            "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+        );
+    }
+
+    #[test]
+    fn test_short_encoding_errors() {
+        assert_eq!(
+            ChibiHash64::<V2>::from_base58(""),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            ChibiHash64::<V2>::from_base58("0"),
+            Err(DecodeError::InvalidCharacter('0'))
+        );
+        assert_eq!(
+            ChibiHash64::<V2>::from_base58("zzzzzzzzzzz"),
+            Err(DecodeError::Overflow)
+        );
+        assert_eq!(
+            ChibiHash64::<V2>::from_crockford("U"),
+            Err(DecodeError::InvalidCharacter('U'))
+        );
+        assert_eq!(
+            ChibiHash64::<V2>::from_crockford("G000000000000"),
+            Err(DecodeError::Overflow)
+        );
+
+        assert_eq!(
+            ChibiHash128::<V2>::from_base58("zzzzzzzzzzzzzzzzzzzzzz"),
+            Err(DecodeError::Overflow)
+        );
+        assert_eq!(
+            ChibiHash128::<V2>::from_base58("11111111111111111111111"),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(
+            ChibiHash128::<V2>::from_crockford("8"),
+            Ok(ChibiHash128::new(8))
+        );
+        assert_eq!(
+            ChibiHash128::<V2>::from_crockford("80000000000000000000000000"),
+            Err(DecodeError::Overflow)
+        );
+    }
+}
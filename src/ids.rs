@@ -0,0 +1,366 @@
+//! Cheap, mostly-unique identifiers built on ChibiHash
+//!
+//! - [`RequestId`]: time-sortable IDs mixed with a hash of request attributes.
+//! - [`SnowflakeLite`]: collision-free Snowflake-style IDs, scrambled through
+//!   the [`moremur`](crate::mix::moremur) finalizer.
+//!
+//! [`RequestId`] mixes a coarse timestamp, a per-node seed, a per-generator
+//! counter and a hash of request attributes into a 64-bit or 128-bit
+//! identifier. The timestamp occupies the most significant bits, so IDs sort
+//! by creation time at the timestamp's granularity.
+//!
+//! These are not UUIDs: uniqueness is probabilistic and bounded by the hash
+//! bits left after the timestamp (32 bits for [`RequestId::id64_at`], 80 bits
+//! for [`RequestId::id128_at`]). Use the 128-bit form when many IDs are minted
+//! per second.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::ids::RequestId;
+//!
+//! let mut ids = RequestId::new(0xC0FFEE);
+//! let a = ids.id64_at(1_700_000_000, b"GET /users/42");
+//! let b = ids.id64_at(1_700_000_001, b"GET /users/42");
+//! assert!(a < b);
+//! assert_eq!(a >> 32, 1_700_000_000);
+//! ```
+
+use core::fmt;
+
+use crate::mix::{moremur, moremur_inverse};
+use crate::v2::StreamingChibiHasher;
+
+/// Generator for time-sortable request identifiers
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    node_seed: u64,
+    counter: u64,
+}
+
+impl RequestId {
+    /// Creates a generator for the node identified by `node_seed`
+    ///
+    /// Give every node (process, host) a different seed.
+    pub const fn new(node_seed: u64) -> Self {
+        Self {
+            node_seed,
+            counter: 0,
+        }
+    }
+
+    fn hasher(&mut self, timestamp: u64, attrs: &[u8]) -> StreamingChibiHasher {
+        let mut hasher = StreamingChibiHasher::new(self.node_seed);
+        hasher.update(&timestamp.to_le_bytes());
+        hasher.update(&self.counter.to_le_bytes());
+        hasher.update(attrs);
+        self.counter = self.counter.wrapping_add(1);
+        hasher
+    }
+
+    /// Builds a 64-bit ID: 32 bits of Unix seconds, then 32 hash bits
+    ///
+    /// The seconds field wraps in the year 2106.
+    pub fn id64_at(&mut self, unix_secs: u64, attrs: &[u8]) -> u64 {
+        let hash = self.hasher(unix_secs, attrs).finalize();
+        (unix_secs << 32) | (hash >> 32)
+    }
+
+    /// Builds a 128-bit ID: 48 bits of Unix milliseconds, then 80 hash bits
+    ///
+    /// The milliseconds field wraps in the year 10889.
+    pub fn id128_at(&mut self, unix_millis: u64, attrs: &[u8]) -> u128 {
+        let mut hasher = self.hasher(unix_millis, attrs);
+        let hi = hasher.finalize();
+        hasher.update(&[0xFF]);
+        let lo = hasher.finalize();
+        let random = ((hi as u128) << 64 | lo as u128) & ((1 << 80) - 1);
+        ((unix_millis as u128 & 0xFFFF_FFFF_FFFF) << 80) | random
+    }
+
+    /// Builds a 64-bit ID stamped with the current system time
+    #[cfg(feature = "std")]
+    pub fn next64(&mut self, attrs: &[u8]) -> u64 {
+        self.id64_at(unix_time().as_secs(), attrs)
+    }
+
+    /// Builds a 128-bit ID stamped with the current system time
+    #[cfg(feature = "std")]
+    pub fn next128(&mut self, attrs: &[u8]) -> u128 {
+        self.id128_at(unix_time().as_millis() as u64, attrs)
+    }
+}
+
+const WORKER_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const TIMESTAMP_BITS: u32 = 41;
+const MAX_WORKER: u16 = (1 << WORKER_BITS) - 1;
+const MAX_SEQUENCE: u16 = (1 << SEQUENCE_BITS) - 1;
+const MAX_TIMESTAMP: u64 = (1 << TIMESTAMP_BITS) - 1;
+
+/// Snowflake-style ID generator with scrambled output
+///
+/// A raw ID packs 41 bits of milliseconds since a custom epoch, a 10-bit
+/// worker ID and a 12-bit sequence number. Raw IDs from one generator are
+/// strictly increasing, even if the clock steps backwards or more than 4096
+/// IDs are requested within a millisecond (the generator then borrows time
+/// from the next millisecond). Once the timestamp no longer fits in 41 bits
+/// the generator returns [`EpochExhausted`] instead of wrapping around.
+///
+/// [`next_at`](Self::next_at) passes the raw ID through the bijective
+/// [`moremur`] finalizer, so published IDs are not obviously sequential, yet
+/// stay unique. The scramble is unkeyed and public: anyone can undo it with
+/// [`unscramble`](Self::unscramble) and read the creation time with
+/// [`decode`](Self::decode), so it hides nothing from a determined reader.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::ids::SnowflakeLite;
+///
+/// let mut ids = SnowflakeLite::new(3, 1_600_000_000_000);
+/// let id = ids.next_at(1_700_000_000_000).unwrap();
+/// let raw = SnowflakeLite::unscramble(id);
+/// assert_eq!(SnowflakeLite::decode(raw), (100_000_000_000, 3, 0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnowflakeLite {
+    worker: u16,
+    epoch_ms: u64,
+    last_ms: u64,
+    sequence: u16,
+    started: bool,
+}
+
+impl SnowflakeLite {
+    /// Creates a generator for `worker` with timestamps relative to `epoch_ms`
+    ///
+    /// `epoch_ms` is in Unix milliseconds. The 41-bit timestamp field lasts
+    /// about 69 years past the epoch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `worker` does not fit in 10 bits (is above 1023).
+    pub const fn new(worker: u16, epoch_ms: u64) -> Self {
+        assert!(worker <= MAX_WORKER, "worker ID must fit in 10 bits");
+        Self {
+            worker,
+            epoch_ms,
+            last_ms: 0,
+            sequence: 0,
+            started: false,
+        }
+    }
+
+    /// Returns the next raw, unscrambled ID for Unix time `now_ms`
+    ///
+    /// Fails, leaving the generator unchanged, once `now_ms` or the time
+    /// borrowed from the future is more than 41 bits of milliseconds past
+    /// the epoch.
+    pub fn next_raw_at(&mut self, now_ms: u64) -> Result<u64, EpochExhausted> {
+        let elapsed = now_ms.saturating_sub(self.epoch_ms);
+        if elapsed > MAX_TIMESTAMP {
+            return Err(EpochExhausted);
+        }
+
+        if !self.started || elapsed > self.last_ms {
+            self.started = true;
+            self.last_ms = elapsed;
+            self.sequence = 0;
+        } else if self.sequence == MAX_SEQUENCE {
+            // Sequence exhausted or clock went backwards, borrow from the future
+            if self.last_ms == MAX_TIMESTAMP {
+                return Err(EpochExhausted);
+            }
+            self.last_ms += 1;
+            self.sequence = 0;
+        } else {
+            self.sequence += 1;
+        }
+
+        Ok((self.last_ms << (WORKER_BITS + SEQUENCE_BITS))
+            | ((self.worker as u64) << SEQUENCE_BITS)
+            | self.sequence as u64)
+    }
+
+    /// Returns the next scrambled ID for Unix time `now_ms`
+    pub fn next_at(&mut self, now_ms: u64) -> Result<u64, EpochExhausted> {
+        self.next_raw_at(now_ms).map(Self::scramble)
+    }
+
+    /// Returns the next raw ID stamped with the current system time
+    #[cfg(feature = "std")]
+    pub fn next_raw(&mut self) -> Result<u64, EpochExhausted> {
+        self.next_raw_at(unix_time().as_millis() as u64)
+    }
+
+    /// Returns the next scrambled ID stamped with the current system time
+    #[cfg(feature = "std")]
+    pub fn next_id(&mut self) -> Result<u64, EpochExhausted> {
+        self.next_raw().map(Self::scramble)
+    }
+
+    /// Scrambles a raw ID, see [`moremur`]
+    pub const fn scramble(raw: u64) -> u64 {
+        moremur(raw)
+    }
+
+    /// Recovers the raw ID from a scrambled one
+    pub const fn unscramble(id: u64) -> u64 {
+        moremur_inverse(id)
+    }
+
+    /// Splits a raw ID into `(milliseconds since epoch, worker, sequence)`
+    pub const fn decode(raw: u64) -> (u64, u16, u16) {
+        (
+            raw >> (WORKER_BITS + SEQUENCE_BITS),
+            ((raw >> SEQUENCE_BITS) as u16) & MAX_WORKER,
+            (raw as u16) & MAX_SEQUENCE,
+        )
+    }
+}
+
+/// Error returned by [`SnowflakeLite`] once its 41-bit timestamp field,
+/// about 69 years past the epoch, is used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochExhausted;
+
+impl fmt::Display for EpochExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timestamp no longer fits in 41 bits past the epoch")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EpochExhausted {}
+
+#[cfg(feature = "std")]
+fn unix_time() -> std::time::Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id64_layout() {
+        let mut ids = RequestId::new(1);
+        let id = ids.id64_at(1_700_000_000, b"attrs");
+        assert_eq!(id >> 32, 1_700_000_000);
+    }
+
+    #[test]
+    fn test_id128_layout() {
+        let mut ids = RequestId::new(1);
+        let id = ids.id128_at(1_700_000_000_123, b"attrs");
+        assert_eq!((id >> 80) as u64, 1_700_000_000_123);
+    }
+
+    #[test]
+    fn test_ids_sort_by_time() {
+        let mut ids = RequestId::new(7);
+        let mut last = 0;
+        for secs in 1_000..1_100 {
+            let id = ids.id64_at(secs, b"same");
+            assert!(id > last);
+            last = id;
+        }
+    }
+
+    #[test]
+    fn test_same_attrs_differ() {
+        let mut ids = RequestId::new(7);
+        let a = ids.id128_at(5, b"same");
+        let b = ids.id128_at(5, b"same");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_nodes_differ() {
+        let a = RequestId::new(1).id64_at(5, b"same");
+        let b = RequestId::new(2).id64_at(5, b"same");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_next_uses_current_time() {
+        let mut ids = RequestId::new(0);
+        let before = unix_time().as_secs();
+        let id = ids.next64(b"x");
+        assert!(id >> 32 >= before);
+        assert!(ids.next128(b"x") >> 80 > 0);
+    }
+
+    #[test]
+    fn test_snowflake_layout() {
+        let mut ids = SnowflakeLite::new(1023, 1_000);
+        let raw = ids.next_raw_at(1_005).unwrap();
+        assert_eq!(SnowflakeLite::decode(raw), (5, 1023, 0));
+        let raw = ids.next_raw_at(1_005).unwrap();
+        assert_eq!(SnowflakeLite::decode(raw), (5, 1023, 1));
+    }
+
+    #[test]
+    fn test_snowflake_monotonic() {
+        let mut ids = SnowflakeLite::new(1, 0);
+        let mut last = ids.next_raw_at(100).unwrap();
+        // Same millisecond past the sequence limit, then a clock step back
+        for _ in 0..10_000 {
+            let raw = ids.next_raw_at(100).unwrap();
+            assert!(raw > last);
+            last = raw;
+        }
+        let raw = ids.next_raw_at(50).unwrap();
+        assert!(raw > last);
+    }
+
+    #[test]
+    fn test_snowflake_scramble_roundtrip() {
+        let mut ids = SnowflakeLite::new(7, 0);
+        for now in 0..100 {
+            let raw = ids.next_raw_at(now).unwrap();
+            let id = SnowflakeLite::scramble(raw);
+            assert_ne!(id, raw);
+            assert_eq!(SnowflakeLite::unscramble(id), raw);
+        }
+    }
+
+    #[test]
+    fn test_snowflake_epoch_exhausted() {
+        let mut ids = SnowflakeLite::new(5, 1_000);
+        assert_eq!(
+            ids.next_raw_at(1_000 + MAX_TIMESTAMP + 1),
+            Err(EpochExhausted)
+        );
+
+        // Borrowing past the last millisecond fails instead of wrapping
+        let last = 1_000 + MAX_TIMESTAMP;
+        let mut raw = 0;
+        for _ in 0..=MAX_SEQUENCE {
+            raw = ids.next_raw_at(last).unwrap();
+        }
+        assert_eq!(SnowflakeLite::decode(raw), (MAX_TIMESTAMP, 5, MAX_SEQUENCE));
+        assert_eq!(ids.next_raw_at(last), Err(EpochExhausted));
+        assert_eq!(ids.next_at(last), Err(EpochExhausted));
+        assert_eq!(ids.next_raw_at(0), Err(EpochExhausted));
+    }
+
+    #[test]
+    #[should_panic(expected = "worker ID must fit in 10 bits")]
+    fn test_snowflake_worker_range() {
+        let _ = SnowflakeLite::new(1024, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_snowflake_now() {
+        let mut ids = SnowflakeLite::new(0, 0);
+        let (ms, _, _) = SnowflakeLite::decode(SnowflakeLite::unscramble(ids.next_id().unwrap()));
+        assert!(ms > 0);
+        assert!(ids.next_raw().unwrap() > 0);
+    }
+}
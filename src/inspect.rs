@@ -0,0 +1,192 @@
+//! A `Hasher` wrapper that records the bytes a `Hash` impl writes
+//!
+//! Two values that print the same can still hash differently: a `usize`
+//! versus a `u64` field, a length prefix added by a slice, or the `0xFF`
+//! terminator `str` appends. [`InspectingHasher`] forwards every call to an
+//! inner hasher and records the exact byte stream, so the difference can be
+//! seen directly.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::inspect::inspect;
+//! use std::hash::Hasher;
+//!
+//! let a = inspect(&("ab", "c"));
+//! let b = inspect(&("a", "bc"));
+//! assert_ne!(a.finish(), b.finish());
+//! assert_eq!(a.bytes(), b"ab\xFFc\xFF");
+//!
+//! // One line per `Hasher` call, e.g. `write_u8 ff`
+//! println!("{}", a);
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
+
+use crate::ChibiHasher;
+
+/// One call made on an [`InspectingHasher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteRecord {
+    /// Name of the `Hasher` method, e.g. `"write_u64"`
+    pub method: &'static str,
+    /// Range of [`InspectingHasher::bytes`] written by the call
+    pub bytes: Range<usize>,
+}
+
+/// Hasher that forwards to `H` and records everything written to it
+#[derive(Debug, Clone, Default)]
+pub struct InspectingHasher<H = ChibiHasher> {
+    inner: H,
+    bytes: Vec<u8>,
+    writes: Vec<WriteRecord>,
+}
+
+/// Hashes `value` with the default [`ChibiHasher`] and returns the recording
+pub fn inspect<T: Hash + ?Sized>(value: &T) -> InspectingHasher {
+    let mut hasher = InspectingHasher::new(ChibiHasher::new(0));
+    value.hash(&mut hasher);
+    hasher
+}
+
+impl<H: Hasher> InspectingHasher<H> {
+    /// Wraps `inner`
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            bytes: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Returns all bytes written so far, in order
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the individual calls made so far
+    pub fn writes(&self) -> &[WriteRecord] {
+        &self.writes
+    }
+
+    /// Returns a reference to the inner hasher
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    /// Unwraps the inner hasher
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    fn record(&mut self, method: &'static str, bytes: &[u8]) {
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(bytes);
+        self.writes.push(WriteRecord {
+            method,
+            bytes: start..self.bytes.len(),
+        });
+    }
+}
+
+macro_rules! forward_write {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(&mut self, i: $ty) {
+                self.record(stringify!($method), &i.to_ne_bytes());
+                self.inner.$method(i);
+            }
+        )*
+    };
+}
+
+impl<H: Hasher> Hasher for InspectingHasher<H> {
+    fn finish(&self) -> u64 {
+        self.inner.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.record("write", bytes);
+        self.inner.write(bytes);
+    }
+
+    forward_write! {
+        write_u8(u8),
+        write_u16(u16),
+        write_u32(u32),
+        write_u64(u64),
+        write_u128(u128),
+        write_usize(usize),
+        write_i8(i8),
+        write_i16(i16),
+        write_i32(i32),
+        write_i64(i64),
+        write_i128(i128),
+        write_isize(isize),
+    }
+}
+
+impl<H: Hasher> fmt::Display for InspectingHasher<H> {
+    /// Lists each call with its bytes in hex, then the final hash
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for write in &self.writes {
+            write!(f, "{:<12}", write.method)?;
+            for byte in &self.bytes[write.bytes.clone()] {
+                write!(f, " {:02x}", byte)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "finish       {:016x}", self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_matches_inner_hasher() {
+        let value = (1u32, "key", [7u8; 3]);
+        let mut plain = ChibiHasher::new(0);
+        value.hash(&mut plain);
+        assert_eq!(inspect(&value).finish(), plain.finish());
+    }
+
+    #[test]
+    fn test_records_calls() {
+        let hasher = inspect(&(1u16, 2u8));
+        assert_eq!(hasher.bytes()[..2], 1u16.to_ne_bytes());
+        assert_eq!(hasher.bytes()[2], 2);
+        assert_eq!(
+            hasher.writes(),
+            [
+                WriteRecord {
+                    method: "write_u16",
+                    bytes: 0..2
+                },
+                WriteRecord {
+                    method: "write_u8",
+                    bytes: 2..3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let text = inspect(&0xABu8).to_string();
+        assert!(text.starts_with("write_u8     ab\n"), "{}", text);
+        assert!(text.contains("finish"));
+    }
+}
@@ -0,0 +1,345 @@
+//! `std::io` adapters that hash data as it streams through
+//!
+//! - [`VerifyingReader`]: checks the stream against an expected digest and
+//!   fails the final read on mismatch.
+//! - [`HashReader`]: hashes everything read through it from an inner
+//!   reader.
+//! - [`HashWriter`]: hashes everything written through it to an inner
+//!   writer.
+//!
+//! The adapters are generic over the hasher and default to the `v2`
+//! [`StreamingChibiHasher`](crate::v2::StreamingChibiHasher).
+
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+
+use crate::v2;
+
+/// A reader that verifies the bytes it yields against an expected digest
+///
+/// Bytes are hashed as they are read. When the inner reader reports EOF the
+/// digest is compared with the expected one; on mismatch the read returns an
+/// [`io::ErrorKind::InvalidData`] error instead of `Ok(0)`, and every later
+/// read fails the same way. Code that reads to the end (e.g.
+/// [`Read::read_to_end`] or [`io::copy`]) therefore fails closed on
+/// corrupted input.
+///
+/// Data is handed out before it is verified, so callers must discard what
+/// they read when an error is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::io::VerifyingReader;
+/// use std::io::Read;
+///
+/// let data = b"cached blob";
+/// let expected = chibihash::v2::chibi_hash64(data, 0);
+///
+/// let mut reader = VerifyingReader::new(&data[..], expected, 0);
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out).unwrap();
+///
+/// let mut reader = VerifyingReader::new(&b"corrupted"[..], expected, 0);
+/// assert!(reader.read_to_end(&mut Vec::new()).is_err());
+/// ```
+#[derive(Debug)]
+pub struct VerifyingReader<R, H = v2::StreamingChibiHasher> {
+    inner: R,
+    hasher: H,
+    expected: u64,
+    poisoned: bool,
+}
+
+impl<R: Read> VerifyingReader<R> {
+    /// Wraps `inner`, expecting the `v2` digest `expected` under `seed`
+    pub fn new(inner: R, expected: u64, seed: u64) -> Self {
+        Self::with_hasher(inner, expected, v2::StreamingChibiHasher::new(seed))
+    }
+}
+
+impl<R: Read, H: Hasher> VerifyingReader<R, H> {
+    /// Wraps `inner`, expecting `hasher` to finish with `expected`
+    pub fn with_hasher(inner: R, expected: u64, hasher: H) -> Self {
+        Self {
+            inner,
+            hasher,
+            expected,
+            poisoned: false,
+        }
+    }
+
+    /// Returns the digest of the bytes read so far
+    pub fn digest(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn mismatch(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "digest mismatch: expected {:016x}, got {:016x}",
+                self.expected,
+                self.hasher.finish()
+            ),
+        )
+    }
+}
+
+impl<R: Read, H: Hasher> Read for VerifyingReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.poisoned {
+            return Err(self.mismatch());
+        }
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.hasher.write(&buf[..n]);
+        } else if !buf.is_empty() && self.hasher.finish() != self.expected {
+            self.poisoned = true;
+            return Err(self.mismatch());
+        }
+        Ok(n)
+    }
+}
+
+/// A reader that hashes every byte it reads from the inner reader
+///
+/// Unlike [`VerifyingReader`] it only computes the digest; check it once
+/// the reader reports EOF.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::io::HashReader;
+/// use std::io::Read;
+///
+/// let mut reader = HashReader::new(&b"downloaded bytes"[..], 0);
+/// let mut out = Vec::new();
+/// reader.read_to_end(&mut out).unwrap();
+/// assert_eq!(reader.finalize(), chibihash::v2::chibi_hash64(&out, 0));
+/// ```
+#[derive(Debug)]
+pub struct HashReader<R, H = v2::StreamingChibiHasher> {
+    inner: R,
+    hasher: H,
+}
+
+impl<R: Read> HashReader<R> {
+    /// Wraps `inner`, hashing with `v2` under `seed`
+    pub fn new(inner: R, seed: u64) -> Self {
+        Self::with_hasher(inner, v2::StreamingChibiHasher::new(seed))
+    }
+}
+
+impl<R: Read, H: Hasher> HashReader<R, H> {
+    /// Wraps `inner`, feeding read bytes to `hasher`
+    pub fn with_hasher(inner: R, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Returns the digest of the bytes read so far
+    pub fn finalize(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader
+    ///
+    /// Bytes read directly from it are not hashed.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Unwraps the inner reader along with the digest so far
+    pub fn into_parts(self) -> (R, u64) {
+        let digest = self.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<R: Read, H: Hasher> Read for HashReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.write(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// A writer that hashes every byte it passes to the inner writer
+///
+/// Only bytes the inner writer accepts are hashed, so after a short write
+/// or an error the digest still covers exactly what was written.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::io::HashWriter;
+/// use std::io::Write;
+///
+/// let mut writer = HashWriter::new(Vec::new(), 0);
+/// writer.write_all(b"file contents").unwrap();
+/// let (out, digest) = writer.into_parts();
+/// assert_eq!(digest, chibihash::v2::chibi_hash64(&out, 0));
+/// ```
+#[derive(Debug)]
+pub struct HashWriter<W, H = v2::StreamingChibiHasher> {
+    inner: W,
+    hasher: H,
+}
+
+impl<W: Write> HashWriter<W> {
+    /// Wraps `inner`, hashing with `v2` under `seed`
+    pub fn new(inner: W, seed: u64) -> Self {
+        Self::with_hasher(inner, v2::StreamingChibiHasher::new(seed))
+    }
+}
+
+impl<W: Write, H: Hasher> HashWriter<W, H> {
+    /// Wraps `inner`, feeding written bytes to `hasher`
+    pub fn with_hasher(inner: W, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Returns the digest of the bytes written so far
+    pub fn finalize(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer
+    ///
+    /// Bytes written directly to it are not hashed.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Unwraps the inner writer along with the final digest
+    pub fn into_parts(self) -> (W, u64) {
+        let digest = self.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<W: Write, H: Hasher> Write for HashWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.write(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verifying_reader_ok() {
+        let data = vec![7u8; 1000];
+        let expected = v2::chibi_hash64(&data, 3);
+        let mut reader = VerifyingReader::new(&data[..], expected, 3);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        assert_eq!(reader.digest(), expected);
+    }
+
+    #[test]
+    fn test_verifying_reader_mismatch() {
+        let data = vec![7u8; 1000];
+        let expected = v2::chibi_hash64(&data, 3);
+        let mut reader = VerifyingReader::new(&data[..999], expected, 3);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Stays poisoned
+        let err = reader.read(&mut [0u8; 8]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_verifying_reader_copy() {
+        let data = b"hello world".repeat(100);
+        let expected = v2::chibi_hash64(&data, 0) ^ 1;
+        let mut reader = VerifyingReader::new(&data[..], expected, 0);
+        assert!(io::copy(&mut reader, &mut io::sink()).is_err());
+    }
+
+    #[test]
+    fn test_verifying_reader_custom_hasher() {
+        let data = b"hello";
+        let expected = crate::v1::chibi_hash64(data, 0);
+        let hasher = crate::v1::StreamingChibiHasher::new(0);
+        let mut reader = VerifyingReader::with_hasher(&data[..], expected, hasher);
+        reader.read_to_end(&mut Vec::new()).unwrap();
+        assert_eq!(reader.into_inner(), b"");
+    }
+
+    #[test]
+    fn test_hash_writer() {
+        let data = b"hello world".repeat(10);
+        let mut writer = HashWriter::new(Vec::new(), 5);
+        io::copy(&mut &data[..], &mut writer).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.finalize(), v2::chibi_hash64(&data, 5));
+        assert_eq!(writer.into_inner(), data);
+    }
+
+    #[test]
+    fn test_hash_writer_short_write() {
+        // A fixed-size buffer accepts only what fits
+        let mut buf = [0u8; 4];
+        let mut writer = HashWriter::new(&mut buf[..], 0);
+        assert_eq!(writer.write(b"abcdef").unwrap(), 4);
+        assert_eq!(writer.finalize(), v2::chibi_hash64(b"abcd", 0));
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        let data = b"hello world".repeat(10);
+        let mut reader = HashReader::new(&data[..], 5);
+        let mut out = Vec::new();
+        io::copy(&mut reader, &mut out).unwrap();
+        assert_eq!(out, data);
+        assert_eq!(reader.finalize(), v2::chibi_hash64(&data, 5));
+
+        let hasher = crate::v1::StreamingChibiHasher::new(0);
+        let mut reader = HashReader::with_hasher(&data[..], hasher);
+        let mut buf = [0u8; 5];
+        reader.read_exact(&mut buf).unwrap();
+        let (rest, digest) = reader.into_parts();
+        assert_eq!(digest, crate::v1::chibi_hash64(b"hello", 0));
+        assert_eq!(rest.len(), data.len() - 5);
+    }
+}
@@ -1,9 +1,129 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
 
-// Default version is `v1` to ensure backwards compatibility
-pub use v1::{chibi_hash64, ChibiHasher, StreamingChibiHasher};
-#[cfg(any(feature = "std", feature = "hashbrown"))]
+// Default version is `v1` to ensure backwards compatibility; the `default-v2`
+// feature switches the root re-exports to `v2`. Feature unification applies it
+// to every dependent, so only final binaries should enable it
+#[cfg(not(feature = "default-v2"))]
+pub use v1::{
+    chibi_hash32, chibi_hash64, chibi_hash64_const, chibi_hash64_multi, hash_one, hash_with_domain,
+    Chibi32Hasher, ChibiBuildHasher, ChibiCollectionHasher, ChibiDefaultHasher, ChibiHasher,
+    ChibiXof, StreamingChibiHasher,
+};
+#[cfg(all(
+    not(feature = "default-v2"),
+    any(feature = "std", feature = "hashbrown")
+))]
 pub use v1::{ChibiHashMap, ChibiHashSet};
 
+#[cfg(all(not(feature = "default-v2"), feature = "lru"))]
+pub use v1::ChibiLruCache;
+#[cfg(all(not(feature = "default-v2"), feature = "random"))]
+pub use v1::ChibiRandomState;
+#[cfg(all(not(feature = "default-v2"), feature = "dashmap"))]
+pub use v1::{ChibiDashMap, ChibiDashSet};
+#[cfg(all(
+    not(feature = "default-v2"),
+    feature = "random",
+    any(feature = "std", feature = "hashbrown")
+))]
+pub use v1::{ChibiHashMapRandom, ChibiHashSetRandom};
+#[cfg(all(not(feature = "default-v2"), feature = "heapless"))]
+pub use v1::{ChibiHeaplessMap, ChibiHeaplessSet};
+#[cfg(all(not(feature = "default-v2"), feature = "indexmap"))]
+pub use v1::{ChibiIndexMap, ChibiIndexSet};
+
+#[cfg(feature = "default-v2")]
+pub use v2::{
+    chibi_hash32, chibi_hash64, chibi_hash64_const, chibi_hash64_multi, hash_one, hash_with_domain,
+    Chibi32Hasher, ChibiBuildHasher, ChibiCollectionHasher, ChibiDefaultHasher, ChibiHasher,
+    ChibiXof, StreamingChibiHasher,
+};
+#[cfg(all(feature = "default-v2", any(feature = "std", feature = "hashbrown")))]
+pub use v2::{ChibiHashMap, ChibiHashSet};
+
+#[cfg(all(feature = "default-v2", feature = "lru"))]
+pub use v2::ChibiLruCache;
+#[cfg(all(feature = "default-v2", feature = "random"))]
+pub use v2::ChibiRandomState;
+#[cfg(all(feature = "default-v2", feature = "dashmap"))]
+pub use v2::{ChibiDashMap, ChibiDashSet};
+#[cfg(all(
+    feature = "default-v2",
+    feature = "random",
+    any(feature = "std", feature = "hashbrown")
+))]
+pub use v2::{ChibiHashMapRandom, ChibiHashSetRandom};
+#[cfg(all(feature = "default-v2", feature = "heapless"))]
+pub use v2::{ChibiHeaplessMap, ChibiHeaplessSet};
+#[cfg(all(feature = "default-v2", feature = "indexmap"))]
+pub use v2::{ChibiIndexMap, ChibiIndexSet};
+
+pub use ext::{ChibiHashExt, IteratorHashExt};
+pub use hash::{ChibiHash128, ChibiHash64};
+pub use seed::{mix_seeds, Seed};
+
+#[macro_use]
+mod macros;
+mod math;
+
+pub mod algorithm;
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod blocks;
+pub mod cdc;
+pub mod collections;
+#[cfg(feature = "digest")]
+pub mod digest;
+#[cfg(feature = "embedded-bench")]
+pub mod embedded_bench;
+pub mod encoding;
+pub mod experiment;
+pub mod ext;
+#[cfg(feature = "mmap")]
+pub mod file;
+pub mod filters;
+#[cfg(feature = "std")]
+pub mod framed;
+#[cfg(feature = "futures")]
+pub mod futures;
+pub mod generic;
+pub mod hash;
+pub mod ids;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod manifest;
+pub mod merkle;
+pub mod mix;
+pub mod net;
+pub mod partition;
+pub mod permute;
+pub mod phf;
+pub mod probe;
+#[cfg(feature = "random")]
+pub mod random;
+pub mod range;
+#[cfg(feature = "rng")]
+pub mod rng;
+pub mod sample;
+pub mod seed;
+pub mod sequence;
+pub mod shingle;
+pub mod shuffle;
+pub mod sketch;
+pub mod state;
+pub mod tree;
 pub mod v1;
 pub mod v2;
+pub mod version;
+pub mod visual;
+
+#[cfg(feature = "smhasher")]
+pub mod smhasher;
+
+#[cfg(feature = "tokio-util")]
+pub mod codec;
+
+#[cfg(feature = "debug")]
+pub mod inspect;
@@ -0,0 +1,227 @@
+/// Hashes a string at compile time with the default version
+///
+/// `chibi_hash!(key)` uses seed 0 and `chibi_hash!(key, seed)` a given seed.
+/// `key` must be a `&str` constant expression, typically a literal. The
+/// result is a `u64` constant equal to `chibi_hash64(key.as_bytes(), seed)`,
+/// so nothing is hashed at runtime.
+///
+/// Bind the result to a `const` to use it as a `match` pattern. For `v2` or
+/// byte strings, call [`v2::chibi_hash64_const`](crate::v2::chibi_hash64_const)
+/// directly.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::{chibi_hash, chibi_hash64};
+///
+/// const GET: u64 = chibi_hash!("GET");
+/// const POST: u64 = chibi_hash!("POST");
+///
+/// fn method(name: &str) -> Option<u8> {
+///     match chibi_hash64(name.as_bytes(), 0) {
+///         GET => Some(0),
+///         POST => Some(1),
+///         _ => None,
+///     }
+/// }
+///
+/// assert_eq!(method("POST"), Some(1));
+/// assert_eq!(chibi_hash!("GET", 7), chibi_hash64(b"GET", 7));
+/// ```
+#[macro_export]
+macro_rules! chibi_hash {
+    ($key:expr $(,)?) => {
+        $crate::chibi_hash!($key, 0)
+    };
+    ($key:expr, $seed:expr $(,)?) => {
+        const { $crate::chibi_hash64_const(<str>::as_bytes($key), $seed) }
+    };
+}
+
+/// Builds a perfect hash [`phf::Map`](crate::phf::Map) at compile time
+///
+/// Keys are `&str` constant expressions, typically literals, and values are
+/// any constant expressions. The result has type
+/// `phf::Map<&'static str, V>` and can initialize a `static`. Duplicate keys
+/// are a compile error.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::{chibi_phf_map, phf::Map};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Method {
+///     Get,
+///     Post,
+/// }
+///
+/// static METHODS: Map<&'static str, Method> = chibi_phf_map! {
+///     "GET" => Method::Get,
+///     "POST" => Method::Post,
+/// };
+///
+/// assert_eq!(METHODS.get("GET"), Some(&Method::Get));
+/// assert_eq!(METHODS.get("PATCH"), None);
+/// ```
+///
+/// Duplicate keys fail to compile:
+///
+/// ```rust,compile_fail
+/// static DUP: chibihash::phf::Map<&'static str, u8> = chibihash::chibi_phf_map! {
+///     "a" => 1,
+///     "a" => 2,
+/// };
+/// ```
+#[macro_export]
+macro_rules! chibi_phf_map {
+    ($($key:expr => $value:expr),* $(,)?) => {{
+        const N: usize = <[&str]>::len(&[$($key),*]);
+        const KEYS: [&[u8]; N] = [$(<str>::as_bytes($key)),*];
+        const TABLE: $crate::phf::ConstGenerated<N> = $crate::phf::generate_const(&KEYS);
+        $crate::phf::Map {
+            seed: TABLE.seed,
+            disps: &TABLE.disps,
+            slots: &TABLE.slots,
+            entries: &[$(($key, $value)),*],
+        }
+    }};
+}
+
+/// Builds a perfect hash [`phf::Set`](crate::phf::Set) at compile time
+///
+/// Elements are `&str` constant expressions. The result has type
+/// `phf::Set<&'static str>` and can initialize a `static`. Duplicate
+/// elements are a compile error.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::{chibi_phf_set, phf::Set};
+///
+/// static KEYWORDS: Set<&'static str> = chibi_phf_set! { "fn", "let", "match" };
+///
+/// assert!(KEYWORDS.contains("let"));
+/// assert!(!KEYWORDS.contains("var"));
+/// ```
+#[macro_export]
+macro_rules! chibi_phf_set {
+    ($($key:expr),* $(,)?) => {
+        $crate::phf::Set {
+            map: $crate::chibi_phf_map!($($key => ()),*),
+        }
+    };
+}
+
+/// Hashes formatted text with the default version, without allocating
+///
+/// `hash_fmt!(seed, "{}/{}", a, b)` returns the same `u64` as
+/// `chibi_hash64(format!("{}/{}", a, b).as_bytes(), seed)`, but writes the
+/// formatted pieces straight into a
+/// [`StreamingChibiHasher`](crate::StreamingChibiHasher). If a `Display`
+/// impl returns an error, the hash covers the text written before it.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::{chibi_hash64, hash_fmt};
+///
+/// let (user_id, ts) = (42, 1700000000);
+/// let key = hash_fmt!(0, "{}:{}", user_id, ts);
+/// assert_eq!(key, chibi_hash64(b"42:1700000000", 0));
+/// ```
+#[macro_export]
+macro_rules! hash_fmt {
+    ($seed:expr, $($arg:tt)+) => {{
+        let mut hasher = $crate::StreamingChibiHasher::new($seed);
+        // Only a failing `Display` impl can make this return an error
+        let _ = ::core::fmt::Write::write_fmt(&mut hasher, ::core::format_args!($($arg)+));
+        hasher.finalize()
+    }};
+}
+
+// Implements the integer `Hasher` methods through the implementor's
+// `write_fixed`, feeding the same native-endian bytes as the default
+// implementations so hash values do not change
+macro_rules! int_write_fast_paths {
+    () => {
+        int_write_fast_paths! {
+            write_u8: u8,
+            write_u16: u16,
+            write_u32: u32,
+            write_u64: u64,
+            write_u128: u128,
+            write_usize: usize,
+            write_i8: i8,
+            write_i16: i16,
+            write_i32: i32,
+            write_i64: i64,
+            write_i128: i128,
+            write_isize: isize
+        }
+    };
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            #[inline]
+            fn $method(&mut self, i: $ty) {
+                self.write_fixed(i.to_ne_bytes());
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_chibi_hash_macro() {
+        const EMPTY: u64 = chibi_hash!("");
+        #[cfg(not(feature = "default-v2"))]
+        assert_eq!(EMPTY, 0x9EA80F3B18E26CFB);
+        assert_eq!(EMPTY, crate::chibi_hash64(b"", 0));
+        assert_eq!(chibi_hash!("hi", 3), crate::chibi_hash64(b"hi", 3));
+
+        const SEED: u64 = 99;
+        static TABLE: [u64; 2] = [chibi_hash!("a", SEED), chibi_hash!("b", SEED)];
+        assert_eq!(TABLE[1], crate::chibi_hash64(b"b", SEED));
+    }
+
+    #[test]
+    fn test_chibi_phf_map() {
+        static MAP: crate::phf::Map<&str, u32> = chibi_phf_map! {
+            "one" => 1,
+            "two" => 2,
+            "three" => 3,
+        };
+        assert_eq!(MAP.len(), 3);
+        assert_eq!(MAP.get("two"), Some(&2));
+        assert_eq!(MAP.get("four"), None);
+
+        // Also usable as a local
+        let local = chibi_phf_map! { "x" => 'x' };
+        assert_eq!(local.get("x"), Some(&'x'));
+
+        let empty: crate::phf::Map<&str, u8> = chibi_phf_map! {};
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_chibi_phf_set() {
+        static SET: crate::phf::Set<&str> = chibi_phf_set!["a", "b"];
+        assert!(SET.contains("a"));
+        assert!(!SET.contains("c"));
+    }
+
+    #[test]
+    fn test_hash_fmt() {
+        let (a, b) = ("users", 7);
+        assert_eq!(
+            hash_fmt!(3, "{}/{}", a, b),
+            crate::chibi_hash64(b"users/7", 3)
+        );
+        assert_eq!(hash_fmt!(0, "plain"), crate::chibi_hash64(b"plain", 0));
+        assert_eq!(
+            hash_fmt!(0, "{a}-{b:03}"),
+            crate::chibi_hash64(b"users-007", 0)
+        );
+    }
+}
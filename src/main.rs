@@ -0,0 +1,803 @@
+// `chibihash`: prints ChibiHash digests of files, like `sha256sum`
+//
+// Run `chibihash --help` for usage. By default digests are printed in the
+// GNU coreutils layout: 16 lowercase hex digits of the 64-bit hash, two
+// spaces and the file name. As in coreutils, a name containing `\`, a
+// newline or a carriage return is escaped and its line starts with `\`.
+
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chibihash::manifest::{Manifest, ManifestOptions};
+use chibihash::version::{Version, VersionedStreamingHasher};
+use chibihash::{v1, v2};
+
+const USAGE: &str = "\
+Usage: chibihash [OPTIONS] [FILE]...
+       chibihash bench [BENCH OPTIONS]
+       chibihash diff [-s SEED] [-a ALG] [-j N] A B
+
+Prints the ChibiHash digest of each FILE. With no FILE, or when FILE is -,
+reads standard input. With --recursive, every file under a directory FILE is
+hashed, in sorted order; symbolic links inside directories are skipped.
+
+Options:
+  -s, --seed <SEED>       Seed, decimal or 0x-prefixed hex [default: 0]
+  -a, --algorithm <ALG>   Algorithm version, v1 or v2 [default: v2]
+  -r, --recursive         Hash the files in directories and their subdirectories
+  -j, --jobs <N>          Files to hash in parallel [default: number of CPUs]
+  -c, --check             Read digests from the FILEs and check them
+      --quiet             With --check, don't print OK for each file
+  -f, --format <FORMAT>   Output format: gnu, bsd, json or json-lines [default: gnu]
+  -z, --zero              End output lines with NUL instead of newline; with
+                          --check, read NUL-terminated lines
+  -h, --help              Print this help
+  -V, --version           Print the version
+
+`chibihash bench` measures ns/hash and throughput on this machine for a range
+of input sizes, from 8 bytes to 1 MiB. To hash a file named bench or diff,
+pass it as ./bench or ./diff.
+
+Bench options:
+      --size <N>          Measure only inputs of N bytes; accepts K and M suffixes
+  -a, --algorithm <ALG>   Measure only v1 or v2 [default: both]
+      --time <MS>         Milliseconds to measure each size for [default: 200]
+
+`chibihash diff A B` compares two files, or two directory trees file by file,
+and lists files that changed, are missing from B, or were added in B. Trees
+are compared as `chibihash::manifest` manifests, which hash with v2; -a and
+-j apply to comparing two files. Exits with 0 if they match, 1 if they
+differ and 2 on errors.
+";
+
+const BENCH_SIZES: [usize; 9] = [8, 16, 32, 64, 256, 1024, 4096, 65536, 1 << 20];
+
+// Tag of the BSD output format, e.g. `CHIBIHASH64-V2 (file) = ...`
+fn bsd_tag(version: Version) -> &'static str {
+    match version {
+        Version::V1 => "CHIBIHASH64-V1",
+        Version::V2 => "CHIBIHASH64-V2",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Gnu,
+    Bsd,
+    Json,
+    JsonLines,
+}
+
+#[derive(Debug)]
+struct Options {
+    seed: u64,
+    algorithm: Version,
+    check: bool,
+    quiet: bool,
+    recursive: bool,
+    jobs: usize,
+    format: Format,
+    zero: bool,
+    files: Vec<String>,
+}
+
+#[derive(Debug)]
+struct BenchOptions {
+    sizes: Vec<usize>,
+    algorithms: Vec<Version>,
+    time: Duration,
+}
+
+enum Command {
+    Hash(Options),
+    Bench(BenchOptions),
+    Diff(Options),
+    Help,
+    Version,
+}
+
+fn parse_seed(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid seed '{}'", s))
+}
+
+fn parse_algorithm(s: &str) -> Result<Version, String> {
+    s.parse()
+        .map_err(|_| format!("invalid algorithm '{}', expected v1 or v2", s))
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    match s {
+        "gnu" => Ok(Format::Gnu),
+        "bsd" => Ok(Format::Bsd),
+        "json" => Ok(Format::Json),
+        "json-lines" => Ok(Format::JsonLines),
+        _ => Err(format!(
+            "invalid format '{}', expected gnu, bsd, json or json-lines",
+            s
+        )),
+    }
+}
+
+// Parses a byte count such as `64`, `4K` or `1M`
+fn parse_size(s: &str) -> Result<usize, String> {
+    let (digits, unit) = match s.as_bytes().last() {
+        Some(b'k' | b'K') => (&s[..s.len() - 1], 1 << 10),
+        Some(b'm' | b'M') => (&s[..s.len() - 1], 1 << 20),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("invalid size '{}'", s))
+}
+
+fn parse_bench_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut options = BenchOptions {
+        sizes: BENCH_SIZES.to_vec(),
+        algorithms: Version::ALL.to_vec(),
+        time: Duration::from_millis(200),
+    };
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for '{}'", name))
+        };
+        match name {
+            "--size" => options.sizes = vec![parse_size(&value()?)?],
+            "-a" | "--algorithm" => options.algorithms = vec![parse_algorithm(&value()?)?],
+            "--time" => {
+                let time = value()?;
+                let ms = match time.parse() {
+                    Ok(ms) if ms > 0 => ms,
+                    _ => return Err(format!("invalid time '{}'", time)),
+                };
+                options.time = Duration::from_millis(ms);
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => return Err(format!("unknown bench option '{}'", arg)),
+        }
+    }
+    Ok(Command::Bench(options))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut args = args.by_ref().peekable();
+    if args.peek().map(String::as_str) == Some("bench") {
+        args.next();
+        return parse_bench_args(args);
+    }
+    let diff = args.peek().map(String::as_str) == Some("diff");
+    if diff {
+        args.next();
+    }
+
+    let mut options = Options {
+        seed: 0,
+        algorithm: Version::V2,
+        check: false,
+        quiet: false,
+        recursive: false,
+        jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        format: Format::Gnu,
+        zero: false,
+        files: Vec::new(),
+    };
+    let mut only_files = false;
+
+    while let Some(arg) = args.next() {
+        if only_files || arg == "-" || !arg.starts_with('-') {
+            options.files.push(arg);
+            continue;
+        }
+        // `--name=value` or `--name value`
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for '{}'", name))
+        };
+        match name {
+            "--" => only_files = true,
+            "-s" | "--seed" => options.seed = parse_seed(&value()?)?,
+            "-a" | "--algorithm" => options.algorithm = parse_algorithm(&value()?)?,
+            "-r" | "--recursive" => options.recursive = true,
+            "-j" | "--jobs" => {
+                let jobs = value()?;
+                options.jobs = match jobs.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid number of jobs '{}'", jobs)),
+                };
+            }
+            "-c" | "--check" => options.check = true,
+            "--quiet" => options.quiet = true,
+            "-f" | "--format" => options.format = parse_format(&value()?)?,
+            "-z" | "--zero" => options.zero = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    if diff {
+        if options.files.len() != 2 {
+            return Err("diff takes exactly two paths".to_string());
+        }
+        return Ok(Command::Diff(options));
+    }
+    if options.files.is_empty() {
+        options.files.push("-".to_string());
+    }
+    Ok(Command::Hash(options))
+}
+
+// Hashes everything `reader` yields, without holding it in memory
+fn hash_reader<R: Read>(mut reader: R, seed: u64, algorithm: Version) -> io::Result<u64> {
+    let mut hasher = VersionedStreamingHasher::new(algorithm, seed);
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+fn hash_path<P: AsRef<Path>>(path: P, seed: u64, algorithm: Version) -> io::Result<u64> {
+    let path = path.as_ref();
+    if path == Path::new("-") {
+        hash_reader(io::stdin().lock(), seed, algorithm)
+    } else {
+        hash_reader(File::open(path)?, seed, algorithm)
+    }
+}
+
+// Appends the regular files under `dir` to `files`, sorted by name at every
+// level so the output order does not depend on the file system
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+// Hashes every path on up to `jobs` threads, returning results in order
+fn hash_all(paths: &[PathBuf], options: &Options) -> Vec<io::Result<u64>> {
+    let hash = |path: &PathBuf| hash_path(path, options.seed, options.algorithm);
+    let jobs = options.jobs.min(paths.len());
+    if jobs <= 1 {
+        return paths.iter().map(hash).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<io::Result<u64>>>> =
+        paths.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(i) else { break };
+                *results[i].lock().unwrap() = Some(hash(path));
+            });
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.into_inner().unwrap().unwrap())
+        .collect()
+}
+
+// Writes `s` as a JSON string literal
+fn write_json_string(out: &mut impl Write, s: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+// Escapes `\`, newline and carriage return in a file name as coreutils
+// does; a line whose name was escaped starts with a `\`
+fn escape_path(path: &str) -> Option<String> {
+    if !path.contains(['\\', '\n', '\r']) {
+        return None;
+    }
+    let mut escaped = String::with_capacity(path.len() + 2);
+    for c in path.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
+
+// Reverses `escape_path`, returning None for an unknown escape
+fn unescape_path(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+// Returns the line prefix and the name to print for `path`; NUL-terminated
+// output is never escaped
+fn display_path(path: &str, zero: bool) -> (&'static str, Cow<'_, str>) {
+    match escape_path(path).filter(|_| !zero) {
+        Some(escaped) => ("\\", Cow::Owned(escaped)),
+        None => ("", Cow::Borrowed(path)),
+    }
+}
+
+// Prints one record per file in the selected format
+struct Printer<'a, W> {
+    out: W,
+    options: &'a Options,
+    records: usize,
+}
+
+impl<'a, W: Write> Printer<'a, W> {
+    fn new(out: W, options: &'a Options) -> Self {
+        Printer {
+            out,
+            options,
+            records: 0,
+        }
+    }
+
+    fn end_line(&mut self) -> io::Result<()> {
+        self.out
+            .write_all(if self.options.zero { b"\0" } else { b"\n" })
+    }
+
+    // Hashing errors appear only in the JSON formats; the others leave
+    // them to standard error, like coreutils
+    fn record(&mut self, path: &str, result: Result<u64, &io::Error>) -> io::Result<()> {
+        let options = self.options;
+        match (options.format, result) {
+            (Format::Gnu, Ok(hash)) => {
+                let (prefix, path) = display_path(path, options.zero);
+                write!(self.out, "{}{:016x}  {}", prefix, hash, path)?;
+                self.end_line()?;
+            }
+            (Format::Bsd, Ok(hash)) => {
+                let (prefix, path) = display_path(path, options.zero);
+                write!(
+                    self.out,
+                    "{}{} ({}) = {:016x}",
+                    prefix,
+                    bsd_tag(options.algorithm),
+                    path,
+                    hash
+                )?;
+                self.end_line()?;
+            }
+            (Format::Gnu | Format::Bsd, Err(_)) => {}
+            (Format::Json | Format::JsonLines, result) => {
+                if options.format == Format::Json {
+                    let sep = if self.records == 0 { "[" } else { "," };
+                    write!(self.out, "{}\n  ", sep)?;
+                }
+                self.out.write_all(b"{\"path\":")?;
+                write_json_string(&mut self.out, path)?;
+                write!(
+                    self.out,
+                    ",\"algorithm\":\"{}\",\"seed\":{},",
+                    options.algorithm, options.seed
+                )?;
+                match result {
+                    Ok(hash) => write!(self.out, "\"hash\":\"{:016x}\"}}", hash)?,
+                    Err(err) => {
+                        self.out.write_all(b"\"error\":")?;
+                        write_json_string(&mut self.out, &err.to_string())?;
+                        self.out.write_all(b"}")?;
+                    }
+                }
+                if options.format == Format::JsonLines {
+                    self.end_line()?;
+                }
+            }
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if self.options.format == Format::Json {
+            let close = if self.records == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(close.as_bytes())?;
+        }
+        self.out.flush()
+    }
+}
+
+fn run(options: &Options) -> io::Result<bool> {
+    let mut ok = true;
+    let mut paths = Vec::new();
+    for file in &options.files {
+        let path = PathBuf::from(file);
+        if options.recursive && file != "-" && path.is_dir() {
+            if let Err(err) = walk(&path, &mut paths) {
+                eprintln!("chibihash: {}: {}", file, err);
+                ok = false;
+            }
+        } else {
+            paths.push(path);
+        }
+    }
+
+    let mut printer = Printer::new(io::stdout().lock(), options);
+    for (path, result) in paths.iter().zip(hash_all(&paths, options)) {
+        let path = path.display().to_string();
+        if let Err(err) = &result {
+            eprintln!("chibihash: {}: {}", path, err);
+            ok = false;
+        }
+        printer.record(&path, result.as_ref().copied())?;
+    }
+    printer.finish()?;
+    Ok(ok)
+}
+
+fn parse_digest(digest: &str) -> Option<u64> {
+    if digest.len() != 16 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digest, 16).ok()
+}
+
+// Parses a GNU `<digest>  <path>` line, or a BSD `<TAG> (<path>) = <digest>`
+// line, whose tag selects the algorithm. `*` in place of the second space of
+// a GNU line, which coreutils writes for binary mode, is accepted too, as is
+// a leading `\` marking an escaped name.
+fn parse_check_line(line: &str, zero: bool) -> Option<(u64, Cow<'_, str>, Option<Version>)> {
+    let (line, escaped) = match line.strip_prefix('\\').filter(|_| !zero) {
+        Some(line) => (line, true),
+        None => (line, false),
+    };
+    let (digest, path, algorithm) = parse_unescaped_line(line)?;
+    let path = if escaped {
+        Cow::Owned(unescape_path(path)?)
+    } else {
+        Cow::Borrowed(path)
+    };
+    Some((digest, path, algorithm))
+}
+
+fn parse_unescaped_line(line: &str) -> Option<(u64, &str, Option<Version>)> {
+    for algorithm in Version::ALL {
+        if let Some(rest) = line.strip_prefix(bsd_tag(algorithm)) {
+            let (path, digest) = rest.strip_prefix(" (")?.rsplit_once(") = ")?;
+            return Some((parse_digest(digest)?, path, Some(algorithm)));
+        }
+    }
+    let (digest, rest) = line.split_at_checked(16)?;
+    let path = rest
+        .strip_prefix("  ")
+        .or_else(|| rest.strip_prefix(" *"))
+        .filter(|path| !path.is_empty())?;
+    Some((parse_digest(digest)?, path, None))
+}
+
+#[derive(Default)]
+struct CheckCounts {
+    mismatched: usize,
+    unreadable: usize,
+    malformed: usize,
+}
+
+fn check_list(
+    list: &str,
+    options: &Options,
+    out: &mut impl Write,
+    counts: &mut CheckCounts,
+) -> io::Result<()> {
+    let mut text = String::new();
+    if list == "-" {
+        io::stdin().lock().read_to_string(&mut text)?;
+    } else {
+        File::open(list)?.read_to_string(&mut text)?;
+    }
+
+    let lines: Vec<&str> = if options.zero {
+        text.split_terminator('\0').collect()
+    } else {
+        text.lines().collect()
+    };
+    for (i, line) in lines.into_iter().enumerate() {
+        let Some((expected, path, algorithm)) = parse_check_line(line, options.zero) else {
+            if !line.trim().is_empty() {
+                eprintln!("chibihash: {}: {}: improperly formatted line", list, i + 1);
+                counts.malformed += 1;
+            }
+            continue;
+        };
+        let algorithm = algorithm.unwrap_or(options.algorithm);
+        let (prefix, name) = display_path(&path, false);
+        match hash_path(&*path, options.seed, algorithm) {
+            Ok(hash) if hash == expected => {
+                if !options.quiet {
+                    writeln!(out, "{}{}: OK", prefix, name)?;
+                }
+            }
+            Ok(_) => {
+                writeln!(out, "{}{}: FAILED", prefix, name)?;
+                counts.mismatched += 1;
+            }
+            Err(err) => {
+                eprintln!("chibihash: {}: {}", name, err);
+                writeln!(out, "{}{}: FAILED open or read", prefix, name)?;
+                counts.unreadable += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_check(options: &Options) -> io::Result<bool> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut counts = CheckCounts::default();
+    let mut ok = true;
+    for list in &options.files {
+        if let Err(err) = check_list(list, options, &mut out, &mut counts) {
+            eprintln!("chibihash: {}: {}", list, err);
+            ok = false;
+        }
+    }
+    out.flush()?;
+
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    if counts.malformed > 0 {
+        let n = counts.malformed;
+        eprintln!(
+            "chibihash: WARNING: {} line{} improperly formatted",
+            n,
+            plural(n)
+        );
+    }
+    if counts.unreadable > 0 {
+        let n = counts.unreadable;
+        eprintln!(
+            "chibihash: WARNING: {} listed file{} could not be read",
+            n,
+            plural(n)
+        );
+    }
+    if counts.mismatched > 0 {
+        let n = counts.mismatched;
+        eprintln!(
+            "chibihash: WARNING: {} computed checksum{} did NOT match",
+            n,
+            plural(n)
+        );
+    }
+    Ok(ok && counts.mismatched == 0 && counts.unreadable == 0)
+}
+
+// Returns (ns per hash, GB/s) for `size`-byte inputs, measured for about
+// `time` after a short warm-up
+fn bench_size(algorithm: Version, size: usize, time: Duration) -> (f64, f64) {
+    let hash: fn(&[u8], u64) -> u64 = match algorithm {
+        Version::V1 => v1::chibi_hash64,
+        Version::V2 => v2::chibi_hash64,
+    };
+    let input: Vec<u8> = (0..size).map(|i| (i * 31) as u8).collect();
+    let run = |iters: u64| {
+        let start = Instant::now();
+        let mut seed = 0;
+        for _ in 0..iters {
+            seed = hash(std::hint::black_box(&input), seed);
+        }
+        std::hint::black_box(seed);
+        start.elapsed()
+    };
+
+    // Grow the batch until it takes a measurable slice of the budget
+    let mut iters = 1u64;
+    while run(iters) < time / 20 && iters < 1 << 40 {
+        iters *= 2;
+    }
+    // Always measure at least one batch so the result is never 0/0
+    let mut total_iters = iters;
+    let mut total = run(iters);
+    while total < time {
+        total += run(iters);
+        total_iters += iters;
+    }
+
+    let ns = total.as_nanos() as f64 / total_iters as f64;
+    (ns, size as f64 / ns)
+}
+
+fn run_bench(options: &BenchOptions) -> io::Result<bool> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(
+        out,
+        "{:<9} {:>10} {:>12} {:>10}",
+        "algorithm", "size", "ns/hash", "GB/s"
+    )?;
+    for &algorithm in &options.algorithms {
+        for &size in &options.sizes {
+            let (ns, gbps) = bench_size(algorithm, size, options.time);
+            writeln!(
+                out,
+                "{:<9} {:>10} {:>12.2} {:>10.2}",
+                algorithm, size, ns, gbps
+            )?;
+            out.flush()?;
+        }
+    }
+    Ok(true)
+}
+
+// Returns whether the two files or trees match
+fn run_diff(options: &Options) -> io::Result<bool> {
+    let (a, b) = (Path::new(&options.files[0]), Path::new(&options.files[1]));
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    match (a.is_dir(), b.is_dir()) {
+        (false, false) => {
+            let hashes = hash_all(&[a.to_path_buf(), b.to_path_buf()], options);
+            let (hash_a, hash_b) = match (&hashes[0], &hashes[1]) {
+                (Ok(hash_a), Ok(hash_b)) => (hash_a, hash_b),
+                (Err(err), _) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("{}: {}", a.display(), err),
+                    ))
+                }
+                (_, Err(err)) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("{}: {}", b.display(), err),
+                    ))
+                }
+            };
+            let verdict = if hash_a == hash_b { "match" } else { "differ" };
+            writeln!(out, "{} and {} {}", a.display(), b.display(), verdict)?;
+            Ok(hash_a == hash_b)
+        }
+        (true, true) => {
+            let manifest_options = ManifestOptions {
+                seed: options.seed,
+                skip_hidden: false,
+            };
+            let tree_a = Manifest::create(a, manifest_options)?;
+            let tree_b = Manifest::create(b, manifest_options)?;
+            let report = tree_a.diff(&tree_b);
+
+            let mut lines: Vec<(&str, &str)> = Vec::new();
+            lines.extend(report.changed.iter().map(|path| (path.as_str(), "changed")));
+            lines.extend(report.removed.iter().map(|path| (path.as_str(), "missing")));
+            lines.extend(report.added.iter().map(|path| (path.as_str(), "added")));
+            lines.sort_unstable();
+            for (path, label) in lines {
+                writeln!(out, "{}: {}", label, path)?;
+            }
+
+            if report.is_empty() {
+                writeln!(
+                    out,
+                    "{} and {} match ({} files)",
+                    a.display(),
+                    b.display(),
+                    tree_a.entries().len()
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "{} and {} differ: {} changed, {} missing, {} added",
+                    a.display(),
+                    b.display(),
+                    report.changed.len(),
+                    report.removed.len(),
+                    report.added.len()
+                )?;
+            }
+            Ok(report.is_empty())
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot compare a file with a directory",
+        )),
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Command::Hash(options)) => options,
+        Ok(Command::Diff(options)) => {
+            return match run_diff(&options) {
+                Ok(true) => ExitCode::SUCCESS,
+                Ok(false) => ExitCode::FAILURE,
+                Err(err) => {
+                    eprintln!("chibihash: {}", err);
+                    ExitCode::from(2)
+                }
+            };
+        }
+        Ok(Command::Bench(options)) => {
+            return match run_bench(&options) {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("chibihash: {}", err);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("chibihash {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("chibihash: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = if options.check {
+        run_check(&options)
+    } else {
+        run(&options)
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("chibihash: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
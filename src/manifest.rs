@@ -0,0 +1,445 @@
+//! Directory manifests: the size and digest of every file in a tree
+//!
+//! [`Manifest::create`] walks a directory and records each regular file's
+//! relative path, size and `v2` digest. [`Manifest::verify`] walks it again
+//! and reports files that were added, removed or changed; [`Manifest::diff`]
+//! compares two manifests, e.g. of a directory and its backup.
+//!
+//! Walks are deterministic: entries are sorted by path, and paths use `/` as
+//! the separator on every platform, so a manifest created on one machine
+//! verifies on another. Symbolic links are not followed and not recorded.
+//! With the `rayon` feature, files are hashed in parallel.
+//!
+//! A manifest prints as text and parses back with [`str::parse`]:
+//!
+//! ```text
+//! # chibihash manifest seed=0000000000000000
+//! 8f3c2e5fa1b0d9c4 1024 assets/logo.png
+//! 0d1e2f3a4b5c6d7e 12 README
+//! ```
+//!
+//! A manifest created with [`ManifestOptions::skip_hidden`] ends its header
+//! with ` skip-hidden=true`. Each further line holds the digest as 16 hex
+//! digits, the size in bytes and the path, in which `\` and newlines are
+//! escaped as `\\` and `\n`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use chibihash::manifest::{Manifest, ManifestOptions};
+//!
+//! let manifest = Manifest::create("build", ManifestOptions::default())?;
+//! std::fs::write("build.manifest", manifest.to_string())?;
+//!
+//! // Later
+//! let manifest: Manifest = std::fs::read_to_string("build.manifest")?.parse()?;
+//! let report = manifest.verify("build")?;
+//! for path in &report.changed {
+//!     println!("changed: {}", path);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::v2::StreamingChibiHasher;
+
+const HEADER: &str = "# chibihash manifest seed=";
+const SKIP_HIDDEN: &str = " skip-hidden=true";
+
+/// Options for [`Manifest::create`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ManifestOptions {
+    /// Seed for the file digests
+    pub seed: u64,
+    /// Skip files and directories whose name starts with `.`
+    pub skip_hidden: bool,
+}
+
+/// One file in a [`Manifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path relative to the manifest root, with `/` separators
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// `v2` digest of the contents under the manifest's seed
+    pub hash: u64,
+}
+
+/// The files of a directory tree with their sizes and digests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    seed: u64,
+    skip_hidden: bool,
+    entries: Vec<Entry>,
+}
+
+/// Differences between two states of a directory tree
+///
+/// Each list holds relative paths in sorted order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Files present now but not in the manifest
+    pub added: Vec<String>,
+    /// Files in the manifest that are gone
+    pub removed: Vec<String>,
+    /// Files whose size or digest differs
+    pub changed: Vec<String>,
+}
+
+impl Report {
+    /// Returns `true` if nothing was added, removed or changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Walks `dir` and records every regular file in it
+    ///
+    /// Fails if `dir` cannot be read, if any file cannot be read, or if a
+    /// path is not valid UTF-8.
+    pub fn create<P: AsRef<Path>>(dir: P, options: ManifestOptions) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        walk(dir, String::new(), options.skip_hidden, &mut files)?;
+        files.sort_unstable();
+
+        let entry = |path: String| -> io::Result<Entry> {
+            let file = dir.join(&path);
+            let (size, hash) = hash_file(&file, options.seed).map_err(|err| {
+                io::Error::new(err.kind(), format!("{}: {}", file.display(), err))
+            })?;
+            Ok(Entry { path, size, hash })
+        };
+        #[cfg(feature = "rayon")]
+        let entries = {
+            use rayon::prelude::*;
+            files
+                .into_par_iter()
+                .map(entry)
+                .collect::<io::Result<_>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let entries = files.into_iter().map(entry).collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            seed: options.seed,
+            skip_hidden: options.skip_hidden,
+            entries,
+        })
+    }
+
+    /// Walks `dir` again and compares it with this manifest
+    ///
+    /// Uses the options the manifest was created with.
+    pub fn verify<P: AsRef<Path>>(&self, dir: P) -> io::Result<Report> {
+        Ok(self.diff(&Self::create(dir, self.options())?))
+    }
+
+    /// Compares this manifest with `other`, treating `other` as the newer
+    ///
+    /// Manifests with different seeds have unrelated digests, so every file
+    /// present in both is reported as changed.
+    pub fn diff(&self, other: &Manifest) -> Report {
+        let mut report = Report::default();
+        let (mut old, mut new) = (
+            self.entries.iter().peekable(),
+            other.entries.iter().peekable(),
+        );
+        loop {
+            match (old.peek(), new.peek()) {
+                (Some(a), Some(b)) if a.path == b.path => {
+                    if a.size != b.size || a.hash != b.hash || self.seed != other.seed {
+                        report.changed.push(a.path.clone());
+                    }
+                    old.next();
+                    new.next();
+                }
+                (Some(a), Some(b)) if a.path < b.path => {
+                    report.removed.push(a.path.clone());
+                    old.next();
+                }
+                (Some(a), None) => {
+                    report.removed.push(a.path.clone());
+                    old.next();
+                }
+                (_, Some(b)) => {
+                    report.added.push(b.path.clone());
+                    new.next();
+                }
+                (None, None) => return report,
+            }
+        }
+    }
+
+    /// Returns the seed the digests were computed with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the options the manifest was created with
+    pub fn options(&self) -> ManifestOptions {
+        ManifestOptions {
+            seed: self.seed,
+            skip_hidden: self.skip_hidden,
+        }
+    }
+
+    /// Returns the entries, sorted by path
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+}
+
+// Collects the relative paths of the regular files under `dir`
+fn walk(dir: &Path, prefix: String, skip_hidden: bool, files: &mut Vec<String>) -> io::Result<()> {
+    for item in fs::read_dir(dir)? {
+        let item = item?;
+        let name = item.file_name().into_string().map_err(|name| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("path is not valid UTF-8: {:?}", dir.join(name)),
+            )
+        })?;
+        if skip_hidden && name.starts_with('.') {
+            continue;
+        }
+        let path = format!("{}{}", prefix, name);
+        let file_type = item.file_type()?;
+        if file_type.is_dir() {
+            walk(&item.path(), path + "/", skip_hidden, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path, seed: u64) -> io::Result<(u64, u64)> {
+    let mut hasher = StreamingChibiHasher::new(seed);
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok((size, hasher.finalize()))
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:016x}", HEADER, self.seed)?;
+        if self.skip_hidden {
+            f.write_str(SKIP_HIDDEN)?;
+        }
+        writeln!(f)?;
+        for entry in &self.entries {
+            write!(f, "{:016x} {} ", entry.hash, entry.size)?;
+            for c in entry.path.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '\n' => f.write_str("\\n")?,
+                    c => fmt::Write::write_char(f, c)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`Manifest`] fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseManifestError {
+    line: usize,
+}
+
+impl ParseManifestError {
+    /// Returns the 1-based number of the offending line
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for ParseManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid manifest at line {}", self.line)
+    }
+}
+
+impl std::error::Error for ParseManifestError {}
+
+impl FromStr for Manifest {
+    type Err = ParseManifestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Not `lines`, which would strip a `\r` ending a path
+        let mut lines = s.split_terminator('\n');
+        let header = lines
+            .next()
+            .and_then(|header| header.strip_prefix(HEADER))
+            .ok_or(ParseManifestError { line: 1 })?;
+        let (seed, skip_hidden) = match header.strip_suffix(SKIP_HIDDEN) {
+            Some(seed) => (seed, true),
+            None => (header, false),
+        };
+        let seed = u64::from_str_radix(seed.trim_end_matches('\r'), 16)
+            .map_err(|_| ParseManifestError { line: 1 })?;
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let err = ParseManifestError { line: i + 2 };
+            let mut fields = line.splitn(3, ' ');
+            let hash = fields.next().filter(|hash| hash.len() == 16);
+            let hash = hash.and_then(|hash| u64::from_str_radix(hash, 16).ok());
+            let size = fields.next().and_then(|size| size.parse().ok());
+            let path = fields.next().and_then(unescape);
+            let (Some(hash), Some(size), Some(path)) = (hash, size, path) else {
+                return Err(err);
+            };
+            // `diff` relies on the order
+            if entries.last().is_some_and(|last| last.path >= path) {
+                return Err(err);
+            }
+            entries.push(Entry { path, size, hash });
+        }
+        Ok(Self {
+            seed,
+            skip_hidden,
+            entries,
+        })
+    }
+}
+
+fn unescape(path: &str) -> Option<String> {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        out.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::v2::chibi_hash64;
+
+    // A directory under the temp directory, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("chibihash-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("sub/deeper")).unwrap();
+            fs::write(path.join("a.txt"), b"alpha").unwrap();
+            fs::write(path.join("sub/b.bin"), [0u8; 100]).unwrap();
+            fs::write(path.join("sub/deeper/c"), b"").unwrap();
+            fs::write(path.join(".hidden"), b"h").unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_create() {
+        let dir = TempDir::new("manifest-create");
+        let options = ManifestOptions {
+            seed: 3,
+            skip_hidden: true,
+        };
+        let manifest = Manifest::create(&dir.0, options).unwrap();
+        assert_eq!(manifest.options(), options);
+        let paths: Vec<&str> = manifest.entries().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "sub/b.bin", "sub/deeper/c"]);
+        assert_eq!(
+            manifest.entries()[0],
+            Entry {
+                path: "a.txt".into(),
+                size: 5,
+                hash: chibi_hash64(b"alpha", 3),
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = TempDir::new("manifest-verify");
+        let manifest = Manifest::create(&dir.0, ManifestOptions::default()).unwrap();
+        assert!(manifest.verify(&dir.0).unwrap().is_empty());
+
+        fs::write(dir.0.join("a.txt"), b"alphb").unwrap();
+        fs::remove_file(dir.0.join("sub/b.bin")).unwrap();
+        fs::write(dir.0.join("sub/new"), b"new").unwrap();
+        let report = manifest.verify(&dir.0).unwrap();
+        assert_eq!(report.changed, ["a.txt"]);
+        assert_eq!(report.removed, ["sub/b.bin"]);
+        assert_eq!(report.added, ["sub/new"]);
+    }
+
+    #[test]
+    fn test_verify_skip_hidden() {
+        let dir = TempDir::new("manifest-skip-hidden");
+        let options = ManifestOptions {
+            seed: 0,
+            skip_hidden: true,
+        };
+        let manifest = Manifest::create(&dir.0, options).unwrap();
+        let text = manifest.to_string();
+        assert!(text.starts_with("# chibihash manifest seed=0000000000000000 skip-hidden=true\n"));
+        let parsed: Manifest = text.parse().unwrap();
+        assert_eq!(parsed, manifest);
+        assert!(parsed.verify(&dir.0).unwrap().is_empty());
+
+        fs::write(dir.0.join(".hidden"), b"changed").unwrap();
+        assert!(parsed.verify(&dir.0).unwrap().is_empty());
+        fs::write(dir.0.join("a.txt"), b"changed").unwrap();
+        assert_eq!(parsed.verify(&dir.0).unwrap().changed, ["a.txt"]);
+    }
+
+    #[test]
+    fn test_text_roundtrip() {
+        let dir = TempDir::new("manifest-text");
+        let mut manifest = Manifest::create(&dir.0, ManifestOptions::default()).unwrap();
+        manifest.entries.push(Entry {
+            path: "z/odd \\ name\nline".into(),
+            size: 1,
+            hash: 2,
+        });
+        manifest.entries.push(Entry {
+            path: "z/trailing cr\r".into(),
+            size: 3,
+            hash: 4,
+        });
+        let text = manifest.to_string();
+        assert!(text.starts_with("# chibihash manifest seed=0000000000000000\n"));
+        assert_eq!(text.parse::<Manifest>().unwrap(), manifest);
+
+        assert_eq!("".parse::<Manifest>().unwrap_err().line(), 1);
+        let bad = format!("{}0\n0123 5 a\n", HEADER);
+        assert_eq!(bad.parse::<Manifest>().unwrap_err().line(), 2);
+        let unsorted = format!(
+            "{}0\n{:016x} 1 a\n{:016x} 1 c\n{:016x} 1 b\n{:016x} 1 d\n",
+            HEADER, 0, 0, 0, 0
+        );
+        assert_eq!(unsorted.parse::<Manifest>().unwrap_err().line(), 4);
+    }
+}
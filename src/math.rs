@@ -0,0 +1,66 @@
+// Floating-point helpers for filters and sketches, since `f64::ln` and
+// `f64::sqrt` need `std`
+
+use core::f64::consts::LN_2;
+
+// Natural logarithm of a positive, normal `x`
+pub(crate) fn ln(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7FF) as i64 - 1023;
+    // The mantissa scaled into [1, 2)
+    let m = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+
+    // ln(m) = 2 atanh(z) with z = (m - 1) / (m + 1) in [0, 1/3)
+    let z = (m - 1.0) / (m + 1.0);
+    let z2 = z * z;
+    let mut term = z;
+    let mut sum = 0.0;
+    let mut n = 1.0;
+    while n < 40.0 {
+        sum += term / n;
+        term *= z2;
+        n += 2.0;
+    }
+    exponent as f64 * LN_2 + 2.0 * sum
+}
+
+// Square root of a non-negative, finite `x`
+pub(crate) fn sqrt(x: f64) -> f64 {
+    if x == 0.0 {
+        return 0.0;
+    }
+    // Halving the exponent gives a guess within a factor of two, and each
+    // Newton step then doubles the number of correct bits
+    let mut y = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..6 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ln() {
+        for (x, expected) in [
+            (1.0, 0.0),
+            (0.5, -LN_2),
+            (0.01, -4.605170185988091),
+            (1e-9, -20.72326583694641),
+            (1.999, 0.6926470555182631),
+            (1e12, 27.631021115928547),
+        ] {
+            assert!((ln(x) - expected).abs() < 1e-12, "ln({}) = {}", x, ln(x));
+        }
+    }
+
+    #[test]
+    fn test_sqrt() {
+        for x in [0.0, 1e-300, 0.25, 2.0, 1e10, 1.7e308] {
+            let y = sqrt(x);
+            assert!((y * y - x).abs() <= x * 1e-15, "sqrt({}) = {}", x, y);
+        }
+    }
+}
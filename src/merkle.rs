@@ -0,0 +1,646 @@
+//! Merkle trees over ChibiHash
+//!
+//! [`MerkleTree`] builds a binary hash tree over a list of leaves and
+//! produces an [`InclusionProof`] for any of them, which lets a holder of
+//! the root check that a leaf is in the tree without the other leaves.
+//!
+//! [`MerkleLog`] grows the same tree one leaf at a time, for append-only
+//! logs, and adds a [`ConsistencyProof`] that an earlier root is a prefix
+//! of the current one, i.e. that nothing logged before was changed.
+//!
+//! The tree has the shape of RFC 9162 (Certificate Transparency): the left
+//! subtree of a tree of `n` leaves holds the largest power of two below
+//! `n`. Leaves and internal nodes are hashed under different domains, see
+//! [`leaf_hash`] and [`node_hash`], so a leaf can never pass for a node.
+//! All hashes use `v2`.
+//!
+//! ChibiHash is not a cryptographic hash: a Merkle tree built on it detects
+//! corruption and accidental divergence, not a forger.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::merkle::{leaf_hash, MerkleTree};
+//!
+//! let blocks = ["block 0", "block 1", "block 2"];
+//! let tree = MerkleTree::from_leaves(blocks, 0);
+//! let root = tree.root();
+//!
+//! let proof = tree.proof(1).unwrap();
+//! assert!(proof.verify(leaf_hash(b"block 1", 0), root, 0));
+//! assert!(!proof.verify(leaf_hash(b"block 9", 0), root, 0));
+//! ```
+//!
+//! ```rust
+//! use chibihash::merkle::MerkleLog;
+//!
+//! let mut log = MerkleLog::new(0);
+//! log.append(b"login alice");
+//! log.append(b"login bob");
+//! let checkpoint = log.root();
+//!
+//! log.append(b"logout alice");
+//! let proof = log.consistency_proof(2).unwrap();
+//! assert!(proof.verify(checkpoint, log.root(), 0));
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::v2::{chibi_hash64, domain_seed};
+
+const LEAF_DOMAIN: &[u8] = b"chibihash-merkle-leaf";
+const NODE_DOMAIN: &[u8] = b"chibihash-merkle-node";
+
+/// Returns the hash of a leaf holding `data`
+pub fn leaf_hash(data: &[u8], seed: u64) -> u64 {
+    chibi_hash64(data, domain_seed(LEAF_DOMAIN, seed))
+}
+
+/// Returns the hash of the internal node with children `left` and `right`
+///
+/// The children are hashed as 16 bytes, `left` then `right`, each as `u64`
+/// little-endian.
+pub fn node_hash(left: u64, right: u64, seed: u64) -> u64 {
+    node(left, right, domain_seed(NODE_DOMAIN, seed))
+}
+
+fn node(left: u64, right: u64, node_seed: u64) -> u64 {
+    let mut buf = [0u8; 16];
+    buf[..8].copy_from_slice(&left.to_le_bytes());
+    buf[8..].copy_from_slice(&right.to_le_bytes());
+    chibi_hash64(&buf, node_seed)
+}
+
+/// A Merkle tree; see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    // levels[0] holds the leaf hashes and the last level the root. A node
+    // without a sibling moves up unchanged.
+    levels: Vec<Vec<u64>>,
+    seed: u64,
+}
+
+impl MerkleTree {
+    /// Builds the tree whose leaves hold `leaves`, hashed with [`leaf_hash`]
+    pub fn from_leaves<I>(leaves: I, seed: u64) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let leaf_seed = domain_seed(LEAF_DOMAIN, seed);
+        let hashes = leaves
+            .into_iter()
+            .map(|leaf| chibi_hash64(leaf.as_ref(), leaf_seed));
+        Self::from_leaf_hashes(hashes, seed)
+    }
+
+    /// Builds the tree over precomputed leaf hashes
+    ///
+    /// The hashes are used as they are. Compute them with [`leaf_hash`], or
+    /// with a hash of your own that cannot equal a [`node_hash`], such as a
+    /// content digest under another seed.
+    pub fn from_leaf_hashes<I: IntoIterator<Item = u64>>(hashes: I, seed: u64) -> Self {
+        let node_seed = domain_seed(NODE_DOMAIN, seed);
+        let mut levels = vec![hashes.into_iter().collect::<Vec<u64>>()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(2)
+                .map(|pair| match *pair {
+                    [left, right] => node(left, right, node_seed),
+                    [single] => single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels, seed }
+    }
+
+    /// Returns the root hash
+    ///
+    /// The root of an empty tree is a fixed value that is neither a leaf nor
+    /// a node hash.
+    pub fn root(&self) -> u64 {
+        match self.levels.last().and_then(|level| level.first()) {
+            Some(&root) => root,
+            None => empty_root(self.seed),
+        }
+    }
+
+    /// Returns the hash of leaf `index`
+    pub fn leaf(&self, index: usize) -> Option<u64> {
+        self.levels[0].get(index).copied()
+    }
+
+    /// Returns the proof that leaf `index` is in the tree, or `None` if
+    /// there is no such leaf
+    pub fn proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::with_capacity(self.levels.len() - 1);
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(&sibling) = level.get(i ^ 1) {
+                path.push(sibling);
+            }
+            i /= 2;
+        }
+        Some(InclusionProof {
+            index: index as u64,
+            tree_size: self.len() as u64,
+            path,
+        })
+    }
+
+    /// Returns the number of leaves
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Returns `true` if the tree has no leaves
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+fn empty_root(seed: u64) -> u64 {
+    chibi_hash64(&[], domain_seed(NODE_DOMAIN, seed))
+}
+
+/// Proof that a leaf is in a [`MerkleTree`]: the sibling hashes on the path
+/// from the leaf to the root, lowest first
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InclusionProof {
+    index: u64,
+    tree_size: u64,
+    path: Vec<u64>,
+}
+
+impl InclusionProof {
+    /// Creates a proof from its parts, e.g. after receiving them
+    pub fn new(index: u64, tree_size: u64, path: Vec<u64>) -> Self {
+        Self {
+            index,
+            tree_size,
+            path,
+        }
+    }
+
+    /// Returns the root implied by `leaf_hash` at this proof's position, or
+    /// `None` if the proof does not fit a tree of its size
+    pub fn root(&self, leaf_hash: u64, seed: u64) -> Option<u64> {
+        if self.index >= self.tree_size {
+            return None;
+        }
+        let node_seed = domain_seed(NODE_DOMAIN, seed);
+        // RFC 9162, section 2.1.3.2
+        let mut index = self.index;
+        let mut last = self.tree_size - 1;
+        let mut hash = leaf_hash;
+        for &sibling in &self.path {
+            if last == 0 {
+                return None;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node(sibling, hash, node_seed);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node(hash, sibling, node_seed);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        (last == 0).then_some(hash)
+    }
+
+    /// Returns `true` if `leaf_hash` is at this proof's position in the tree
+    /// with `root`
+    pub fn verify(&self, leaf_hash: u64, root: u64, seed: u64) -> bool {
+        self.root(leaf_hash, seed) == Some(root)
+    }
+
+    /// Returns the position of the leaf
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns the number of leaves in the tree
+    pub fn tree_size(&self) -> u64 {
+        self.tree_size
+    }
+
+    /// Returns the sibling hashes, lowest first
+    pub fn path(&self) -> &[u64] {
+        &self.path
+    }
+}
+
+/// An append-only Merkle tree; see the [module documentation](self)
+///
+/// Every leaf sequence has the same root as a [`MerkleTree`] over the same
+/// leaves. Appending is O(log n), like incrementing a binary counter: a new
+/// leaf completes at most one subtree per level. The log keeps every
+/// complete subtree, about two hashes per leaf, so it can prove
+/// consistency with any earlier size and give the root it had then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleLog {
+    // subtrees[h] holds the hashes of the complete subtrees of 2^h leaves,
+    // in order
+    subtrees: Vec<Vec<u64>>,
+    seed: u64,
+    node_seed: u64,
+}
+
+impl MerkleLog {
+    /// Creates an empty log
+    pub fn new(seed: u64) -> Self {
+        Self {
+            subtrees: Vec::new(),
+            seed,
+            node_seed: domain_seed(NODE_DOMAIN, seed),
+        }
+    }
+
+    /// Appends a leaf holding `data`, hashed with [`leaf_hash`], and returns
+    /// its index
+    pub fn append(&mut self, data: &[u8]) -> usize {
+        self.append_hash(leaf_hash(data, self.seed))
+    }
+
+    /// Appends a precomputed leaf hash and returns its index
+    ///
+    /// See [`MerkleTree::from_leaf_hashes`] for the requirements on it.
+    pub fn append_hash(&mut self, hash: u64) -> usize {
+        let index = self.len();
+        let mut hash = hash;
+        let mut height = 0;
+        loop {
+            if self.subtrees.len() == height {
+                self.subtrees.push(Vec::new());
+            }
+            let level = &mut self.subtrees[height];
+            level.push(hash);
+            if level.len() % 2 == 1 {
+                return index;
+            }
+            hash = node(level[level.len() - 2], hash, self.node_seed);
+            height += 1;
+        }
+    }
+
+    /// Returns the root hash over every leaf appended so far
+    pub fn root(&self) -> u64 {
+        // The last subtree of each level with an odd count, smallest first
+        let frontier = self
+            .subtrees
+            .iter()
+            .filter(|level| level.len() % 2 == 1)
+            .filter_map(|level| level.last());
+        frontier
+            .copied()
+            .reduce(|right, left| node(left, right, self.node_seed))
+            .unwrap_or_else(|| empty_root(self.seed))
+    }
+
+    /// Returns the root the log had when it held `size` leaves, or `None`
+    /// if it never did
+    pub fn root_at(&self, size: usize) -> Option<u64> {
+        match size {
+            0 => Some(empty_root(self.seed)),
+            _ if size <= self.len() => Some(self.subtree(0, size)),
+            _ => None,
+        }
+    }
+
+    /// Returns the hash of leaf `index`
+    pub fn leaf(&self, index: usize) -> Option<u64> {
+        self.subtrees.first()?.get(index).copied()
+    }
+
+    /// Returns the proof that leaf `index` is in the current tree, or `None`
+    /// if there is no such leaf
+    pub fn proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        self.inclusion_path(index, 0, self.len(), &mut path);
+        Some(InclusionProof::new(index as u64, self.len() as u64, path))
+    }
+
+    /// Returns the proof that the tree of the first `old_size` leaves is a
+    /// prefix of the current tree, or `None` unless
+    /// `0 < old_size <= len()`
+    pub fn consistency_proof(&self, old_size: usize) -> Option<ConsistencyProof> {
+        if old_size == 0 || old_size > self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        self.consistency_path(old_size, 0, self.len(), true, &mut path);
+        Some(ConsistencyProof::new(
+            old_size as u64,
+            self.len() as u64,
+            path,
+        ))
+    }
+
+    /// Returns the number of leaves
+    pub fn len(&self) -> usize {
+        self.subtrees.first().map_or(0, Vec::len)
+    }
+
+    /// Returns `true` if nothing has been appended
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Root of the `len` leaves from `start`, as split by RFC 9162
+    fn subtree(&self, start: usize, len: usize) -> u64 {
+        if len.is_power_of_two() && start.is_multiple_of(len) {
+            return self.subtrees[len.trailing_zeros() as usize][start / len];
+        }
+        let k = split(len);
+        node(
+            self.subtree(start, k),
+            self.subtree(start + k, len - k),
+            self.node_seed,
+        )
+    }
+
+    // RFC 9162, section 2.1.3.1
+    fn inclusion_path(&self, index: usize, start: usize, len: usize, path: &mut Vec<u64>) {
+        if len == 1 {
+            return;
+        }
+        let k = split(len);
+        if index < k {
+            self.inclusion_path(index, start, k, path);
+            path.push(self.subtree(start + k, len - k));
+        } else {
+            self.inclusion_path(index - k, start + k, len - k, path);
+            path.push(self.subtree(start, k));
+        }
+    }
+
+    // RFC 9162, section 2.1.4.1
+    fn consistency_path(
+        &self,
+        old: usize,
+        start: usize,
+        len: usize,
+        complete: bool,
+        path: &mut Vec<u64>,
+    ) {
+        if old == len {
+            if !complete {
+                path.push(self.subtree(start, len));
+            }
+            return;
+        }
+        let k = split(len);
+        if old <= k {
+            self.consistency_path(old, start, k, complete, path);
+            path.push(self.subtree(start + k, len - k));
+        } else {
+            self.consistency_path(old - k, start + k, len - k, false, path);
+            path.push(self.subtree(start, k));
+        }
+    }
+}
+
+// The size of the left subtree of a tree of `len` > 1 leaves
+fn split(len: usize) -> usize {
+    1 << (len - 1).ilog2()
+}
+
+/// Proof that a [`MerkleLog`] of `new_size` leaves extends the same log at
+/// `old_size` leaves
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsistencyProof {
+    old_size: u64,
+    new_size: u64,
+    path: Vec<u64>,
+}
+
+impl ConsistencyProof {
+    /// Creates a proof from its parts, e.g. after receiving them
+    pub fn new(old_size: u64, new_size: u64, path: Vec<u64>) -> Self {
+        Self {
+            old_size,
+            new_size,
+            path,
+        }
+    }
+
+    /// Returns `true` if the tree with `old_root` at `old_size` leaves is a
+    /// prefix of the tree with `new_root` at `new_size` leaves
+    pub fn verify(&self, old_root: u64, new_root: u64, seed: u64) -> bool {
+        let (old, new) = (self.old_size, self.new_size);
+        if old == 0 || old > new {
+            return false;
+        }
+        if old == new {
+            return self.path.is_empty() && old_root == new_root;
+        }
+        let node_seed = domain_seed(NODE_DOMAIN, seed);
+        // RFC 9162, section 2.1.4.2; a power-of-two old tree is a subtree of
+        // the new one and its root is not repeated in the path
+        let mut path = self.path.iter().copied();
+        let first = if old.is_power_of_two() {
+            Some(old_root)
+        } else {
+            path.next()
+        };
+        let Some(first) = first else {
+            return false;
+        };
+        let mut index = old - 1;
+        let mut last = new - 1;
+        while index & 1 == 1 {
+            index >>= 1;
+            last >>= 1;
+        }
+        let (mut old_hash, mut new_hash) = (first, first);
+        for sibling in path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                old_hash = node(sibling, old_hash, node_seed);
+                new_hash = node(sibling, new_hash, node_seed);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                new_hash = node(new_hash, sibling, node_seed);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && old_hash == old_root && new_hash == new_root
+    }
+
+    /// Returns the number of leaves in the earlier tree
+    pub fn old_size(&self) -> u64 {
+        self.old_size
+    }
+
+    /// Returns the number of leaves in the later tree
+    pub fn new_size(&self) -> u64 {
+        self.new_size
+    }
+
+    /// Returns the subtree hashes of the proof
+    pub fn path(&self) -> &[u64] {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The root by the recursive definition of RFC 9162
+    fn reference_root(leaves: &[u64], seed: u64) -> u64 {
+        match leaves.len() {
+            0 => empty_root(seed),
+            1 => leaves[0],
+            n => {
+                let k = 1 << (n - 1).ilog2();
+                node_hash(
+                    reference_root(&leaves[..k], seed),
+                    reference_root(&leaves[k..], seed),
+                    seed,
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        for n in 0..40u32 {
+            let leaves: Vec<[u8; 4]> = (0..n).map(|i| i.to_le_bytes()).collect();
+            let tree = MerkleTree::from_leaves(&leaves, 3);
+            let hashes: Vec<u64> = leaves.iter().map(|l| leaf_hash(l, 3)).collect();
+            assert_eq!(tree.root(), reference_root(&hashes, 3), "{} leaves", n);
+            assert_eq!(tree.len(), n as usize);
+            assert_eq!(tree, MerkleTree::from_leaf_hashes(hashes, 3));
+        }
+        let one = MerkleTree::from_leaves([b"x"], 0);
+        assert_eq!(one.root(), leaf_hash(b"x", 0));
+        assert_ne!(leaf_hash(b"x", 0), leaf_hash(b"x", 1));
+        assert!(MerkleTree::from_leaves::<[&[u8]; 0]>([], 0).is_empty());
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        for n in 1..40u32 {
+            let tree = MerkleTree::from_leaves((0..n).map(|i| i.to_le_bytes()), 5);
+            for i in 0..n as usize {
+                let proof = tree.proof(i).unwrap();
+                let leaf = tree.leaf(i).unwrap();
+                assert!(proof.verify(leaf, tree.root(), 5), "{} of {}", i, n);
+                assert!(!proof.verify(leaf ^ 1, tree.root(), 5));
+                assert!(!proof.verify(leaf, tree.root(), 6) || n == 1);
+                if n > 1 {
+                    let moved = InclusionProof::new(
+                        (i as u64 + 1) % n as u64,
+                        n as u64,
+                        proof.path().to_vec(),
+                    );
+                    assert!(!moved.verify(leaf, tree.root(), 5));
+                }
+            }
+            assert_eq!(tree.proof(n as usize), None);
+        }
+    }
+
+    #[test]
+    fn test_merkle_log() {
+        let mut log = MerkleLog::new(7);
+        assert_eq!(log.root(), MerkleTree::from_leaf_hashes([], 7).root());
+        let mut hashes = Vec::new();
+        for n in 1..=40u32 {
+            assert_eq!(log.append(&n.to_le_bytes()), n as usize - 1);
+            hashes.push(leaf_hash(&n.to_le_bytes(), 7));
+            let tree = MerkleTree::from_leaf_hashes(hashes.iter().copied(), 7);
+            assert_eq!(log.root(), tree.root(), "{} leaves", n);
+            for i in 0..n as usize {
+                assert_eq!(log.proof(i), tree.proof(i));
+            }
+        }
+        for size in 0..=40 {
+            let prefix = MerkleTree::from_leaf_hashes(hashes[..size].iter().copied(), 7);
+            assert_eq!(log.root_at(size), Some(prefix.root()));
+        }
+        assert_eq!(log.root_at(41), None);
+        assert_eq!(log.proof(40), None);
+        assert_eq!(log.leaf(3), Some(hashes[3]));
+    }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let mut log = MerkleLog::new(0);
+        for n in 1..=33u32 {
+            log.append(&n.to_le_bytes());
+            let root = log.root();
+            for old in 1..=n as usize {
+                let old_root = log.root_at(old).unwrap();
+                let proof = log.consistency_proof(old).unwrap();
+                assert!(proof.verify(old_root, root, 0), "{} of {}", old, n);
+                assert!(!proof.verify(old_root ^ 1, root, 0));
+                assert!(!proof.verify(old_root, root ^ 1, 0));
+                if !proof.path().is_empty() {
+                    let mut tampered = proof.path().to_vec();
+                    tampered[0] ^= 1;
+                    let tampered = ConsistencyProof::new(old as u64, n as u64, tampered);
+                    assert!(!tampered.verify(old_root, root, 0));
+                }
+            }
+            assert_eq!(log.consistency_proof(0), None);
+            assert_eq!(log.consistency_proof(n as usize + 1), None);
+        }
+
+        // A log that rewrote history is caught
+        let mut honest = MerkleLog::new(0);
+        let mut forked = MerkleLog::new(0);
+        for n in 1..=20u32 {
+            honest.append(&n.to_le_bytes());
+            forked.append(&if n == 3 { 0 } else { n }.to_le_bytes());
+        }
+        let proof = forked.consistency_proof(5).unwrap();
+        assert!(!proof.verify(honest.root_at(5).unwrap(), forked.root(), 0));
+    }
+
+    #[test]
+    fn test_malformed_proofs() {
+        let tree = MerkleTree::from_leaves(["a", "b", "c", "d", "e"], 0);
+        let proof = tree.proof(2).unwrap();
+        let leaf = tree.leaf(2).unwrap();
+        let mut long = proof.path().to_vec();
+        long.push(0);
+        assert_eq!(InclusionProof::new(2, 5, long).root(leaf, 0), None);
+        let short = proof.path()[..1].to_vec();
+        assert_eq!(InclusionProof::new(2, 5, short).root(leaf, 0), None);
+        assert_eq!(InclusionProof::new(5, 5, vec![]).root(leaf, 0), None);
+    }
+}
@@ -0,0 +1,124 @@
+//! Mixing primitives shared by the hash functions
+//!
+//! These are bijections on `u64`: distinct inputs always give distinct
+//! outputs, which makes them useful for scrambling counters and IDs without
+//! introducing collisions.
+
+const MOREMUR_M1: u64 = 0x3C79AC492BA7B653;
+const MOREMUR_M2: u64 = 0x1C69B3F74AC4AE35;
+
+// Multiplicative inverses of the moremur constants modulo 2^64
+const MOREMUR_M1_INV: u64 = mul_inverse(MOREMUR_M1);
+const MOREMUR_M2_INV: u64 = mul_inverse(MOREMUR_M2);
+
+/// The moremur finalizer used at the end of `v1`
+///
+/// See Pelle Evensen's
+/// [moremur](https://mostlymangling.blogspot.com/2019/12/stronger-better-morer-moremur-better.html).
+#[inline(always)]
+pub const fn moremur(mut x: u64) -> u64 {
+    x ^= x >> 27;
+    x = x.wrapping_mul(MOREMUR_M1);
+    x ^= x >> 33;
+    x = x.wrapping_mul(MOREMUR_M2);
+    x ^= x >> 27;
+    x
+}
+
+/// Inverse of [`moremur`]
+///
+/// `moremur_inverse(moremur(x)) == x` for every `x`.
+pub const fn moremur_inverse(mut x: u64) -> u64 {
+    x = unxorshift(x, 27);
+    x = x.wrapping_mul(MOREMUR_M2_INV);
+    x = unxorshift(x, 33);
+    x = x.wrapping_mul(MOREMUR_M1_INV);
+    unxorshift(x, 27)
+}
+
+// Folds a 64-bit hash to 32 bits so every output bit depends on both halves
+#[inline(always)]
+pub(crate) const fn fold32(x: u64) -> u32 {
+    (x ^ (x >> 32)) as u32
+}
+
+// Outputs of `finalize_multi`: `first`, the regular hash, then `moremur` of
+// the state before the final mix `x`, combined with `lanes`, a second word
+// folded from the four lanes, and offset by multiples of the golden ratio.
+// Keys whose `x` collide still get different later outputs unless `lanes`
+// collides too.
+#[inline(always)]
+pub(crate) fn derive_multi<const K: usize>(x: u64, lanes: u64, first: u64) -> [u64; K] {
+    let w = x ^ moremur(lanes);
+    core::array::from_fn(|i| match i {
+        0 => first,
+        _ => moremur(w ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15)),
+    })
+}
+
+// Folds the four lanes into one word, independently of how `fold_state`
+// combines them
+#[inline(always)]
+pub(crate) const fn fold_lanes(h: &[u64; 4]) -> u64 {
+    h[0] ^ h[1].rotate_left(16) ^ h[2].rotate_left(32) ^ h[3].rotate_left(48)
+}
+
+// Maps a uniformly distributed `x` onto `0..n` with Lemire's multiply-shift
+#[inline(always)]
+pub(crate) const fn reduce(x: u64, n: u64) -> u64 {
+    ((x as u128 * n as u128) >> 64) as u64
+}
+
+// Inverts `x ^ (x >> shift)`
+const fn unxorshift(x: u64, shift: u32) -> u64 {
+    let mut y = x;
+    let mut s = shift;
+    while s < 64 {
+        y = x ^ (y >> shift);
+        s += shift;
+    }
+    y
+}
+
+// Newton's iteration for the inverse of an odd number modulo 2^64
+const fn mul_inverse(a: u64) -> u64 {
+    let mut x = a;
+    let mut i = 0;
+    while i < 5 {
+        x = x.wrapping_mul(2u64.wrapping_sub(a.wrapping_mul(x)));
+        i += 1;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_inverse() {
+        assert_eq!(MOREMUR_M1.wrapping_mul(MOREMUR_M1_INV), 1);
+        assert_eq!(MOREMUR_M2.wrapping_mul(MOREMUR_M2_INV), 1);
+    }
+
+    #[test]
+    fn test_moremur_inverse() {
+        let mut x = 0x0123456789ABCDEFu64;
+        for _ in 0..1000 {
+            assert_eq!(moremur_inverse(moremur(x)), x);
+            x = moremur(x);
+        }
+        assert_eq!(moremur_inverse(moremur(0)), 0);
+        assert_eq!(moremur_inverse(moremur(u64::MAX)), u64::MAX);
+    }
+
+    #[test]
+    fn test_derive_multi_uses_lanes() {
+        // A collision in the folded state must not carry over to the others
+        let a: [u64; 3] = derive_multi(7, 1, 42);
+        let b: [u64; 3] = derive_multi(7, 2, 42);
+        assert_eq!(a[0], b[0]);
+        assert_ne!(a[1], b[1]);
+        assert_ne!(a[2], b[2]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Portable hashing of IP and socket addresses
+//!
+//! `std`'s `Hash` impls for address types are an implementation detail: the
+//! bytes they feed to a hasher may change between Rust versions and differ
+//! in endianness between platforms. Routing decisions that must agree across
+//! hosts should hash a defined encoding instead. The encoding used here is:
+//!
+//! | Address | Bytes |
+//! |---------|-------|
+//! | IPv4 | `0x04`, then the 4 octets |
+//! | IPv6 | `0x06`, then the 16 octets |
+//! | Socket address | the IP encoding, then the port as 2 big-endian bytes |
+//!
+//! IPv6 flow info and scope IDs are not part of the encoding. The bytes are
+//! hashed with `v2`.
+//!
+//! By default `::ffff:10.0.0.1` and `10.0.0.1` hash differently. Use the
+//! `_canonical` variants to hash IPv4-mapped IPv6 addresses as their IPv4
+//! form, e.g. when dual-stack sockets report IPv4 peers as mapped addresses.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::net::{hash_ip, hash_ip_canonical};
+//! use std::net::{IpAddr, Ipv4Addr};
+//!
+//! let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+//! let mapped = IpAddr::V6(Ipv4Addr::new(10, 0, 0, 1).to_ipv6_mapped());
+//!
+//! assert_ne!(hash_ip(v4, 0), hash_ip(mapped, 0));
+//! assert_eq!(hash_ip_canonical(v4, 0), hash_ip_canonical(mapped, 0));
+//! ```
+
+use core::net::{IpAddr, SocketAddr};
+
+use crate::v2::chibi_hash64;
+
+// Tag byte, up to 16 octets and a 2-byte port
+const MAX_ENCODED_LEN: usize = 1 + 16 + 2;
+
+fn encode_ip(addr: IpAddr, buf: &mut [u8; MAX_ENCODED_LEN]) -> usize {
+    match addr {
+        IpAddr::V4(v4) => {
+            buf[0] = 4;
+            buf[1..5].copy_from_slice(&v4.octets());
+            5
+        }
+        IpAddr::V6(v6) => {
+            buf[0] = 6;
+            buf[1..17].copy_from_slice(&v6.octets());
+            17
+        }
+    }
+}
+
+fn canonical_ip(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        IpAddr::V4(_) => addr,
+    }
+}
+
+/// Hashes an IP address using the portable encoding
+pub fn hash_ip(addr: IpAddr, seed: u64) -> u64 {
+    let mut buf = [0u8; MAX_ENCODED_LEN];
+    let len = encode_ip(addr, &mut buf);
+    chibi_hash64(&buf[..len], seed)
+}
+
+/// Like [`hash_ip`], but hashes IPv4-mapped IPv6 addresses as IPv4
+pub fn hash_ip_canonical(addr: IpAddr, seed: u64) -> u64 {
+    hash_ip(canonical_ip(addr), seed)
+}
+
+/// Hashes a socket address (IP and port) using the portable encoding
+pub fn hash_socketaddr(addr: SocketAddr, seed: u64) -> u64 {
+    let mut buf = [0u8; MAX_ENCODED_LEN];
+    let len = encode_ip(addr.ip(), &mut buf);
+    buf[len..len + 2].copy_from_slice(&addr.port().to_be_bytes());
+    chibi_hash64(&buf[..len + 2], seed)
+}
+
+/// Like [`hash_socketaddr`], but hashes IPv4-mapped IPv6 addresses as IPv4
+pub fn hash_socketaddr_canonical(addr: SocketAddr, seed: u64) -> u64 {
+    hash_socketaddr(SocketAddr::new(canonical_ip(addr.ip()), addr.port()), seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::net::{Ipv4Addr, Ipv6Addr, SocketAddrV6};
+
+    #[test]
+    fn test_hash_ip_encoding() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hash_ip(v4, 3), chibi_hash64(&[4, 192, 168, 1, 1], 3));
+
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let mut expected = [0u8; 17];
+        expected[0] = 6;
+        expected[16] = 1;
+        assert_eq!(hash_ip(v6, 3), chibi_hash64(&expected, 3));
+    }
+
+    #[test]
+    fn test_hash_ip_canonical() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mapped = IpAddr::V6(Ipv4Addr::new(10, 0, 0, 1).to_ipv6_mapped());
+        assert_ne!(hash_ip(v4, 0), hash_ip(mapped, 0));
+        assert_eq!(hash_ip_canonical(mapped, 0), hash_ip(v4, 0));
+
+        // Plain IPv6 addresses are left alone
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        assert_eq!(hash_ip_canonical(v6, 0), hash_ip(v6, 0));
+    }
+
+    #[test]
+    fn test_hash_socketaddr() {
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let a = SocketAddr::new(ip, 80);
+        let b = SocketAddr::new(ip, 443);
+        assert_ne!(hash_socketaddr(a, 0), hash_socketaddr(b, 0));
+        assert_ne!(hash_socketaddr(a, 0), hash_ip(ip, 0));
+        assert_eq!(
+            hash_socketaddr(a, 0),
+            chibi_hash64(&[4, 10, 0, 0, 1, 0, 80], 0)
+        );
+    }
+
+    #[test]
+    fn test_hash_socketaddr_ignores_scope() {
+        let a = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 80, 0, 0));
+        let b = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 80, 7, 3));
+        assert_eq!(hash_socketaddr(a, 0), hash_socketaddr(b, 0));
+    }
+
+    #[test]
+    fn test_hash_socketaddr_canonical() {
+        let v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 80);
+        let mapped = SocketAddr::new(IpAddr::V6(Ipv4Addr::new(10, 0, 0, 1).to_ipv6_mapped()), 80);
+        assert_eq!(hash_socketaddr_canonical(mapped, 0), hash_socketaddr(v4, 0));
+    }
+}
@@ -0,0 +1,150 @@
+//! Routing keys to partitions
+//!
+//! A [`Partitioner`] assigns each record key to one of `num_partitions`
+//! partitions, e.g. a topic partition for a producer or a shard for a
+//! router. [`ChibiPartitioner`] is the implementation built on ChibiHash.
+//!
+//! # Stability
+//!
+//! Producers written against different releases of this crate must agree
+//! on where a key goes. Each [`PartitionScheme`] therefore fixes its
+//! assignment forever: a key, seed and partition count map to the same
+//! partition in every release, on every platform, and the tests pin known
+//! values. Improvements come as new schemes, which have to be selected
+//! explicitly; [`ChibiPartitioner::default`] stays on
+//! [`PartitionScheme::V1`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::partition::{ChibiPartitioner, Partitioner};
+//!
+//! let partitioner = ChibiPartitioner::default();
+//! let partition = partitioner.partition(b"order-1234", 12);
+//! assert!(partition < 12);
+//! assert_eq!(partition, partitioner.partition(b"order-1234", 12));
+//! ```
+
+use core::fmt;
+
+use crate::range::bounded;
+use crate::v2::chibi_hash64;
+
+/// Assigns keys to partitions
+///
+/// Implemented for closures taking the key and partition count.
+pub trait Partitioner {
+    /// Returns the partition of `key`, in `0..num_partitions`
+    ///
+    /// # Panics
+    ///
+    /// May panic if `num_partitions` is zero.
+    fn partition(&self, key: &[u8], num_partitions: u32) -> u32;
+}
+
+impl<F: Fn(&[u8], u32) -> u32> Partitioner for F {
+    fn partition(&self, key: &[u8], num_partitions: u32) -> u32 {
+        self(key, num_partitions)
+    }
+}
+
+/// A fixed key-to-partition assignment; see the [module
+/// documentation](self#stability)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum PartitionScheme {
+    /// `v2::chibi_hash64(key, seed)` mapped onto the partitions with
+    /// [`range::bounded`](crate::range::bounded)
+    #[default]
+    V1,
+}
+
+impl fmt::Display for PartitionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionScheme::V1 => f.pad("v1"),
+        }
+    }
+}
+
+/// A [`Partitioner`] backed by ChibiHash
+///
+/// Uses [`PartitionScheme::V1`] and seed 0 unless configured otherwise.
+/// Every producer of a topic must use the same scheme and seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ChibiPartitioner {
+    scheme: PartitionScheme,
+    seed: u64,
+}
+
+impl ChibiPartitioner {
+    /// Creates a partitioner using `scheme` and seed 0
+    pub const fn new(scheme: PartitionScheme) -> Self {
+        Self { scheme, seed: 0 }
+    }
+
+    /// Returns the partitioner with `seed`
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the scheme
+    pub const fn scheme(&self) -> PartitionScheme {
+        self.scheme
+    }
+
+    /// Returns the seed
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Partitioner for ChibiPartitioner {
+    /// # Panics
+    ///
+    /// Panics if `num_partitions` is zero.
+    fn partition(&self, key: &[u8], num_partitions: u32) -> u32 {
+        assert!(num_partitions > 0, "number of partitions must be non-zero");
+        match self.scheme {
+            PartitionScheme::V1 => {
+                bounded(chibi_hash64(key, self.seed), u64::from(num_partitions)) as u32
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Part of the stability promise: these must never change
+    #[test]
+    fn test_partition_v1_known_values() {
+        let default = ChibiPartitioner::default();
+        let seeded = ChibiPartitioner::new(PartitionScheme::V1).with_seed(42);
+        let cases: [(&[u8], u32, u32, u32); 5] = [
+            (b"", 1, 0, 0),
+            (b"", 12, 9, 9),
+            (b"order-1234", 12, 8, 11),
+            (b"user:42", 100, 60, 48),
+            (b"a longer key that spans more than one stripe", 7, 3, 3),
+        ];
+        for (key, n, expected, expected_seeded) in cases {
+            assert_eq!(default.partition(key, n), expected);
+            assert_eq!(seeded.partition(key, n), expected_seeded);
+        }
+    }
+
+    #[test]
+    fn test_partitioner_closure() {
+        fn route<P: Partitioner>(partitioner: &P, key: &[u8]) -> u32 {
+            partitioner.partition(key, 4)
+        }
+        assert_eq!(route(&|key: &[u8], n| key.len() as u32 % n, b"abcdef"), 2);
+        assert_eq!(
+            route(&ChibiPartitioner::default(), b"abcdef"),
+            crate::range::hash_to_range(b"abcdef", 0, 4) as u32
+        );
+    }
+}
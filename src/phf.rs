@@ -0,0 +1,682 @@
+//! Perfect hash maps and sets keyed by ChibiHash
+//!
+//! A perfect hash function maps each key of a fixed set to a distinct slot,
+//! so a lookup is one hash, two table reads and one key comparison. The
+//! tables are generated ahead of time and stored in `static` data; nothing
+//! is allocated at runtime.
+//!
+//! Generation follows the "hash, displace" scheme: keys are grouped into
+//! buckets by their hash, and each bucket gets a displacement that moves all
+//! of its keys into free slots. Seeds are tried in order until every bucket
+//! fits. Keys are hashed with `v2`.
+//!
+//! Tables can be built in three ways:
+//!
+//! - At compile time with [`generate_const`], for string and byte string
+//!   keys.
+//! - At runtime with [`generate`], for any [`PhfKey`].
+//! - From a build script with [`MapCodegen`] and [`SetCodegen`], which emit
+//!   Rust source for a `static` [`Map`] or [`Set`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::phf::{generate_const, ConstGenerated, Map};
+//!
+//! const KEYS: [&[u8]; 3] = [b"GET", b"POST", b"DELETE"];
+//! const TABLE: ConstGenerated<3> = generate_const(&KEYS);
+//!
+//! static METHODS: Map<&'static str, u8> = Map {
+//!     seed: TABLE.seed,
+//!     disps: &TABLE.disps,
+//!     slots: &TABLE.slots,
+//!     entries: &[("GET", 0), ("POST", 1), ("DELETE", 2)],
+//! };
+//!
+//! assert_eq!(METHODS.get("POST"), Some(&1));
+//! assert_eq!(METHODS.get("PUT"), None);
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::mix::{moremur, reduce};
+use crate::v2::{chibi_hash64, chibi_hash64_const};
+
+// Golden ratio, spreads displacements over the whole 64-bit range
+const DISP_MULT: u64 = 0x9E3779B97F4A7C15;
+
+// Seeds tried before giving up. Each seed fails with small probability, so
+// running out means something is badly wrong.
+const MAX_SEEDS: u64 = 64;
+
+const EMPTY: u32 = u32::MAX;
+
+/// Keys that can be stored in a perfect hash [`Map`] or [`Set`]
+///
+/// Implementations must hash equal keys to equal values, and a borrowed form
+/// (e.g. `str` for `String`) must hash like its owner.
+pub trait PhfKey {
+    /// Hashes the key with `seed`
+    fn phf_hash(&self, seed: u64) -> u64;
+}
+
+impl PhfKey for str {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        chibi_hash64(self.as_bytes(), seed)
+    }
+}
+
+impl PhfKey for [u8] {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        chibi_hash64(self, seed)
+    }
+}
+
+impl<const N: usize> PhfKey for [u8; N] {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        chibi_hash64(self, seed)
+    }
+}
+
+impl<T: PhfKey + ?Sized> PhfKey for &T {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        (**self).phf_hash(seed)
+    }
+}
+
+impl PhfKey for String {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        self.as_str().phf_hash(seed)
+    }
+}
+
+impl PhfKey for char {
+    fn phf_hash(&self, seed: u64) -> u64 {
+        (*self as u32).phf_hash(seed)
+    }
+}
+
+macro_rules! impl_phf_key_int {
+    ($($ty:ty),*) => {
+        $(
+            impl PhfKey for $ty {
+                fn phf_hash(&self, seed: u64) -> u64 {
+                    chibi_hash64(&self.to_le_bytes(), seed)
+                }
+            }
+        )*
+    };
+}
+
+impl_phf_key_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Errors from perfect hash generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhfError {
+    /// The key at this index appears more than once
+    DuplicateKey(usize),
+    /// No seed produced a perfect hash
+    SeedsExhausted,
+}
+
+impl fmt::Display for PhfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhfError::DuplicateKey(index) => write!(f, "duplicate key at index {}", index),
+            PhfError::SeedsExhausted => write!(f, "no seed produced a perfect hash"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PhfError {}
+
+#[inline(always)]
+const fn bucket(hash: u64, n: usize) -> usize {
+    reduce(hash, n as u64) as usize
+}
+
+#[inline(always)]
+const fn slot(hash: u64, disp: u32, n: usize) -> usize {
+    reduce(
+        moremur(hash ^ (disp as u64).wrapping_mul(DISP_MULT)),
+        n as u64,
+    ) as usize
+}
+
+enum Attempt {
+    Done,
+    // Two keys, by index, with the same hash
+    Collision(usize, usize),
+    Exhausted,
+}
+
+// Finds displacements for one seed's `hashes`. Every slice has one element
+// per key; `order` and `starts` are scratch space. Shared by the runtime and
+// compile-time generators.
+const fn place(
+    hashes: &[u64],
+    disps: &mut [u32],
+    slots: &mut [u32],
+    order: &mut [u32],
+    starts: &mut [u32],
+) -> Attempt {
+    let n = hashes.len();
+    let mut i = 0;
+    while i < n {
+        disps[i] = 0;
+        slots[i] = EMPTY;
+        starts[i] = 0;
+        i += 1;
+    }
+
+    // Sort keys by bucket: count, take running totals, then fill backwards
+    // so that `starts[b]` ends up at the first key of bucket `b`
+    i = 0;
+    while i < n {
+        starts[bucket(hashes[i], n)] += 1;
+        i += 1;
+    }
+    let mut largest = 0;
+    let mut total = 0;
+    i = 0;
+    while i < n {
+        if starts[i] > largest {
+            largest = starts[i];
+        }
+        total += starts[i];
+        starts[i] = total;
+        i += 1;
+    }
+    i = n;
+    while i > 0 {
+        i -= 1;
+        let b = bucket(hashes[i], n);
+        starts[b] -= 1;
+        order[starts[b] as usize] = i as u32;
+    }
+
+    // Place the largest buckets first, while the table is still empty
+    let max_disp = (n as u32).saturating_mul(8).saturating_add(1024);
+    let mut size = largest as usize;
+    while size > 0 {
+        let mut b = 0;
+        while b < n {
+            let start = starts[b] as usize;
+            let end = if b + 1 < n { starts[b + 1] as usize } else { n };
+            if end - start == size {
+                // Keys with equal hashes can never be separated
+                let mut x = start;
+                while x < end {
+                    let mut y = x + 1;
+                    while y < end {
+                        if hashes[order[x] as usize] == hashes[order[y] as usize] {
+                            return Attempt::Collision(order[x] as usize, order[y] as usize);
+                        }
+                        y += 1;
+                    }
+                    x += 1;
+                }
+
+                let mut disp = 0;
+                while !try_displace(hashes, slots, order, start, end, disp) {
+                    disp += 1;
+                    if disp == max_disp {
+                        return Attempt::Exhausted;
+                    }
+                }
+                disps[b] = disp;
+            }
+            b += 1;
+        }
+        size -= 1;
+    }
+    Attempt::Done
+}
+
+// Claims the slots of `order[start..end]` under `disp`, or none of them
+const fn try_displace(
+    hashes: &[u64],
+    slots: &mut [u32],
+    order: &[u32],
+    start: usize,
+    end: usize,
+    disp: u32,
+) -> bool {
+    let n = hashes.len();
+    let mut k = start;
+    while k < end {
+        let s = slot(hashes[order[k] as usize], disp, n);
+        if slots[s] != EMPTY {
+            while k > start {
+                k -= 1;
+                slots[slot(hashes[order[k] as usize], disp, n)] = EMPTY;
+            }
+            return false;
+        }
+        slots[s] = order[k];
+        k += 1;
+    }
+    true
+}
+
+/// Perfect hash tables for `N` keys, built at compile time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstGenerated<const N: usize> {
+    /// Seed the keys are hashed with
+    pub seed: u64,
+    /// Displacement of each bucket
+    pub disps: [u32; N],
+    /// Index of the key that owns each slot
+    pub slots: [u32; N],
+}
+
+/// Builds perfect hash tables for `keys` in a `const` context
+///
+/// # Panics
+///
+/// Panics, which is a compile error in a `const` item, if a key appears
+/// twice or no seed works.
+pub const fn generate_const<const N: usize>(keys: &[&[u8]; N]) -> ConstGenerated<N> {
+    let mut hashes = [0u64; N];
+    let mut disps = [0u32; N];
+    let mut slots = [0u32; N];
+    let mut order = [0u32; N];
+    let mut starts = [0u32; N];
+
+    let mut seed = 0;
+    while seed < MAX_SEEDS {
+        let mut i = 0;
+        while i < N {
+            hashes[i] = chibi_hash64_const(keys[i], seed);
+            i += 1;
+        }
+        match place(&hashes, &mut disps, &mut slots, &mut order, &mut starts) {
+            Attempt::Done => return ConstGenerated { seed, disps, slots },
+            Attempt::Collision(a, b) => {
+                if bytes_eq(keys[a], keys[b]) {
+                    panic!("duplicate key in perfect hash map");
+                }
+            }
+            Attempt::Exhausted => {}
+        }
+        seed += 1;
+    }
+    panic!("no seed produced a perfect hash");
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Perfect hash tables built at runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    /// Seed the keys are hashed with
+    pub seed: u64,
+    /// Displacement of each bucket
+    pub disps: Vec<u32>,
+    /// Index of the key that owns each slot
+    pub slots: Vec<u32>,
+}
+
+/// Builds perfect hash tables for `keys`
+///
+/// Returns [`PhfError::DuplicateKey`] if a key appears twice.
+pub fn generate<K: PhfKey + Eq>(keys: &[K]) -> Result<Generated, PhfError> {
+    let n = keys.len();
+    let mut hashes = vec![0u64; n];
+    let mut disps = vec![0u32; n];
+    let mut slots = vec![0u32; n];
+    let mut order = vec![0u32; n];
+    let mut starts = vec![0u32; n];
+
+    for seed in 0..MAX_SEEDS {
+        for (hash, key) in hashes.iter_mut().zip(keys) {
+            *hash = key.phf_hash(seed);
+        }
+        match place(&hashes, &mut disps, &mut slots, &mut order, &mut starts) {
+            Attempt::Done => return Ok(Generated { seed, disps, slots }),
+            Attempt::Collision(a, b) if keys[a] == keys[b] => {
+                return Err(PhfError::DuplicateKey(a.max(b)))
+            }
+            Attempt::Collision(..) | Attempt::Exhausted => {}
+        }
+    }
+    Err(PhfError::SeedsExhausted)
+}
+
+/// An immutable map with perfect hash lookups
+///
+/// Build one with [`generate_const`], [`MapCodegen`] or the tables from
+/// [`generate`]. The fields are public so a `static` can be initialized with
+/// a struct literal; they must come from the same generation run, with
+/// `entries` in the order the keys were passed.
+#[derive(Debug, Clone, Copy)]
+pub struct Map<K: 'static, V: 'static> {
+    #[doc(hidden)]
+    pub seed: u64,
+    #[doc(hidden)]
+    pub disps: &'static [u32],
+    #[doc(hidden)]
+    pub slots: &'static [u32],
+    #[doc(hidden)]
+    pub entries: &'static [(K, V)],
+}
+
+impl<K, V> Map<K, V> {
+    fn index<Q>(&self, key: &Q) -> Option<usize>
+    where
+        Q: PhfKey + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        let n = self.entries.len();
+        if n == 0 {
+            return None;
+        }
+        let hash = key.phf_hash(self.seed);
+        let disp = self.disps[bucket(hash, n)];
+        let index = self.slots[slot(hash, disp, n)] as usize;
+        (self.entries[index].0.borrow() == key).then_some(index)
+    }
+
+    /// Returns the value for `key`
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: PhfKey + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.get_entry(key).map(|(_, value)| value)
+    }
+
+    /// Returns the stored key and value for `key`
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: PhfKey + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.index(key).map(|i| {
+            let (key, value) = &self.entries[i];
+            (key, value)
+        })
+    }
+
+    /// Returns `true` if the map contains `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: PhfKey + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.index(key).is_some()
+    }
+
+    /// Returns the number of entries
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map has no entries
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries in the order they were defined
+    pub fn entries(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Iterates over the keys in the order they were defined
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over the values in the order they were defined
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+/// An immutable set with perfect hash lookups
+#[derive(Debug, Clone, Copy)]
+pub struct Set<T: 'static> {
+    #[doc(hidden)]
+    pub map: Map<T, ()>,
+}
+
+impl<T> Set<T> {
+    /// Returns `true` if the set contains `key`
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        Q: PhfKey + Eq + ?Sized,
+        T: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the stored element equal to `key`
+    pub fn get_key<Q>(&self, key: &Q) -> Option<&T>
+    where
+        Q: PhfKey + Eq + ?Sized,
+        T: Borrow<Q>,
+    {
+        self.map.get_entry(key).map(|(key, _)| key)
+    }
+
+    /// Returns the number of elements
+    pub const fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set has no elements
+    pub const fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the elements in the order they were defined
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.map.keys()
+    }
+}
+
+/// Emits Rust source for a `static` [`Map`], e.g. from a build script
+///
+/// Keys are written with their `Debug` representation, which is valid Rust
+/// for strings, chars and integers. Values are written verbatim.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::phf::MapCodegen;
+///
+/// let mut map = MapCodegen::new();
+/// map.entry("GET", "Method::Get").entry("POST", "Method::Post");
+/// let source = format!(
+///     "static METHODS: ::chibihash::phf::Map<&'static str, Method> = {};",
+///     map.build().unwrap()
+/// );
+/// assert!(source.contains("(\"POST\", Method::Post)"));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct MapCodegen<K> {
+    keys: Vec<K>,
+    values: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl<K: PhfKey + Eq + fmt::Debug> MapCodegen<K> {
+    pub fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Adds an entry; `value` is a Rust expression
+    pub fn entry(&mut self, key: K, value: &str) -> &mut Self {
+        self.keys.push(key);
+        self.values.push(value.to_string());
+        self
+    }
+
+    /// Generates the tables and returns a `Map` struct expression
+    pub fn build(&self) -> Result<String, PhfError> {
+        let generated = generate(&self.keys)?;
+        let entries: Vec<String> = self
+            .keys
+            .iter()
+            .zip(&self.values)
+            .map(|(key, value)| format!("({:?}, {})", key, value))
+            .collect();
+        Ok(map_source(&generated, &entries))
+    }
+}
+
+/// Emits Rust source for a `static` [`Set`], e.g. from a build script
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct SetCodegen<T> {
+    keys: Vec<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T: PhfKey + Eq + fmt::Debug> SetCodegen<T> {
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    /// Adds an element
+    pub fn entry(&mut self, key: T) -> &mut Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Generates the tables and returns a `Set` struct expression
+    pub fn build(&self) -> Result<String, PhfError> {
+        let generated = generate(&self.keys)?;
+        let entries: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| format!("({:?}, ())", key))
+            .collect();
+        Ok(format!(
+            "::chibihash::phf::Set {{ map: {} }}",
+            map_source(&generated, &entries)
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+fn map_source(generated: &Generated, entries: &[String]) -> String {
+    format!(
+        "::chibihash::phf::Map {{ seed: {}, disps: &{:?}, slots: &{:?}, entries: &[{}] }}",
+        generated.seed,
+        generated.disps,
+        generated.slots,
+        entries.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{boxed::Box, format};
+
+    #[test]
+    fn test_generate_is_perfect() {
+        let keys: Vec<String> = (0..5000).map(|i| format!("key-{}", i)).collect();
+        let generated = generate(&keys).unwrap();
+
+        let mut seen = vec![false; keys.len()];
+        for &owner in &generated.slots {
+            assert!(!seen[owner as usize]);
+            seen[owner as usize] = true;
+        }
+
+        let entries: Vec<(String, usize)> = keys.iter().cloned().zip(0..).collect();
+        let map = Map {
+            seed: generated.seed,
+            disps: Box::leak(generated.disps.into_boxed_slice()),
+            slots: Box::leak(generated.slots.into_boxed_slice()),
+            entries: Box::leak(entries.into_boxed_slice()),
+        };
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(map.get(key.as_str()), Some(&i));
+        }
+        assert_eq!(map.get("key-5000"), None);
+    }
+
+    #[test]
+    fn test_generate_duplicate() {
+        assert_eq!(generate(&["a", "b", "a"]), Err(PhfError::DuplicateKey(2)));
+    }
+
+    #[test]
+    fn test_const_matches_runtime() {
+        const KEYS: [&[u8]; 4] = [b"one", b"two", b"three", b""];
+        const TABLE: ConstGenerated<4> = generate_const(&KEYS);
+        let runtime = generate(&KEYS).unwrap();
+        assert_eq!(TABLE.seed, runtime.seed);
+        assert_eq!(&TABLE.disps[..], &runtime.disps[..]);
+        assert_eq!(&TABLE.slots[..], &runtime.slots[..]);
+    }
+
+    #[test]
+    fn test_static_set() {
+        const KEYS: [&[u8]; 2] = [b"alpha", b"beta"];
+        const TABLE: ConstGenerated<2> = generate_const(&KEYS);
+        static SET: Set<&str> = Set {
+            map: Map {
+                seed: TABLE.seed,
+                disps: &TABLE.disps,
+                slots: &TABLE.slots,
+                entries: &[("alpha", ()), ("beta", ())],
+            },
+        };
+        assert!(SET.contains("beta"));
+        assert!(!SET.contains("gamma"));
+        assert_eq!(SET.get_key("alpha"), Some(&"alpha"));
+        assert_eq!(SET.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_empty() {
+        static EMPTY: Map<u32, u32> = Map {
+            seed: 0,
+            disps: &[],
+            slots: &[],
+            entries: &[],
+        };
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.get(&1), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_codegen() {
+        let mut set = SetCodegen::new();
+        set.entry(1u32).entry(2u32);
+        let source = set.build().unwrap();
+        assert!(source.starts_with("::chibihash::phf::Set { map: ::chibihash::phf::Map {"));
+        assert!(source.contains("entries: &[(1, ()), (2, ())]"));
+    }
+}
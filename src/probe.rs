@@ -0,0 +1,135 @@
+//! Double-hashing probe sequences
+//!
+//! [`ProbeSeq`] yields the slots `h1 + i * h2` modulo the table length, for
+//! `i = 0, 1, ...`: the probe order of an open-addressing table with double
+//! hashing, or the positions of a multi-probe filter. Two hashes of the key
+//! are needed; [`v2::chibi_hash64_multi`](crate::v2::chibi_hash64_multi)
+//! gives both from one pass.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::probe::ProbeSeq;
+//! use chibihash::v2::chibi_hash64_multi;
+//!
+//! let mut table: [Option<&str>; 16] = [None; 16];
+//! for key in ["alpha", "beta", "gamma"] {
+//!     let [h1, h2] = chibi_hash64_multi(key.as_bytes(), 0);
+//!     let slot = ProbeSeq::new(h1, h2, table.len())
+//!         .find(|&slot| table[slot].is_none())
+//!         .expect("table is full");
+//!     table[slot] = Some(key);
+//! }
+//! assert_eq!(table.iter().flatten().count(), 3);
+//! ```
+
+/// Iterator over the double-hashing probe sequence of a key
+///
+/// Yields `table_len` slots, starting at `h1 % table_len` and stepping by
+/// `h2 % table_len`. A zero step becomes 1, and for power-of-two lengths the
+/// step is made odd, so the sequence visits every slot exactly once when
+/// the length is a power of two or a prime. Other lengths may repeat slots
+/// when the step shares a factor with the length.
+#[derive(Debug, Clone)]
+pub struct ProbeSeq {
+    pos: usize,
+    step: usize,
+    table_len: usize,
+    remaining: usize,
+}
+
+impl ProbeSeq {
+    /// Creates the probe sequence for hashes `h1` and `h2` in a table of
+    /// `table_len` slots
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table_len` is zero.
+    pub fn new(h1: u64, h2: u64, table_len: usize) -> Self {
+        assert!(table_len > 0, "table length must be non-zero");
+        let len = table_len as u64;
+        let mut step = (h2 % len) as usize;
+        if table_len.is_power_of_two() {
+            step |= 1;
+        }
+        if step == 0 {
+            step = 1;
+        }
+        Self {
+            pos: (h1 % len) as usize,
+            step: step % table_len,
+            table_len,
+            remaining: table_len,
+        }
+    }
+}
+
+impl Iterator for ProbeSeq {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = self.pos;
+        // `pos + step` modulo the length, without overflow
+        let gap = self.table_len - self.step;
+        self.pos = if self.pos >= gap {
+            self.pos - gap
+        } else {
+            self.pos + self.step
+        };
+        Some(slot)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ProbeSeq {}
+
+impl core::iter::FusedIterator for ProbeSeq {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each slot is visited once
+    fn assert_permutation(probes: ProbeSeq, table_len: usize) {
+        let mut seen = [false; 1024];
+        assert_eq!(probes.len(), table_len);
+        for slot in probes {
+            assert!(!seen[slot], "slot {} visited twice", slot);
+            seen[slot] = true;
+        }
+        assert!(seen[..table_len].iter().all(|&s| s));
+    }
+
+    #[test]
+    fn test_probe_seq() {
+        let probes: [usize; 5] = {
+            let mut seq = ProbeSeq::new(3, 4, 7);
+            core::array::from_fn(|_| seq.next().unwrap())
+        };
+        assert_eq!(probes, [3, 0, 4, 1, 5]);
+        let mut single = ProbeSeq::new(5, 0, 1);
+        assert_eq!((single.next(), single.next()), (Some(0), None));
+    }
+
+    #[test]
+    fn test_probe_seq_covers_table() {
+        for i in 0..100u64 {
+            let [h1, h2] = crate::v2::chibi_hash64_multi(&i.to_le_bytes(), 0);
+            for table_len in [1, 2, 16, 1024, 7, 509] {
+                assert_permutation(ProbeSeq::new(h1, h2, table_len), table_len);
+            }
+            // Even and zero steps are adjusted
+            assert_permutation(ProbeSeq::new(h1, 32, 64), 64);
+            assert_permutation(ProbeSeq::new(h1, 0, 13), 13);
+        }
+        let max = ProbeSeq::new(u64::MAX, u64::MAX - 1, usize::MAX);
+        assert!(max.take(3).all(|slot| slot < usize::MAX));
+    }
+}
@@ -0,0 +1,144 @@
+//! Random seeds for DoS-resistant hash maps
+//!
+//! A map whose seed is known, such as the seed 0 used by `ChibiHashMap`,
+//! lets anyone who controls the keys craft collisions and degrade lookups to
+//! linear scans. `ChibiRandomState` in [`v1`](crate::v1) and
+//! [`v2`](crate::v2) gives every map its own seed from [`random_seed`].
+//! `ChibiHashMapRandom` and `ChibiHashSetRandom` use it, and with the
+//! `random-seed` feature so do `ChibiHashMap` and the other collection
+//! aliases, making their `default()` draw a fresh seed per collection.
+//!
+//! Entropy comes from the function passed to [`set_entropy_source`] if one
+//! was set, and otherwise from the operating system through the `getrandom`
+//! feature. The `random` feature alone has no dependencies and is meant for
+//! bare-metal targets that register a hardware RNG.
+//!
+//! With `std`, each thread draws one value of entropy and derives later
+//! seeds from it, like `std::collections::hash_map::RandomState`, so
+//! creating a map does not cost a system call. Without `std`, every seed
+//! draws fresh entropy.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "getrandom")]
+//! # {
+//! use std::collections::HashMap;
+//!
+//! use chibihash::random::random_seed;
+//! use chibihash::v2::{ChibiHashMapRandom, ChibiHasher, ChibiRandomState};
+//!
+//! let mut map: ChibiHashMapRandom<&str, u32> = ChibiHashMapRandom::default();
+//! map.insert("hello", 1);
+//!
+//! let state = ChibiRandomState::new();
+//! println!("seed {:016x}", state.seed());
+//!
+//! // Any hasher can be seeded the same way
+//! let map: HashMap<u32, u32, ChibiHasher> = HashMap::with_hasher(ChibiHasher::new(random_seed()));
+//! # }
+//! ```
+//!
+//! On bare metal, register the hardware RNG once at startup:
+//!
+//! ```rust,ignore
+//! fn trng() -> u64 {
+//!     // Read the RNG peripheral
+//! }
+//!
+//! chibihash::random::set_entropy_source(trng);
+//! ```
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::mix::moremur;
+
+// A `fn() -> u64` stored as a data pointer, or null when unset
+static ENTROPY_SOURCE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Sets the function random seeds draw entropy from
+///
+/// Takes precedence over `getrandom`. The source should return
+/// unpredictable values, e.g. from a hardware RNG. With `std`, threads that
+/// already drew their entropy keep using it.
+pub fn set_entropy_source(source: fn() -> u64) {
+    ENTROPY_SOURCE.store(source as *mut (), Ordering::Release);
+}
+
+/// Returns a fresh, unpredictable seed
+///
+/// # Panics
+///
+/// Panics if no entropy source was set and the `getrandom` feature is
+/// disabled, or if the operating system's random source fails.
+pub fn random_seed() -> u64 {
+    #[cfg(feature = "std")]
+    {
+        use std::cell::Cell;
+
+        std::thread_local! {
+            static NEXT: Cell<u64> = Cell::new(entropy());
+        }
+        // Consecutive counters map to unrelated seeds through the bijective
+        // finalizer, and the starting point is secret
+        NEXT.with(|next| {
+            let counter = next.get();
+            next.set(counter.wrapping_add(1));
+            moremur(counter)
+        })
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        moremur(entropy())
+    }
+}
+
+fn entropy() -> u64 {
+    let source = ENTROPY_SOURCE.load(Ordering::Acquire);
+    if source.is_null() {
+        return os_random();
+    }
+    // SAFETY: only `set_entropy_source` stores a non-null value, and it is
+    // always a `fn() -> u64`
+    let source: fn() -> u64 = unsafe { core::mem::transmute(source) };
+    source()
+}
+
+#[cfg(feature = "getrandom")]
+fn os_random() -> u64 {
+    getrandom::u64().expect("failed to obtain a random seed")
+}
+
+#[cfg(not(feature = "getrandom"))]
+fn os_random() -> u64 {
+    panic!("no entropy source: enable the `getrandom` feature or call `set_entropy_source`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::AtomicU64;
+
+    // Distinct values, so tests sharing the source still get distinct seeds
+    fn counting_source() -> u64 {
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        set_entropy_source(counting_source);
+        let a = random_seed();
+        let b = random_seed();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_entropy_source() {
+        set_entropy_source(counting_source);
+        let before = counting_source();
+        entropy();
+        assert!(counting_source() > before + 1);
+    }
+}
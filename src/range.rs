@@ -0,0 +1,121 @@
+//! Mapping hashes onto `0..n`
+//!
+//! `hash % n` favours the low residues whenever `n` does not divide 2^64,
+//! and only looks at the low bits of the hash. [`bounded`] uses Lemire's
+//! multiply-shift instead: `(hash * n) >> 64` in 128-bit arithmetic. It
+//! takes the high bits, costs one multiply instead of a division, and its
+//! bias is at most `n / 2^64` per value, far below anything measurable for
+//! realistic `n`.
+//!
+//! [`bounded_mod`] is the modulo variant, for layouts that already assign
+//! buckets by `hash % n`. The two give different buckets for the same hash.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::range::hash_to_range;
+//!
+//! let shard = hash_to_range(b"user:1234", 0, 12);
+//! assert!(shard < 12);
+//! ```
+
+use crate::mix::reduce;
+use crate::v2::chibi_hash64;
+
+/// Maps `hash` onto `0..n` with Lemire's multiply-shift
+///
+/// Uniformly distributed hashes give uniformly distributed results, and the
+/// order of hashes is kept: larger hashes never map to smaller values.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::range::bounded;
+///
+/// assert_eq!(bounded(0, 10), 0);
+/// assert_eq!(bounded(u64::MAX, 10), 9);
+/// assert_eq!(bounded(1 << 63, 10), 5);
+/// ```
+#[inline]
+pub const fn bounded(hash: u64, n: u64) -> u64 {
+    assert!(n > 0, "range must be non-empty");
+    reduce(hash, n)
+}
+
+/// Maps `hash` onto `0..n` with `hash % n`
+///
+/// Only as uniform as the low bits of `hash`, and slower than [`bounded`];
+/// use it to keep compatibility with existing modulo bucketing.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+#[inline]
+pub const fn bounded_mod(hash: u64, n: u64) -> u64 {
+    assert!(n > 0, "range must be non-empty");
+    hash % n
+}
+
+// Maps `hash` onto `[0, 1)` from its top 53 bits, every `f64` multiple of
+// 2^-53 being equally likely
+#[inline]
+pub(crate) fn unit_interval(hash: u64) -> f64 {
+    (hash >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Hashes `key` with `v2::chibi_hash64` and maps the hash onto `0..n` with
+/// [`bounded`]
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn hash_to_range(key: &[u8], seed: u64, n: u64) -> u64 {
+    bounded(chibi_hash64(key, seed), n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded() {
+        assert_eq!(bounded(12345, 1), 0);
+        assert_eq!(bounded(u64::MAX, u64::MAX), u64::MAX - 1);
+        assert_eq!(bounded_mod(17, 5), 2);
+
+        // Evenly spaced hashes land in equal shares
+        let mut counts = [0u32; 3];
+        for i in 0..3000u128 {
+            counts[bounded(((i << 64).div_ceil(3000)) as u64, 3) as usize] += 1;
+        }
+        assert_eq!(counts, [1000; 3]);
+    }
+
+    #[test]
+    fn test_unit_interval() {
+        assert_eq!(unit_interval(0), 0.0);
+        assert_eq!(unit_interval(1 << 63), 0.5);
+        assert!(unit_interval(u64::MAX) < 1.0);
+    }
+
+    #[test]
+    fn test_hash_to_range() {
+        let mut counts = [0u32; 10];
+        for i in 0..10_000u32 {
+            let bucket = hash_to_range(&i.to_le_bytes(), 1, 10);
+            assert_eq!(bucket, bounded(chibi_hash64(&i.to_le_bytes(), 1), 10));
+            counts[bucket as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| (900..1100).contains(&c)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bounded_empty() {
+        bounded(1, 0);
+    }
+}
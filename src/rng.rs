@@ -0,0 +1,143 @@
+//! A counter-based random number generator (feature `rng`)
+//!
+//! [`ChibiRng`] returns `v2::chibi_hash64(&counter.to_le_bytes(), seed)` for
+//! `counter = 0, 1, 2, ...`. Its output depends only on the seed and the
+//! position, so it is the same on every platform and in every release, and
+//! any position can be reached in O(1) with
+//! [`set_counter`](ChibiRng::set_counter).
+//!
+//! It implements the `rand_core` 0.10 traits: `TryRng` with an infallible
+//! error, which makes it a `Rng` (formerly `RngCore`), and `SeedableRng`.
+//! It is not a cryptographic generator.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::rng::ChibiRng;
+//! use rand_core::{Rng, SeedableRng};
+//!
+//! let mut rng = ChibiRng::seed_from_u64(42);
+//! let roll = rng.next_u64() % 6 + 1;
+//!
+//! let mut replay = ChibiRng::new(42);
+//! assert_eq!(replay.next_u64() % 6 + 1, roll);
+//! ```
+
+use core::convert::Infallible;
+
+use rand_core::{SeedableRng, TryRng};
+
+use crate::v2::chibi_hash64;
+
+/// A counter-based generator built on ChibiHash; see the [module
+/// documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChibiRng {
+    seed: u64,
+    counter: u64,
+}
+
+impl ChibiRng {
+    /// Creates a generator at position 0 of the stream for `seed`
+    pub const fn new(seed: u64) -> Self {
+        Self { seed, counter: 0 }
+    }
+
+    /// Returns the seed
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of `u64` outputs produced so far
+    pub const fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Moves to position `counter` of the stream
+    pub fn set_counter(&mut self, counter: u64) {
+        self.counter = counter;
+    }
+
+    #[inline]
+    fn step(&mut self) -> u64 {
+        let x = chibi_hash64(&self.counter.to_le_bytes(), self.seed);
+        self.counter = self.counter.wrapping_add(1);
+        x
+    }
+}
+
+impl TryRng for ChibiRng {
+    type Error = Infallible;
+
+    /// Returns the high half of the next `u64`
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        Ok((self.step() >> 32) as u32)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+        Ok(self.step())
+    }
+
+    /// Fills `dst` with consecutive outputs as little-endian bytes; a
+    /// partial last output is truncated
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Infallible> {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.step().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+impl SeedableRng for ChibiRng {
+    /// The seed as a little-endian `u64`
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: [u8; 8]) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    /// Equal to [`ChibiRng::new`]
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand_core::Rng;
+
+    #[test]
+    fn test_chibi_rng() {
+        let mut rng = ChibiRng::seed_from_u64(7);
+        assert_eq!(rng, ChibiRng::from_seed(7u64.to_le_bytes()));
+        for i in 0..10u64 {
+            assert_eq!(rng.next_u64(), chibi_hash64(&i.to_le_bytes(), 7));
+        }
+        assert_eq!(rng.counter(), 10);
+        assert_eq!(
+            rng.next_u32(),
+            (chibi_hash64(&10u64.to_le_bytes(), 7) >> 32) as u32
+        );
+
+        rng.set_counter(3);
+        assert_eq!(rng.next_u64(), chibi_hash64(&3u64.to_le_bytes(), 7));
+        assert_ne!(ChibiRng::new(8).next_u64(), ChibiRng::new(7).next_u64());
+    }
+
+    #[test]
+    fn test_fill_bytes() {
+        let mut bytes = [0u8; 20];
+        let mut rng = ChibiRng::new(1);
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(rng.counter(), 3);
+
+        let mut words = ChibiRng::new(1);
+        let expected: [[u8; 8]; 3] = core::array::from_fn(|_| words.next_u64().to_le_bytes());
+        assert_eq!(bytes[..8], expected[0]);
+        assert_eq!(bytes[8..16], expected[1]);
+        assert_eq!(bytes[16..], expected[2][..4]);
+    }
+}
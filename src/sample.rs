@@ -0,0 +1,294 @@
+//! Consistent sampling by hash
+//!
+//! Sampling decisions derived from a hash of the key, rather than from a
+//! random number generator, come out the same on every host: a distributed
+//! log pipeline keeps or drops all events of a trace together, without
+//! coordination.
+//!
+//! - [`keep`]: keeps each key with a fixed probability.
+//! - [`BottomK`]: keeps the `k` items of smallest hash-derived rank,
+//!   optionally weighted, so shards sampled with the same seed can be
+//!   merged into a sample of their union.
+//!
+//! `keep` hashes keys with `v2` under a domain of its own, so sampling does
+//! not line up with other uses of the same seed, such as partitioning.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::sample::keep;
+//!
+//! // Keep 1% of traces, the same ones on every host
+//! let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+//! if keep(trace_id, 0, 0.01) {
+//!     println!("sampled {}", trace_id);
+//! }
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::hash::Hash;
+
+use crate::range::unit_interval;
+use crate::sketch::MergeError;
+use crate::v2::{chibi_hash64, domain_seed, hash_one};
+
+const DOMAIN: &[u8] = b"chibihash-sample";
+
+/// Returns `true` if `key` is kept at sampling rate `rate`
+///
+/// The hash of `key` is mapped to `[0, 1)` and compared with `rate`, so a
+/// key kept at some rate is kept at every higher rate, and samples taken
+/// at different rates are nested. Rates of 0 or less and NaN keep nothing,
+/// and rates of 1 or more keep everything.
+pub fn keep<K: AsRef<[u8]>>(key: K, seed: u64, rate: f64) -> bool {
+    unit_interval(chibi_hash64(key.as_ref(), domain_seed(DOMAIN, seed))) < rate
+}
+
+/// A bottom-k sample: the `k` items of smallest rank
+///
+/// An item's rank is `u / weight`, where `u` in `(0, 1]` comes from the
+/// item's ChibiHash, so the rank is a property of the item and not of the
+/// order or the shard it arrives in. Unweighted, this is a uniform sample
+/// of the distinct items; weighted, it is a priority sample that favours
+/// heavy items. An item inserted again is not sampled twice, provided it
+/// keeps the same weight.
+///
+/// Samples of different shards taken with the same `k` and seed merge into
+/// exactly the sample of the union of the shards.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sample::BottomK;
+///
+/// let mut east = BottomK::new(3);
+/// let mut west = BottomK::new(3);
+/// for i in 0..1000 {
+///     east.insert(i);
+/// }
+/// for i in 500..2000 {
+///     west.insert(i);
+/// }
+/// east.merge(&west).unwrap();
+///
+/// let mut all = BottomK::new(3);
+/// for i in 0..2000 {
+///     all.insert(i);
+/// }
+/// assert!(east.iter().eq(all.iter()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BottomK<T> {
+    k: usize,
+    // Sorted by rank
+    entries: Vec<Entry<T>>,
+    seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry<T> {
+    rank: f64,
+    hash: u64,
+    weight: f64,
+    item: T,
+}
+
+impl<T: Hash + Eq> BottomK<T> {
+    /// Creates an empty sample of at most `k` items, with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0, "k must be non-zero");
+        Self {
+            k,
+            entries: Vec::with_capacity(k),
+            seed: 0,
+        }
+    }
+
+    /// Returns the sample emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Offers `item` with weight 1
+    pub fn insert(&mut self, item: T) {
+        self.insert_weighted(item, 1.0);
+    }
+
+    /// Offers `item` with `weight`
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `weight` is positive.
+    pub fn insert_weighted(&mut self, item: T, weight: f64) {
+        assert!(weight > 0.0, "weight must be positive");
+        let hash = hash_one(&item, self.seed);
+        let u = ((hash >> 11) + 1) as f64 * (1.0 / (1u64 << 53) as f64);
+        self.offer(Entry {
+            rank: u / weight,
+            hash,
+            weight,
+            item,
+        });
+    }
+
+    /// Adds the items of `other`, which must have the same `k` and seed,
+    /// giving the sample of both inputs combined
+    pub fn merge(&mut self, other: &Self) -> Result<(), MergeError>
+    where
+        T: Clone,
+    {
+        if self.k != other.k || self.seed != other.seed {
+            return Err(MergeError);
+        }
+        for entry in &other.entries {
+            self.offer(entry.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns the sampled items, smallest rank first
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.entries.iter().map(|e| &e.item)
+    }
+
+    /// Returns the sampled items with their weights, smallest rank first
+    pub fn iter_weighted(&self) -> impl Iterator<Item = (&T, f64)> + '_ {
+        self.entries.iter().map(|e| (&e.item, e.weight))
+    }
+
+    /// Returns the largest rank in the sample once it holds `k` items
+    ///
+    /// Every item of smaller rank offered so far is in the sample, which is
+    /// what estimators over bottom-k samples build on.
+    pub fn threshold(&self) -> Option<f64> {
+        if self.entries.len() < self.k {
+            return None;
+        }
+        self.entries.last().map(|e| e.rank)
+    }
+
+    /// Removes every item
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the number of sampled items, at most `k`
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no item has been offered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the maximum number of sampled items
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn offer(&mut self, entry: Entry<T>) {
+        if self.threshold().is_some_and(|t| entry.rank >= t) {
+            return;
+        }
+        let pos = self.entries.partition_point(|e| e.rank < entry.rank);
+        let duplicate = self.entries[pos..]
+            .iter()
+            .take_while(|e| e.rank == entry.rank)
+            .any(|e| e.hash == entry.hash && e.item == entry.item);
+        if !duplicate {
+            self.entries.insert(pos, entry);
+            self.entries.truncate(self.k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep() {
+        let kept = |seed: u64, rate: f64| {
+            (0..10_000u32).filter(move |i| keep(i.to_le_bytes(), seed, rate))
+        };
+        assert_eq!(kept(0, 0.0).count(), 0);
+        assert_eq!(kept(0, f64::NAN).count(), 0);
+        assert_eq!(kept(0, 1.0).count(), 10_000);
+        assert!((400..600).contains(&kept(0, 0.05).count()));
+        assert!(kept(0, 0.05).all(|i| keep(i.to_le_bytes(), 0, 0.5)));
+        assert!(kept(0, 0.05).ne(kept(1, 0.05)));
+        assert_eq!(keep("key", 3, 0.5), keep(b"key", 3, 0.5));
+    }
+
+    #[test]
+    fn test_bottom_k() {
+        let mut sample = BottomK::new(100).with_seed(6);
+        for i in 0..50u32 {
+            sample.insert(i);
+            sample.insert(i);
+        }
+        assert_eq!(sample.len(), 50);
+        assert_eq!(sample.threshold(), None);
+        for i in 0..10_000u32 {
+            sample.insert(i);
+        }
+        assert_eq!(sample.len(), 100);
+        let t = sample.threshold().unwrap();
+        // The k-th smallest of n uniform ranks is close to k / n
+        assert!((0.008..0.012).contains(&t), "{}", t);
+
+        // Insertion order does not matter
+        let mut reversed = BottomK::new(100).with_seed(6);
+        for i in (0..10_000u32).rev() {
+            reversed.insert(i);
+        }
+        assert_eq!(reversed, sample);
+
+        sample.clear();
+        assert!(sample.is_empty());
+    }
+
+    #[test]
+    fn test_bottom_k_weighted() {
+        let mut sample = BottomK::new(10);
+        for i in 0..1000u32 {
+            sample.insert_weighted(i, if i < 10 { 1e6 } else { 1.0 });
+        }
+        let mut heavy: Vec<u32> = sample.iter().copied().collect();
+        heavy.sort_unstable();
+        assert_eq!(heavy, (0..10).collect::<Vec<_>>());
+        assert!(sample.iter_weighted().all(|(_, w)| w == 1e6));
+    }
+
+    #[test]
+    fn test_bottom_k_merge() {
+        let mut a = BottomK::new(20).with_seed(1);
+        let mut b = BottomK::new(20).with_seed(1);
+        let mut all = BottomK::new(20).with_seed(1);
+        for i in 0..3000u32 {
+            [&mut a, &mut b][(i % 2) as usize].insert(i);
+            all.insert(i);
+        }
+        a.merge(&b).unwrap();
+        assert_eq!(a, all);
+        assert_eq!(a.merge(&BottomK::new(20)), Err(MergeError));
+        assert_eq!(a.merge(&BottomK::new(10).with_seed(1)), Err(MergeError));
+    }
+}
@@ -0,0 +1,114 @@
+//! Helpers for deriving a seed from several identifiers
+//!
+//! Combining identifiers with XOR (`app_id ^ tenant_id ^ epoch`) is tempting
+//! but weak: the operands commute, equal values cancel out and a single bit
+//! flip in one part flips exactly one bit of the seed. [`mix_seeds`] instead
+//! feeds every part through the `v2` streaming hasher, which gives:
+//!
+//! - Order sensitivity: `[a, b]` and `[b, a]` produce different seeds.
+//! - Length sensitivity: `[a]` and `[a, 0]` produce different seeds.
+//! - Full diffusion: changing any bit of any part changes every output bit
+//!   with probability close to 1/2, so seeds derived from related identifiers
+//!   behave as if they were drawn independently.
+//!
+//! The mixing is fixed and portable, the same parts give the same seed on
+//! every platform.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::{chibi_hash64, mix_seeds, Seed};
+//!
+//! let seed = Seed::from_parts(7, 1234, 2024);
+//! assert_eq!(seed.get(), mix_seeds(&[7, 1234, 2024]));
+//!
+//! let hash = chibi_hash64(b"user:42", seed.get());
+//! println!("{:016x}", hash);
+//! ```
+
+use crate::v2::StreamingChibiHasher;
+
+// Keeps `mix_seeds(&[x])` distinct from hashing the bytes of `x` directly
+const MIX_SEED: u64 = 0x5EED_5EED_5EED_5EED;
+
+/// Combines several seed sources into one well-mixed seed
+///
+/// Each part is fed to the `v2` streaming hasher as 8 little-endian bytes.
+pub fn mix_seeds(parts: &[u64]) -> u64 {
+    let mut hasher = StreamingChibiHasher::new(MIX_SEED);
+    for part in parts {
+        hasher.update(&part.to_le_bytes());
+    }
+    hasher.finalize()
+}
+
+/// A seed value, optionally derived from several identifiers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seed(u64);
+
+impl Seed {
+    /// Wraps a raw seed value as-is
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Derives a seed from an application, tenant and epoch identifier
+    ///
+    /// Equivalent to `Seed::new(mix_seeds(&[app_id, tenant_id, epoch]))`.
+    pub fn from_parts(app_id: u64, tenant_id: u64, epoch: u64) -> Self {
+        Self(mix_seeds(&[app_id, tenant_id, epoch]))
+    }
+
+    /// Returns the raw seed value
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for Seed {
+    fn from(seed: u64) -> Self {
+        Self(seed)
+    }
+}
+
+impl From<Seed> for u64 {
+    fn from(seed: Seed) -> Self {
+        seed.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_seeds_order_sensitive() {
+        assert_ne!(mix_seeds(&[1, 2]), mix_seeds(&[2, 1]));
+        assert_ne!(mix_seeds(&[1, 1]), mix_seeds(&[2, 2]));
+    }
+
+    #[test]
+    fn test_mix_seeds_length_sensitive() {
+        assert_ne!(mix_seeds(&[]), mix_seeds(&[0]));
+        assert_ne!(mix_seeds(&[5]), mix_seeds(&[5, 0]));
+    }
+
+    #[test]
+    fn test_mix_seeds_diffusion() {
+        let base = mix_seeds(&[1, 2, 3]);
+        for bit in 0..64 {
+            let flipped = mix_seeds(&[1 ^ (1 << bit), 2, 3]);
+            let changed = (base ^ flipped).count_ones();
+            assert!(changed > 10, "bit {} changed only {} bits", bit, changed);
+        }
+    }
+
+    #[test]
+    fn test_seed_from_parts() {
+        let seed = Seed::from_parts(7, 1234, 2024);
+        assert_eq!(seed.get(), mix_seeds(&[7, 1234, 2024]));
+        assert_ne!(seed, Seed::from_parts(1234, 7, 2024));
+        assert_eq!(u64::from(Seed::from(42)), 42);
+    }
+}
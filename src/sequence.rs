@@ -0,0 +1,100 @@
+//! Deterministic `u64` streams derived from a key
+//!
+//! A [`Sequence`] turns one key into as many well-mixed `u64`s as needed,
+//! e.g. per-field salts or probe offsets, and any of them can be read
+//! directly by index. Element `i` is
+//! `v2::chibi_hash64(&i.to_le_bytes(), stream)`, where `stream` is the hash
+//! of the key under a domain of its own, so sequences do not line up with
+//! plain hashes of the same key and seed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::sequence::Sequence;
+//!
+//! let mut salts = Sequence::new(b"table:users", 0);
+//! let email_salt = salts.next().unwrap();
+//! let phone_salt = salts.next().unwrap();
+//! assert_ne!(email_salt, phone_salt);
+//! assert_eq!(Sequence::new(b"table:users", 0).get(1), phone_salt);
+//! ```
+
+use crate::v2::{chibi_hash64, domain_seed};
+
+const DOMAIN: &[u8] = b"chibihash-sequence";
+
+/// An endless stream of `u64`s determined by a key and seed
+///
+/// Implements [`Iterator`], with [`nth`](Iterator::nth) skipping ahead in
+/// O(1); [`get`](Self::get) reads any element without moving.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    stream: u64,
+    index: u64,
+}
+
+impl Sequence {
+    /// Creates the sequence of `key` under `seed`, starting at element 0
+    pub fn new(key: &[u8], seed: u64) -> Self {
+        Self {
+            stream: chibi_hash64(key, domain_seed(DOMAIN, seed)),
+            index: 0,
+        }
+    }
+
+    /// Returns element `index`, wherever the iterator is
+    pub fn get(&self, index: u64) -> u64 {
+        chibi_hash64(&index.to_le_bytes(), self.stream)
+    }
+
+    /// Returns the index of the element [`next`](Iterator::next) returns
+    pub fn position(&self) -> u64 {
+        self.index
+    }
+}
+
+impl Iterator for Sequence {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let x = self.get(self.index);
+        self.index = self.index.wrapping_add(1);
+        Some(x)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<u64> {
+        self.index = self.index.wrapping_add(n as u64);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence() {
+        let seq = Sequence::new(b"key", 3);
+        let first: [u64; 4] = {
+            let mut seq = seq.clone();
+            core::array::from_fn(|_| seq.next().unwrap())
+        };
+        for (i, &x) in first.iter().enumerate() {
+            assert_eq!(seq.get(i as u64), x);
+        }
+        assert!(first[1..].iter().all(|&x| x != first[0]));
+
+        let mut skip = seq.clone();
+        assert_eq!(skip.nth(2), Some(first[2]));
+        assert_eq!(skip.position(), 3);
+        assert_eq!(skip.next(), Some(first[3]));
+
+        assert_ne!(Sequence::new(b"key", 4).get(0), first[0]);
+        assert_ne!(Sequence::new(b"kez", 3).get(0), first[0]);
+        assert_ne!(chibi_hash64(b"key", 3), first[0]);
+    }
+}
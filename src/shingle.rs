@@ -0,0 +1,264 @@
+//! Hashes of overlapping windows (shingles) for near-duplicate detection
+//!
+//! - [`shingles`]: every overlapping `k`-byte window of a byte string.
+//! - [`char_ngrams`]: every overlapping `n`-character window of a string.
+//! - [`token_shingles`]: every run of `k` consecutive tokens, e.g. words.
+//! - [`word_shingles`]: every run of `k` consecutive whitespace-separated
+//!   words of a string.
+//!
+//! Windows are hashed in place; no intermediate strings are allocated. The
+//! resulting hashes can be fed straight into the [`MinHash`] and
+//! [`SimHash`] sketches. All hashes use `v2`.
+//!
+//! [`MinHash`]: crate::sketch::MinHash
+//! [`SimHash`]: crate::sketch::SimHash
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::shingle::{char_ngrams, shingles, token_shingles, word_shingles};
+//! use chibihash::sketch::MinHash;
+//!
+//! assert_eq!(shingles(b"abcde", 3, 0).count(), 3);
+//! assert_eq!(char_ngrams("héllo", 3, 0).count(), 3);
+//!
+//! let a: Vec<u64> = token_shingles("the quick brown fox".split(' '), 2, 0).collect();
+//! let b: Vec<u64> = token_shingles("a quick brown dog".split(' '), 2, 0).collect();
+//! assert_eq!(a[1], b[1]); // "quick brown"
+//!
+//! let mut signature = MinHash::new(128);
+//! signature.extend(word_shingles("the quick  brown fox", 2, 0));
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::slice::Windows;
+use core::str::SplitWhitespace;
+
+use crate::v2::{chibi_hash64, StreamingChibiHasher};
+
+/// Returns an iterator over the hashes of every `k`-byte window of `bytes`
+///
+/// Yields `bytes.len() - k + 1` hashes, or none if `bytes` is shorter than
+/// `k`.
+///
+/// # Panics
+///
+/// Panics if `k` is zero.
+pub fn shingles(bytes: &[u8], k: usize, seed: u64) -> Shingles<'_> {
+    assert!(k > 0, "shingle size must be non-zero");
+    Shingles {
+        windows: bytes.windows(k),
+        seed,
+    }
+}
+
+/// Iterator returned by [`shingles`]
+#[derive(Debug, Clone)]
+pub struct Shingles<'a> {
+    windows: Windows<'a, u8>,
+    seed: u64,
+}
+
+impl Iterator for Shingles<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.windows.next().map(|w| chibi_hash64(w, self.seed))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.windows.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Shingles<'_> {}
+
+/// Returns an iterator over the hashes of every `n`-character window of
+/// `text`
+///
+/// Windows are counted in `char`s, not bytes, and each is hashed as its
+/// UTF-8 bytes. Yields none if `text` has fewer than `n` characters.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn char_ngrams(text: &str, n: usize, seed: u64) -> CharNgrams<'_> {
+    assert!(n > 0, "n-gram size must be non-zero");
+    CharNgrams {
+        text,
+        start: 0,
+        end: text
+            .char_indices()
+            .nth(n - 1)
+            .map(|(i, c)| i + c.len_utf8()),
+        seed,
+    }
+}
+
+/// Iterator returned by [`char_ngrams`]
+#[derive(Debug, Clone)]
+pub struct CharNgrams<'a> {
+    text: &'a str,
+    // Byte range of the next window, `None` once past the end
+    start: usize,
+    end: Option<usize>,
+    seed: u64,
+}
+
+impl Iterator for CharNgrams<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let end = self.end?;
+        let hash = chibi_hash64(&self.text.as_bytes()[self.start..end], self.seed);
+        let first = self.text[self.start..].chars().next()?;
+        self.start += first.len_utf8();
+        self.end = self.text[end..].chars().next().map(|c| end + c.len_utf8());
+        Some(hash)
+    }
+}
+
+/// Returns an iterator over the hashes of every run of `k` consecutive tokens
+///
+/// Each token is hashed once and the shingle hash is computed from the `k`
+/// token hashes, so tokens are never concatenated. Token boundaries are
+/// preserved: `["ab", "c"]` and `["a", "bc"]` give different shingles.
+///
+/// # Panics
+///
+/// Panics if `k` is zero.
+pub fn token_shingles<I>(tokens: I, k: usize, seed: u64) -> TokenShingles<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    assert!(k > 0, "shingle size must be non-zero");
+    TokenShingles {
+        tokens: tokens.into_iter(),
+        window: Vec::with_capacity(k),
+        next: 0,
+        k,
+        seed,
+    }
+}
+
+/// Returns an iterator over the hashes of every run of `k` consecutive
+/// words of `text`
+///
+/// Words are separated by any whitespace, so `"a  b"` and `"a b"` give the
+/// same shingles. Equal to `token_shingles(text.split_whitespace(), k, seed)`.
+///
+/// # Panics
+///
+/// Panics if `k` is zero.
+pub fn word_shingles(text: &str, k: usize, seed: u64) -> TokenShingles<SplitWhitespace<'_>> {
+    token_shingles(text.split_whitespace(), k, seed)
+}
+
+/// Iterator returned by [`token_shingles`]
+#[derive(Debug, Clone)]
+pub struct TokenShingles<I> {
+    tokens: I,
+    // Ring buffer of the last `k` token hashes, oldest at `next`
+    window: Vec<u64>,
+    next: usize,
+    k: usize,
+    seed: u64,
+}
+
+impl<I> Iterator for TokenShingles<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            let token = chibi_hash64(self.tokens.next()?.as_ref(), self.seed);
+            if self.window.len() < self.k {
+                self.window.push(token);
+                if self.window.len() < self.k {
+                    continue;
+                }
+            } else {
+                self.window[self.next] = token;
+                self.next = (self.next + 1) % self.k;
+            }
+            break;
+        }
+
+        let mut hasher = StreamingChibiHasher::new(self.seed);
+        let (newer, older) = self.window.split_at(self.next);
+        for hash in older.iter().chain(newer) {
+            hasher.update(&hash.to_le_bytes());
+        }
+        Some(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shingles() {
+        let hashes: Vec<u64> = shingles(b"abcd", 2, 5).collect();
+        assert_eq!(
+            hashes,
+            [
+                chibi_hash64(b"ab", 5),
+                chibi_hash64(b"bc", 5),
+                chibi_hash64(b"cd", 5)
+            ]
+        );
+        assert_eq!(shingles(b"ab", 3, 5).len(), 0);
+    }
+
+    #[test]
+    fn test_char_ngrams() {
+        let hashes: Vec<u64> = char_ngrams("héllo", 2, 5).collect();
+        let expected: Vec<u64> = ["hé", "él", "ll", "lo"]
+            .iter()
+            .map(|s| chibi_hash64(s.as_bytes(), 5))
+            .collect();
+        assert_eq!(hashes, expected);
+        assert_eq!(char_ngrams("日本語", 3, 0).count(), 1);
+        assert_eq!(char_ngrams("日本", 3, 0).count(), 0);
+        assert_eq!(char_ngrams("", 1, 0).count(), 0);
+    }
+
+    #[test]
+    fn test_word_shingles() {
+        let a: Vec<u64> = word_shingles(" the  quick\tbrown fox\n", 2, 1).collect();
+        let b: Vec<u64> = token_shingles(["the", "quick", "brown", "fox"], 2, 1).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_token_shingles_are_position_independent() {
+        let a: Vec<u64> = token_shingles(["x", "a", "b", "c"], 3, 0).collect();
+        let b: Vec<u64> = token_shingles(["a", "b", "c", "y"], 3, 0).collect();
+        assert_eq!(a.len(), 2);
+        assert_eq!(a[1], b[0]);
+        assert_ne!(a[0], a[1]);
+    }
+
+    #[test]
+    fn test_token_shingles_order_and_boundaries() {
+        let ab = token_shingles(["a", "b"], 2, 0).next();
+        let ba = token_shingles(["b", "a"], 2, 0).next();
+        assert_ne!(ab, ba);
+
+        let split1 = token_shingles(["ab", "c"], 2, 0).next();
+        let split2 = token_shingles(["a", "bc"], 2, 0).next();
+        assert_ne!(split1, split2);
+
+        assert_eq!(token_shingles(["a"], 2, 0).next(), None);
+    }
+}
@@ -0,0 +1,30 @@
+//! Reproducible shuffling
+//!
+//! [`deterministic_shuffle`] orders a slice the same way for a given seed
+//! and length on every platform and in every release: the random indices
+//! are `v2` hashes of `u64` counters, never of `usize`, and the order is
+//! pinned by tests. Use it for test fixtures, or to spread work over
+//! workers in an order every host agrees on.
+//!
+//! It is [`permute::shuffle_in_place`] under a name that states the promise;
+//! to visit a range in shuffled order without materializing it, see
+//! [`permute::permute`].
+//!
+//! [`permute::shuffle_in_place`]: crate::permute::shuffle_in_place
+//! [`permute::permute`]: crate::permute::permute
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::shuffle::deterministic_shuffle;
+//!
+//! let mut hosts = ["a", "b", "c", "d", "e"];
+//! deterministic_shuffle(&mut hosts, 2024);
+//! let mut again = ["a", "b", "c", "d", "e"];
+//! deterministic_shuffle(&mut again, 2024);
+//! assert_eq!(hosts, again);
+//! ```
+
+/// Shuffles `slice` in place with Fisher-Yates, drawing the swap for
+/// position `i` from `v2::chibi_hash64(&(i as u64).to_le_bytes(), seed)`
+pub use crate::permute::shuffle_in_place as deterministic_shuffle;
@@ -0,0 +1,216 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::f64::consts::E;
+use core::hash::Hash;
+
+use super::MergeError;
+use crate::math::ln;
+use crate::mix::reduce;
+use crate::seed::mix_seeds;
+use crate::v2::hash_one;
+
+/// A Count-Min sketch: approximate item counts in `width * depth` counters
+///
+/// Each of the `depth` rows hashes an item with its own seed, derived from
+/// the sketch seed with [`mix_seeds`], to pick one counter. An estimate is
+/// the smallest of the item's counters, so it never undercounts; with
+/// [`with_error`](Self::with_error) sizing it overcounts by more than
+/// `epsilon` times the total count with probability at most `delta`.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::CountMinSketch;
+///
+/// let mut sketch = CountMinSketch::with_error(0.001, 0.01);
+/// for word in "the cat and the hat and the bat".split(' ') {
+///     sketch.add(word, 1);
+/// }
+/// assert!(sketch.estimate("the") >= 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountMinSketch {
+    width: usize,
+    row_seeds: Vec<u64>,
+    counters: Vec<u64>,
+    total: u64,
+    seed: u64,
+}
+
+impl CountMinSketch {
+    /// Creates an empty sketch with `depth` rows of `width` counters, with
+    /// seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn new(width: usize, depth: usize) -> Self {
+        assert!(width > 0 && depth > 0, "width and depth must be non-zero");
+        let mut sketch = Self {
+            width,
+            row_seeds: vec![0; depth],
+            counters: vec![0; width * depth],
+            total: 0,
+            seed: 0,
+        };
+        sketch.set_seed(0);
+        sketch
+    }
+
+    /// Creates an empty sketch whose estimates exceed the true count by at
+    /// most `epsilon` times the total count, except with probability
+    /// `delta`
+    ///
+    /// Uses `width = ceil(e / epsilon)` and `depth = ceil(ln(1 / delta))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `epsilon > 0` and `0 < delta < 1`.
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        assert!(delta > 0.0 && delta < 1.0, "delta must be between 0 and 1");
+        let width = (E / epsilon) as usize + 1;
+        let depth = (-ln(delta)) as usize + 1;
+        Self::new(width, depth)
+    }
+
+    /// Returns the sketch emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.set_seed(seed);
+        self
+    }
+
+    /// Adds `count` occurrences of `item`
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        for row in 0..self.depth() {
+            let i = self.index(row, item);
+            self.counters[i] = self.counters[i].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Adds `count` occurrences of `item` with conservative update
+    ///
+    /// Only the counters below the item's new estimate are raised, which
+    /// keeps estimates of other items lower than [`add`](Self::add) does.
+    /// Sketches updated this way can still be merged, but the merged
+    /// estimates lose that advantage.
+    pub fn add_conservative<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        let target = self.estimate(item).saturating_add(count);
+        for row in 0..self.depth() {
+            let i = self.index(row, item);
+            self.counters[i] = self.counters[i].max(target);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Returns an estimate of the number of occurrences of `item`, never
+    /// below the true count
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        (0..self.depth())
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Adds the counts of `other`, which must have the same width, depth
+    /// and seed
+    pub fn merge(&mut self, other: &Self) -> Result<(), MergeError> {
+        if self.width != other.width || self.row_seeds != other.row_seeds {
+            return Err(MergeError);
+        }
+        for (a, b) in self.counters.iter_mut().zip(&other.counters) {
+            *a = a.saturating_add(*b);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    /// Returns the total count added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Resets every counter to zero
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.total = 0;
+    }
+
+    /// Returns the number of counters per row
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows
+    pub fn depth(&self) -> usize {
+        self.row_seeds.len()
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for (row, row_seed) in self.row_seeds.iter_mut().enumerate() {
+            *row_seed = mix_seeds(&[seed, row as u64]);
+        }
+    }
+
+    fn index<T: Hash + ?Sized>(&self, row: usize, item: &T) -> usize {
+        let column = reduce(hash_one(item, self.row_seeds[row]), self.width as u64);
+        row * self.width + column as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_error() {
+        let sketch = CountMinSketch::with_error(0.01, 0.01);
+        assert_eq!((sketch.width(), sketch.depth()), (272, 5));
+    }
+
+    #[test]
+    fn test_count_min_sketch() {
+        let mut sketch = CountMinSketch::new(200, 4).with_seed(5);
+        let mut conservative = sketch.clone();
+        for i in 0..1000u32 {
+            let count = u64::from(i % 10 + 1);
+            sketch.add(&i, count);
+            conservative.add_conservative(&i, count);
+        }
+        assert_eq!(sketch.total(), 5500);
+        for i in 0..1000u32 {
+            let count = u64::from(i % 10 + 1);
+            assert!(sketch.estimate(&i) >= count);
+            assert!(conservative.estimate(&i) >= count);
+            assert!(conservative.estimate(&i) <= sketch.estimate(&i));
+        }
+        assert!(sketch.estimate(&5000u32) <= sketch.total());
+    }
+
+    #[test]
+    fn test_count_min_merge() {
+        let mut a = CountMinSketch::new(64, 3);
+        let mut b = CountMinSketch::new(64, 3);
+        a.add("x", 2);
+        b.add("x", 3);
+        a.merge(&b).unwrap();
+        assert!(a.estimate("x") >= 5);
+        assert_eq!(a.total(), 5);
+
+        let c = CountMinSketch::new(64, 3).with_seed(1);
+        assert_eq!(a.merge(&c), Err(MergeError));
+        assert_eq!(a.merge(&CountMinSketch::new(32, 3)), Err(MergeError));
+    }
+}
@@ -0,0 +1,189 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::hash::Hash;
+
+use crate::mix::{moremur, reduce};
+use crate::v2::hash_one;
+
+// Counters per item, each in a different 4-bit slot of the table
+const DEPTH: u64 = 4;
+
+// Every counter bit except the top one of each nibble
+const RESET_MASK: u64 = 0x7777_7777_7777_7777;
+
+/// A TinyLFU frequency sketch: recent access counts in 4-bit counters
+///
+/// A Count-Min sketch of depth 4 whose counters saturate at 15 and are all
+/// halved by [`reset_half`](Self::reset_half), which happens on its own
+/// after every [`sample_size`](Self::sample_size) increments. Estimates
+/// therefore favour recent popularity, which makes the sketch suited to
+/// deciding whether a new cache entry is worth evicting an old one for.
+///
+/// The four counters of an item are found as in
+/// [`BloomFilter`](crate::filters::BloomFilter), from one ChibiHash.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::FrequencySketch;
+///
+/// let mut sketch = FrequencySketch::new(1000);
+/// for _ in 0..5 {
+///     sketch.increment("hot");
+/// }
+/// sketch.increment("cold");
+/// // Admit a candidate only if it is more popular than the victim
+/// assert!(sketch.estimate("hot") > sketch.estimate("cold"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrequencySketch {
+    table: Vec<u64>,
+    additions: usize,
+    sample_size: usize,
+    seed: u64,
+}
+
+impl FrequencySketch {
+    /// Creates an empty sketch for a cache of `capacity` entries, with seed
+    /// 0
+    ///
+    /// The sketch has 16 counters per entry, rounded up to a power of two,
+    /// and ages after `10 * capacity` increments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        Self {
+            table: vec![0; capacity.next_power_of_two()],
+            additions: 0,
+            sample_size: capacity.saturating_mul(10),
+            seed: 0,
+        }
+    }
+
+    /// Returns the sketch emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Counts one access to `item`
+    pub fn increment<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.increment_hash(hash_one(item, self.seed));
+    }
+
+    /// Counts one access to an item by its 64-bit hash
+    ///
+    /// The hash must be well mixed, such as a ChibiHash value.
+    pub fn increment_hash(&mut self, hash: u64) {
+        let mut added = false;
+        for counter in self.counters(hash) {
+            let (word, shift) = (counter / 16, counter % 16 * 4);
+            if (self.table[word] >> shift) & 0xF < 15 {
+                self.table[word] += 1 << shift;
+                added = true;
+            }
+        }
+        if added {
+            self.additions += 1;
+            if self.additions >= self.sample_size {
+                self.reset_half();
+            }
+        }
+    }
+
+    /// Returns the estimated recent access count of `item`, at most 15
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u8 {
+        self.estimate_hash(hash_one(item, self.seed))
+    }
+
+    /// Returns the estimated recent access count of an item by its 64-bit
+    /// hash, at most 15
+    pub fn estimate_hash(&self, hash: u64) -> u8 {
+        self.counters(hash)
+            .map(|counter| (self.table[counter / 16] >> (counter % 16 * 4)) & 0xF)
+            .min()
+            .unwrap_or(0) as u8
+    }
+
+    /// Halves every counter, rounding down
+    pub fn reset_half(&mut self) {
+        for word in &mut self.table {
+            *word = (*word >> 1) & RESET_MASK;
+        }
+        self.additions /= 2;
+    }
+
+    /// Resets every counter to zero
+    pub fn clear(&mut self) {
+        self.table.fill(0);
+        self.additions = 0;
+    }
+
+    /// Returns the number of increments between automatic halvings
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn counters(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_counters = self.table.len() as u64 * 16;
+        let step = moremur(hash) | 1;
+        (0..DEPTH)
+            .map(move |i| reduce(hash.wrapping_add(i.wrapping_mul(step)), num_counters) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency_sketch() {
+        let mut sketch = FrequencySketch::new(100).with_seed(8);
+        assert_eq!(sketch.sample_size(), 1000);
+        for i in 0..50u32 {
+            for _ in 0..i % 8 {
+                sketch.increment(&i);
+            }
+        }
+        for i in 0..50u32 {
+            assert!(sketch.estimate(&i) >= (i % 8) as u8);
+        }
+        for _ in 0..20 {
+            sketch.increment("saturated");
+        }
+        assert_eq!(sketch.estimate("saturated"), 15);
+
+        sketch.reset_half();
+        assert_eq!(sketch.estimate("saturated"), 7);
+        assert!(sketch.estimate(&7u32) >= 3);
+        sketch.clear();
+        assert_eq!(sketch.estimate("saturated"), 0);
+    }
+
+    #[test]
+    fn test_frequency_sketch_aging() {
+        let mut sketch = FrequencySketch::new(10);
+        for _ in 0..15 {
+            sketch.increment("old");
+        }
+        assert_eq!(sketch.estimate("old"), 15);
+        // 100 more increments reach the sample size of 100 and halve
+        for i in 0..100u32 {
+            sketch.increment(&i);
+        }
+        assert!(sketch.estimate("old") <= 7);
+    }
+}
@@ -0,0 +1,388 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::f64::consts::LN_2;
+use core::hash::Hash;
+
+use super::MergeError;
+use crate::math::{ln, sqrt};
+use crate::v2::hash_one;
+
+// Precision of the sparse representation, as in HLL++
+const SPARSE_PRECISION: u32 = 25;
+
+/// A HyperLogLog sketch: approximate distinct counts in `2^precision` bytes
+///
+/// The relative standard error is about `1.04 / sqrt(2^precision)`, e.g.
+/// 0.8% at the default precision of 14.
+///
+/// As in HLL++, a new sketch starts sparse: it keeps a sorted list of
+/// registers at precision 25, which is exact enough to count small sets
+/// almost without error, and switches to the dense register array once the
+/// list would take more memory than the array. Dense estimates use Ertl's
+/// improved estimator, which corrects the bias of the raw HyperLogLog
+/// estimate over the whole range without the empirical tables of HLL++.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::HyperLogLog;
+///
+/// let mut hll = HyperLogLog::new(12);
+/// for i in 0..10_000u32 {
+///     hll.insert(&(i % 2500));
+/// }
+/// let estimate = hll.estimate();
+/// assert!((2400.0..2600.0).contains(&estimate));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u32,
+    repr: Repr,
+    seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Repr {
+    // Sorted by index, one entry per index: the index at precision 25 in
+    // the high bits and the rank of the remaining 39 hash bits in the low 6
+    Sparse(Vec<u32>),
+    Dense(Vec<u8>),
+}
+
+impl HyperLogLog {
+    /// Precision used by [`Default`]
+    pub const DEFAULT_PRECISION: u8 = 14;
+
+    /// Creates an empty sketch with `2^precision` registers and seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `precision` is between 4 and 18.
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (4..=18).contains(&precision),
+            "precision must be between 4 and 18"
+        );
+        Self {
+            precision: u32::from(precision),
+            repr: Repr::Sparse(Vec::new()),
+            seed: 0,
+        }
+    }
+
+    /// Returns the sketch emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Adds `item`
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.insert_hash(hash_one(item, self.seed));
+    }
+
+    /// Adds an item by its 64-bit hash
+    ///
+    /// The hash must be well mixed, such as a ChibiHash value.
+    pub fn insert_hash(&mut self, hash: u64) {
+        match &mut self.repr {
+            Repr::Sparse(entries) => {
+                insert_sparse(entries, sparse_entry(hash));
+                if entries.len() * 4 > self.num_registers() {
+                    self.promote();
+                }
+            }
+            Repr::Dense(registers) => {
+                let (index, rank) = dense_register(hash, self.precision);
+                registers[index] = registers[index].max(rank);
+            }
+        }
+    }
+
+    /// Returns an estimate of the number of distinct items added
+    pub fn estimate(&self) -> f64 {
+        match &self.repr {
+            Repr::Sparse(entries) => {
+                // Linear counting over the 2^25 sparse registers
+                let m = (1u64 << SPARSE_PRECISION) as f64;
+                let empty = m - entries.len() as f64;
+                m * ln(m / empty)
+            }
+            Repr::Dense(registers) => estimate_dense(registers, self.precision),
+        }
+    }
+
+    /// Adds the items of `other`, which must have the same precision and
+    /// seed
+    pub fn merge(&mut self, other: &Self) -> Result<(), MergeError> {
+        if self.precision != other.precision || self.seed != other.seed {
+            return Err(MergeError);
+        }
+        match &other.repr {
+            Repr::Sparse(theirs) => {
+                for &entry in theirs {
+                    match &mut self.repr {
+                        Repr::Sparse(entries) => insert_sparse(entries, entry),
+                        Repr::Dense(registers) => {
+                            let (index, rank) = sparse_to_dense(entry, self.precision);
+                            registers[index] = registers[index].max(rank);
+                        }
+                    }
+                }
+                if let Repr::Sparse(entries) = &self.repr {
+                    if entries.len() * 4 > self.num_registers() {
+                        self.promote();
+                    }
+                }
+            }
+            Repr::Dense(theirs) => {
+                self.promote();
+                if let Repr::Dense(registers) = &mut self.repr {
+                    for (a, b) in registers.iter_mut().zip(theirs) {
+                        *a = (*a).max(*b);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes every item, returning to the sparse representation
+    pub fn clear(&mut self) {
+        self.repr = Repr::Sparse(Vec::new());
+    }
+
+    /// Returns `true` if no item has been added
+    pub fn is_empty(&self) -> bool {
+        match &self.repr {
+            Repr::Sparse(entries) => entries.is_empty(),
+            Repr::Dense(registers) => registers.iter().all(|&r| r == 0),
+        }
+    }
+
+    /// Returns `true` while the sketch uses the sparse representation
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.repr, Repr::Sparse(_))
+    }
+
+    /// Returns the precision
+    pub fn precision(&self) -> u8 {
+        self.precision as u8
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn num_registers(&self) -> usize {
+        1 << self.precision
+    }
+
+    fn promote(&mut self) {
+        if let Repr::Sparse(entries) = &self.repr {
+            let mut registers = vec![0u8; self.num_registers()];
+            for &entry in entries {
+                let (index, rank) = sparse_to_dense(entry, self.precision);
+                registers[index] = registers[index].max(rank);
+            }
+            self.repr = Repr::Dense(registers);
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PRECISION)
+    }
+}
+
+// Register index and rank of `hash` at precision `p`: the rank is one more
+// than the number of leading zeros after the index bits
+fn dense_register(hash: u64, p: u32) -> (usize, u8) {
+    let index = (hash >> (64 - p)) as usize;
+    let rank = (hash << p).leading_zeros().min(64 - p) + 1;
+    (index, rank as u8)
+}
+
+fn sparse_entry(hash: u64) -> u32 {
+    let (index, rank) = dense_register(hash, SPARSE_PRECISION);
+    (index as u32) << 6 | u32::from(rank)
+}
+
+// The dense register a sparse entry maps to, equal to `dense_register` of
+// any hash that produced the entry
+fn sparse_to_dense(entry: u32, p: u32) -> (usize, u8) {
+    let index = entry >> 6;
+    let extra = SPARSE_PRECISION - p;
+    let low = index & ((1 << extra) - 1);
+    let rank = if low != 0 {
+        low.leading_zeros() - (32 - extra) + 1
+    } else {
+        extra + (entry & 0x3F)
+    };
+    ((index >> extra) as usize, rank as u8)
+}
+
+fn insert_sparse(entries: &mut Vec<u32>, entry: u32) {
+    match entries.binary_search_by_key(&(entry >> 6), |e| e >> 6) {
+        Ok(i) => entries[i] = entries[i].max(entry),
+        Err(i) => entries.insert(i, entry),
+    }
+}
+
+// Ertl, "New cardinality estimation algorithms for HyperLogLog sketches"
+// (2017), section 4: the improved raw estimator, from the histogram of
+// register values
+fn estimate_dense(registers: &[u8], p: u32) -> f64 {
+    let q = 64 - p as usize;
+    let mut counts = [0u32; 66];
+    for &r in registers {
+        counts[r as usize] += 1;
+    }
+    let m = registers.len() as f64;
+
+    let mut z = m * tau(1.0 - f64::from(counts[q + 1]) / m);
+    for k in (1..=q).rev() {
+        z = 0.5 * (z + f64::from(counts[k]));
+    }
+    z += m * sigma(f64::from(counts[0]) / m);
+    m * m / (2.0 * LN_2 * z)
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let prev = z;
+        z += x * y;
+        y += y;
+        if z == prev {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = sqrt(x);
+        let prev = z;
+        y *= 0.5;
+        z -= (1.0 - x) * (1.0 - x) * y;
+        if z == prev {
+            return z / 3.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative_error(hll: &HyperLogLog, n: u32) -> f64 {
+        (hll.estimate() - f64::from(n)).abs() / f64::from(n)
+    }
+
+    #[test]
+    fn test_sparse_to_dense() {
+        for i in 0..10_000u64 {
+            let hash = hash_one(&i, 0) >> (i % 30);
+            for p in [4, 14, 18] {
+                assert_eq!(
+                    sparse_to_dense(sparse_entry(hash), p),
+                    dense_register(hash, p)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_hyperloglog_small() {
+        let mut hll = HyperLogLog::new(14).with_seed(3);
+        assert!(hll.is_empty());
+        assert_eq!(hll.estimate(), 0.0);
+        for i in 0..1000u32 {
+            hll.insert(&i);
+            hll.insert(&i);
+        }
+        assert!(hll.is_sparse());
+        assert!(relative_error(&hll, 1000) < 0.002);
+    }
+
+    #[test]
+    fn test_hyperloglog_promotion() {
+        let mut hll = HyperLogLog::new(10);
+        let mut promoted_at = None;
+        for i in 0..100_000u32 {
+            hll.insert(&i);
+            if promoted_at.is_none() && !hll.is_sparse() {
+                promoted_at = Some(i);
+            }
+        }
+        assert_eq!(promoted_at, Some(256));
+        // 1.04 / sqrt(1024) is about 3.3%
+        assert!(relative_error(&hll, 100_000) < 0.1);
+    }
+
+    #[test]
+    fn test_hyperloglog_accuracy() {
+        for n in [100, 3000, 20_000, 200_000, 2_000_000] {
+            let mut hll = HyperLogLog::new(12);
+            for i in 0..n {
+                hll.insert(&i);
+            }
+            // 1.04 / sqrt(4096) is about 1.6%
+            assert!(relative_error(&hll, n) < 0.05, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_hyperloglog_merge() {
+        let mut a = HyperLogLog::new(12);
+        let mut b = HyperLogLog::new(12);
+        let mut c = HyperLogLog::new(12);
+        for i in 0..50_000u32 {
+            a.insert(&i);
+        }
+        for i in 40_000..40_500u32 {
+            b.insert(&i);
+        }
+        for i in 45_000..60_000u32 {
+            c.insert(&i);
+        }
+        assert!(!a.is_sparse() && b.is_sparse());
+
+        let mut sparse = b.clone();
+        sparse.merge(&b).unwrap();
+        assert_eq!(sparse, b);
+
+        let mut all = b.clone();
+        all.merge(&a).unwrap();
+        all.merge(&c).unwrap();
+        let mut dense = a.clone();
+        dense.merge(&b).unwrap();
+        dense.merge(&c).unwrap();
+        assert_eq!(all, dense);
+        assert!(relative_error(&all, 60_000) < 0.05);
+
+        assert_eq!(a.merge(&HyperLogLog::new(11)), Err(MergeError));
+        assert_eq!(a.merge(&HyperLogLog::new(12).with_seed(1)), Err(MergeError));
+
+        a.clear();
+        assert!(a.is_empty() && a.is_sparse());
+    }
+}
@@ -0,0 +1,201 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use core::hash::Hash;
+
+use super::MergeError;
+use crate::seed::mix_seeds;
+use crate::v2::hash_one;
+
+/// A MinHash signature: the minimum hash of a set under `k` hash functions
+///
+/// Each function is ChibiHash with its own seed, derived from the signature
+/// seed with [`mix_seeds`]. Two sets agree at a position with probability
+/// equal to their Jaccard similarity, so the fraction of equal positions
+/// estimates it with a standard error of about `1 / sqrt(k)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::MinHash;
+///
+/// let mut a = MinHash::new(256);
+/// let mut b = MinHash::new(256);
+/// for word in "the quick brown fox jumps over the lazy dog".split(' ') {
+///     a.insert(word);
+/// }
+/// for word in "the quick brown fox jumps over the lazy cat".split(' ') {
+///     b.insert(word);
+/// }
+/// // 7 shared words out of 9 distinct ones
+/// assert!((a.jaccard_estimate(&b) - 7.0 / 9.0).abs() < 0.15);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHash {
+    seeds: Vec<u64>,
+    mins: Vec<u64>,
+    seed: u64,
+}
+
+impl MinHash {
+    /// Creates an empty signature of `k` hashes, with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0, "number of hashes must be non-zero");
+        let mut minhash = Self {
+            seeds: vec![0; k],
+            mins: vec![u64::MAX; k],
+            seed: 0,
+        };
+        minhash.set_seed(0);
+        minhash
+    }
+
+    /// Returns the signature emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.set_seed(seed);
+        self
+    }
+
+    /// Adds `item` to the set
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for (min, &seed) in self.mins.iter_mut().zip(&self.seeds) {
+            *min = (*min).min(hash_one(item, seed));
+        }
+    }
+
+    /// Returns an estimate of the Jaccard similarity of the two sets, from
+    /// 0 for disjoint sets to 1 for equal ones
+    ///
+    /// Two empty signatures have similarity 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` has a different number of hashes or seed.
+    pub fn jaccard_estimate(&self, other: &Self) -> f64 {
+        assert!(
+            self.seeds == other.seeds,
+            "signatures have different parameters and cannot be compared"
+        );
+        let equal = self
+            .mins
+            .iter()
+            .zip(&other.mins)
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / self.mins.len() as f64
+    }
+
+    /// Adds the items of `other`, which must have the same number of hashes
+    /// and seed, giving the signature of the union
+    pub fn merge(&mut self, other: &Self) -> Result<(), MergeError> {
+        if self.seeds != other.seeds {
+            return Err(MergeError);
+        }
+        for (a, b) in self.mins.iter_mut().zip(&other.mins) {
+            *a = (*a).min(*b);
+        }
+        Ok(())
+    }
+
+    /// Returns the signature, one minimum per hash function
+    pub fn signature(&self) -> &[u64] {
+        &self.mins
+    }
+
+    /// Removes every item
+    pub fn clear(&mut self) {
+        self.mins.fill(u64::MAX);
+    }
+
+    /// Returns `true` if no item has been added
+    pub fn is_empty(&self) -> bool {
+        self.mins.iter().all(|&min| min == u64::MAX)
+    }
+
+    /// Returns the number of hash functions
+    pub fn num_hashes(&self) -> usize {
+        self.seeds.len()
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for (i, s) in self.seeds.iter_mut().enumerate() {
+            *s = mix_seeds(&[seed, i as u64]);
+        }
+    }
+}
+
+impl<T: Hash> Extend<T> for MinHash {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            self.insert(&item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minhash(items: core::ops::Range<u32>) -> MinHash {
+        let mut minhash = MinHash::new(512).with_seed(9);
+        for i in items {
+            minhash.insert(&i);
+        }
+        minhash
+    }
+
+    #[test]
+    fn test_jaccard_estimate() {
+        let a = minhash(0..1000);
+        assert_eq!(a.jaccard_estimate(&a), 1.0);
+        assert_eq!(a.jaccard_estimate(&minhash(1000..2000)), 0.0);
+        // 500 shared of 1500
+        let estimate = a.jaccard_estimate(&minhash(500..1500));
+        assert!((estimate - 1.0 / 3.0).abs() < 0.07, "{}", estimate);
+        // Order and duplicates do not matter
+        let mut b = minhash(500..1000);
+        for i in (0..500u32).rev() {
+            b.insert(&i);
+            b.insert(&i);
+        }
+        assert_eq!(a, b);
+
+        let mut c = MinHash::new(512).with_seed(9);
+        c.extend(0..1000u32);
+        assert_eq!(a, c);
+    }
+
+    #[test]
+    fn test_minhash_merge() {
+        let mut a = minhash(0..600);
+        assert!(!a.is_empty());
+        a.merge(&minhash(400..1000)).unwrap();
+        assert_eq!(a, minhash(0..1000));
+        assert_eq!(a.merge(&MinHash::new(512)), Err(MergeError));
+        assert_eq!(a.merge(&MinHash::new(64).with_seed(9)), Err(MergeError));
+
+        a.clear();
+        assert!(a.is_empty());
+        assert_eq!(a.signature().len(), a.num_hashes());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_jaccard_mismatch() {
+        MinHash::new(8).jaccard_estimate(&MinHash::new(16));
+    }
+}
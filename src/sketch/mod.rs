@@ -0,0 +1,44 @@
+//! Probabilistic summaries of streams built on ChibiHash
+//!
+//! - [`CountMinSketch`]: approximate per-item counts in fixed memory.
+//! - [`FrequencySketch`]: TinyLFU style 4-bit access counters that age, for
+//!   cache admission.
+//! - [`HyperLogLog`]: approximate distinct counts, nearly exact for small sets
+//!   thanks to a sparse representation.
+//! - [`MinHash`]: signatures that estimate the Jaccard similarity of sets.
+//! - [`SimHash`]: 64-bit fingerprints of weighted token sets, compared by
+//!   [`hamming_distance`].
+//! - [`TopK`]: the approximate most frequent items, with error bounds.
+//!
+//! Count-Min sketches, HyperLogLogs and MinHash signatures can be merged when
+//! they have the same shape and seed, e.g. to combine the summaries of several
+//! shards. All hashes use `v2`, and only `alloc` is needed.
+
+use core::fmt;
+
+mod count_min;
+mod frequency;
+mod hyperloglog;
+mod minhash;
+mod simhash;
+mod top_k;
+
+pub use count_min::CountMinSketch;
+pub use frequency::FrequencySketch;
+pub use hyperloglog::HyperLogLog;
+pub use minhash::MinHash;
+pub use simhash::{hamming_distance, is_near_duplicate, SimHash};
+pub use top_k::{HeavyHitter, TopK};
+
+/// Error returned when merging sketches of different shapes or seeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeError;
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sketches have different parameters and cannot be merged")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeError {}
@@ -0,0 +1,164 @@
+use core::hash::Hash;
+
+use crate::v2::hash_one;
+
+/// A SimHash builder: a 64-bit fingerprint of a weighted set of tokens
+///
+/// Every token hash votes on each of the 64 bits, +weight where the hash
+/// has a one and -weight where it has a zero, and the fingerprint keeps the
+/// bits with a positive total. Similar token sets give fingerprints a small
+/// [`hamming_distance`] apart, so near-duplicates can be found by comparing
+/// fingerprints alone.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::{hamming_distance, SimHash};
+///
+/// fn fingerprint(text: &str) -> u64 {
+///     let mut simhash = SimHash::new();
+///     simhash.extend(text.split_whitespace());
+///     simhash.fingerprint()
+/// }
+///
+/// let a = fingerprint("GET /index.html 200 in 13 ms from 10.0.0.1 user agent curl");
+/// let b = fingerprint("GET /index.html 200 in 15 ms from 10.0.0.1 user agent curl");
+/// let c = fingerprint("disk /dev/sda1 is 97% full, cleanup job scheduled");
+/// assert!(hamming_distance(a, b) < hamming_distance(a, c));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimHash {
+    votes: [i64; 64],
+    seed: u64,
+}
+
+impl SimHash {
+    /// Creates an empty builder with seed 0
+    pub fn new() -> Self {
+        Self {
+            votes: [0; 64],
+            seed: 0,
+        }
+    }
+
+    /// Returns the builder emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Adds `token` with weight 1
+    pub fn add<T: Hash + ?Sized>(&mut self, token: &T) {
+        self.add_weighted(token, 1);
+    }
+
+    /// Adds `token` with `weight`; a negative weight removes earlier
+    /// additions
+    pub fn add_weighted<T: Hash + ?Sized>(&mut self, token: &T, weight: i64) {
+        self.add_hash(hash_one(token, self.seed), weight);
+    }
+
+    /// Adds a token by its 64-bit hash, with `weight`
+    pub fn add_hash(&mut self, hash: u64, weight: i64) {
+        for (bit, vote) in self.votes.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *vote = vote.saturating_add(weight);
+            } else {
+                *vote = vote.saturating_sub(weight);
+            }
+        }
+    }
+
+    /// Returns the fingerprint of the tokens added so far
+    pub fn fingerprint(&self) -> u64 {
+        self.votes
+            .iter()
+            .enumerate()
+            .filter(|(_, &vote)| vote > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+    }
+
+    /// Returns the number of differing bits between the fingerprints of
+    /// `self` and `other`
+    pub fn hamming_distance(&self, other: &Self) -> u32 {
+        hamming_distance(self.fingerprint(), other.fingerprint())
+    }
+
+    /// Removes every token
+    pub fn clear(&mut self) {
+        self.votes = [0; 64];
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for SimHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash> Extend<T> for SimHash {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, tokens: I) {
+        for token in tokens {
+            self.add(&token);
+        }
+    }
+}
+
+/// Returns the number of bits in which two fingerprints differ
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Returns `true` if two fingerprints differ in at most `max_distance` bits
+///
+/// A threshold of 3 out of 64 bits is a common choice for near-duplicate
+/// web pages.
+pub fn is_near_duplicate(a: u64, b: u64, max_distance: u32) -> bool {
+    hamming_distance(a, b) <= max_distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(tokens: core::ops::Range<u32>) -> u64 {
+        let mut simhash = SimHash::new().with_seed(4);
+        simhash.extend(tokens);
+        simhash.fingerprint()
+    }
+
+    #[test]
+    fn test_simhash() {
+        assert_eq!(SimHash::new().fingerprint(), 0);
+
+        let mut one = SimHash::new();
+        one.add("token");
+        assert_eq!(one.fingerprint(), hash_one("token", 0));
+
+        let base = fingerprint(0..200);
+        assert_eq!(base, fingerprint(0..200));
+        assert!(is_near_duplicate(base, fingerprint(0..199), 8));
+        assert!(hamming_distance(base, fingerprint(1000..1200)) > 16);
+    }
+
+    #[test]
+    fn test_simhash_weights() {
+        let mut a = SimHash::new();
+        a.add_weighted("rare", 10);
+        a.add("common");
+        a.add("common");
+        assert_eq!(a.fingerprint(), hash_one("rare", 0));
+
+        a.add_weighted("rare", -10);
+        let mut b = SimHash::new();
+        b.add_weighted("common", 2);
+        assert_eq!(a, b);
+        assert_eq!(a.hamming_distance(&b), 0);
+    }
+}
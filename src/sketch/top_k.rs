@@ -0,0 +1,271 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::borrow::Borrow;
+use core::hash::Hash;
+
+use crate::v2::hash_one;
+
+/// A space-saving heavy hitters tracker: the approximate `k` most frequent
+/// items of a stream
+///
+/// At most `k` items are kept with a counter each. A new item takes over
+/// the slot with the smallest counter `min` and starts at `min`, recording
+/// `min` as its error. Each reported count is therefore an upper bound that
+/// exceeds the true count by at most its error, and every item more
+/// frequent than `total / k` is reported.
+///
+/// Items are found through their ChibiHash values, so only `alloc` is
+/// needed. Adding an item that is not tracked takes `O(k)` time to find the
+/// smallest counter.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::sketch::TopK;
+///
+/// let mut top = TopK::new(3);
+/// for key in ["a", "b", "a", "c", "a", "d", "b", "e", "a"] {
+///     top.add(key, 1);
+/// }
+/// let hot = top.top();
+/// assert_eq!(*hot[0].item, "a");
+/// assert_eq!(hot[0].count, 4);
+/// assert!(hot[0].count - hot[0].error <= 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    slots: Vec<Slot<T>>,
+    // (hash, slot) pairs sorted by hash
+    index: Vec<(u64, usize)>,
+    total: u64,
+    seed: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    item: T,
+    hash: u64,
+    count: u64,
+    error: u64,
+}
+
+/// An item reported by [`TopK`], with bounds on its count
+///
+/// The true count is between `count - error` and `count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeavyHitter<'a, T> {
+    /// The item
+    pub item: &'a T,
+    /// Upper bound on the number of occurrences
+    pub count: u64,
+    /// Maximum overestimate in `count`
+    pub error: u64,
+}
+
+impl<T: Hash + Eq> TopK<T> {
+    /// Creates an empty tracker of `k` items, with seed 0
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0, "k must be non-zero");
+        Self {
+            k,
+            slots: Vec::with_capacity(k),
+            index: Vec::with_capacity(k),
+            total: 0,
+            seed: 0,
+        }
+    }
+
+    /// Returns the tracker emptied and hashing with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.clear();
+        self.seed = seed;
+        self
+    }
+
+    /// Adds `count` occurrences of `item`
+    pub fn add(&mut self, item: T, count: u64) {
+        self.total = self.total.saturating_add(count);
+        let hash = hash_one(&item, self.seed);
+        if let Some(slot) = self.find(hash, &item) {
+            let slot = &mut self.slots[slot];
+            slot.count = slot.count.saturating_add(count);
+            return;
+        }
+
+        let slot = if self.slots.len() < self.k {
+            self.slots.push(Slot {
+                item,
+                hash,
+                count,
+                error: 0,
+            });
+            self.slots.len() - 1
+        } else {
+            let slot = (0..self.k)
+                .min_by_key(|&i| self.slots[i].count)
+                .expect("k is non-zero");
+            let min = &mut self.slots[slot];
+            let pos = self
+                .index
+                .binary_search(&(min.hash, slot))
+                .expect("every slot is indexed");
+            self.index.remove(pos);
+            *min = Slot {
+                item,
+                hash,
+                count: min.count.saturating_add(count),
+                error: min.count,
+            };
+            slot
+        };
+        let pos = self.index.binary_search(&(hash, slot)).unwrap_err();
+        self.index.insert(pos, (hash, slot));
+    }
+
+    /// Returns `item` with the bounds on its count, or `None` if it is not
+    /// tracked, in which case its count is at most the smallest tracked
+    /// count
+    pub fn get<Q>(&self, item: &Q) -> Option<HeavyHitter<'_, T>>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = &self.slots[self.find(hash_one(item, self.seed), item)?];
+        Some(slot.heavy_hitter())
+    }
+
+    /// Returns the tracked items, most frequent first
+    pub fn top(&self) -> Vec<HeavyHitter<'_, T>> {
+        let mut top: Vec<_> = self.slots.iter().map(Slot::heavy_hitter).collect();
+        top.sort_by(|a, b| b.count.cmp(&a.count).then(a.error.cmp(&b.error)));
+        top
+    }
+
+    /// Returns the total count added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Removes every item
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.index.clear();
+        self.total = 0;
+    }
+
+    /// Returns the number of tracked items, at most `k`
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if no item has been added
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the maximum number of tracked items
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn find<Q>(&self, hash: u64, item: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let start = self.index.partition_point(|&(h, _)| h < hash);
+        self.index[start..]
+            .iter()
+            .take_while(|&&(h, _)| h == hash)
+            .map(|&(_, slot)| slot)
+            .find(|&slot| self.slots[slot].item.borrow() == item)
+    }
+}
+
+impl<T> Slot<T> {
+    fn heavy_hitter(&self) -> HeavyHitter<'_, T> {
+        HeavyHitter {
+            item: &self.item,
+            count: self.count,
+            error: self.error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec};
+
+    #[test]
+    fn test_top_k_exact() {
+        let mut top = TopK::new(4).with_seed(2);
+        assert!(top.is_empty());
+        for (item, count) in [(1u32, 5), (2, 3), (3, 8), (1, 2)] {
+            top.add(item, count);
+        }
+        let counts: Vec<_> = top.top().iter().map(|h| (*h.item, h.count)).collect();
+        assert_eq!(counts, vec![(3, 8), (1, 7), (2, 3)]);
+        assert!(top.top().iter().all(|h| h.error == 0));
+        assert_eq!(top.get(&4), None);
+        assert_eq!(top.total(), 18);
+    }
+
+    #[test]
+    fn test_top_k_bounds() {
+        // Item i occurs 1000 / (i + 1) times, interleaved
+        let mut top = TopK::new(20);
+        let mut expected = vec![0u64; 500];
+        for round in 0..1000u64 {
+            for (i, count) in expected.iter_mut().enumerate() {
+                if round % (i as u64 + 1) == 0 {
+                    top.add(i, 1);
+                    *count += 1;
+                }
+            }
+        }
+        assert_eq!(top.len(), 20);
+        let total = expected.iter().sum::<u64>();
+        assert_eq!(top.total(), total);
+        for h in top.top() {
+            let actual = expected[*h.item];
+            assert!(h.count - h.error <= actual && actual <= h.count);
+        }
+        for (i, &count) in expected.iter().enumerate() {
+            if count > total / 20 {
+                assert!(top.get(&i).is_some(), "missing {}", i);
+            }
+        }
+        assert_eq!(*top.top()[0].item, 0);
+    }
+
+    #[test]
+    fn test_top_k_borrow() {
+        let mut top = TopK::new(2);
+        top.add(String::from("a"), 1);
+        top.add(String::from("b"), 1);
+        top.add(String::from("c"), 1);
+        assert_eq!(top.get("a"), None);
+        let c = top.get("c").unwrap();
+        assert_eq!((c.item.as_str(), c.count, c.error), ("c", 2, 1));
+        top.clear();
+        assert_eq!(top.total(), 0);
+        assert!(top.get("b").is_none());
+    }
+}
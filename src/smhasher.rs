@@ -0,0 +1,105 @@
+//! SMHasher3 plugin interface
+//!
+//! Exposes the crate's hash functions with the C ABI expected by
+//! [SMHasher3](https://gitlab.com/fwojcik/smhasher3) hash plugins:
+//!
+//! ```c
+//! void hash(const void *in, const size_t len, const seed_t seed, void *out);
+//! ```
+//!
+//! The plugin glue lives in `csrc/smhasher3/chibihash_rs.cpp`. Run
+//! `make smhasher3-plugin` to build the static library to link into
+//! SMHasher3, or `make test-smhasher` to run the curated subset of its tests
+//! that ships with this repository.
+
+use core::ffi::c_void;
+
+/// `v1` hash with the SMHasher3 plugin signature.
+///
+/// Writes the 64-bit digest to `out` in little-endian byte order.
+///
+/// # Safety
+///
+/// `input` must be valid for reads of `len` bytes (it may be dangling when
+/// `len` is zero) and `out` must be valid for writes of 8 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chibihash_rs_v1(
+    input: *const c_void,
+    len: usize,
+    seed: u64,
+    out: *mut c_void,
+) {
+    let key = key_from_raw(input, len);
+    write_digest(crate::v1::chibi_hash64(key, seed), out);
+}
+
+/// `v2` hash with the SMHasher3 plugin signature.
+///
+/// Writes the 64-bit digest to `out` in little-endian byte order.
+///
+/// # Safety
+///
+/// `input` must be valid for reads of `len` bytes (it may be dangling when
+/// `len` is zero) and `out` must be valid for writes of 8 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chibihash_rs_v2(
+    input: *const c_void,
+    len: usize,
+    seed: u64,
+    out: *mut c_void,
+) {
+    let key = key_from_raw(input, len);
+    write_digest(crate::v2::chibi_hash64(key, seed), out);
+}
+
+#[inline(always)]
+unsafe fn key_from_raw<'a>(input: *const c_void, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        core::slice::from_raw_parts(input as *const u8, len)
+    }
+}
+
+#[inline(always)]
+unsafe fn write_digest(hash: u64, out: *mut c_void) {
+    core::ptr::copy_nonoverlapping(hash.to_le_bytes().as_ptr(), out as *mut u8, 8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_matches_direct() {
+        let key = b"Hello, world!";
+        let mut out = [0u8; 8];
+
+        unsafe {
+            chibihash_rs_v1(
+                key.as_ptr() as *const c_void,
+                key.len(),
+                0,
+                out.as_mut_ptr() as *mut c_void,
+            )
+        };
+        assert_eq!(u64::from_le_bytes(out), crate::v1::chibi_hash64(key, 0));
+
+        unsafe {
+            chibihash_rs_v2(
+                key.as_ptr() as *const c_void,
+                key.len(),
+                0,
+                out.as_mut_ptr() as *mut c_void,
+            )
+        };
+        assert_eq!(u64::from_le_bytes(out), crate::v2::chibi_hash64(key, 0));
+    }
+
+    #[test]
+    fn test_plugin_empty_input() {
+        let mut out = [0u8; 8];
+        unsafe { chibihash_rs_v2(core::ptr::null(), 0, 55555, out.as_mut_ptr() as *mut c_void) };
+        assert_eq!(u64::from_le_bytes(out), 0x58AEE94CA9FB5092);
+    }
+}
@@ -0,0 +1,243 @@
+//! Stable encoding of a streaming hasher's midstream state
+//!
+//! `StreamingChibiHasher::to_bytes` in [`v1`](crate::v1) and
+//! [`v2`](crate::v2) checkpoints a computation so it can be resumed later
+//! with `from_bytes`, even in another process or on another platform.
+//!
+//! The encoding is [`STATE_LEN`] bytes, with every integer little-endian:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 1 | Format version, currently 1 |
+//! | 1 | 1 | Algorithm, 1 for `v1` and 2 for `v2` |
+//! | 2 | 32 | State lanes, four `u64`s |
+//! | 34 | 8 | Bytes written so far |
+//! | 42 | 8 | Seed |
+//! | 50 | 1 | Buffered byte count, always below 32 |
+//! | 51 | 32 | Buffered bytes, zero-padded |
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::v2::{chibi_hash64, StreamingChibiHasher};
+//!
+//! let mut hasher = StreamingChibiHasher::new(0);
+//! hasher.update(b"first half, ");
+//! let checkpoint = hasher.to_bytes();
+//!
+//! // Later, possibly after a restart
+//! let mut resumed = StreamingChibiHasher::from_bytes(&checkpoint).unwrap();
+//! resumed.update(b"second half");
+//! assert_eq!(resumed.finalize(), chibi_hash64(b"first half, second half", 0));
+//! ```
+
+use core::fmt;
+
+/// Length of an encoded state in bytes
+pub const STATE_LEN: usize = 83;
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Error returned when decoding an encoded state fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The input is not [`STATE_LEN`] bytes long
+    InvalidLength,
+    /// The input uses a format version this crate does not know
+    UnsupportedFormat(u8),
+    /// The input was produced by a different algorithm version
+    WrongAlgorithm {
+        /// Algorithm of the hasher being restored
+        expected: u8,
+        /// Algorithm recorded in the input
+        found: u8,
+    },
+    /// The buffered byte count does not match the total length
+    Inconsistent,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidLength => f.write_str("invalid encoded state length"),
+            StateError::UnsupportedFormat(version) => {
+                write!(f, "unsupported state format version {}", version)
+            }
+            StateError::WrongAlgorithm { expected, found } => write!(
+                f,
+                "state is for algorithm v{}, expected v{}",
+                found, expected
+            ),
+            StateError::Inconsistent => f.write_str("inconsistent encoded state"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateError {}
+
+// The fields of a streaming hasher, shared by `v1` and `v2`
+pub(crate) struct RawState {
+    pub(crate) h: [u64; 4],
+    pub(crate) total_len: u64,
+    pub(crate) seed: u64,
+    pub(crate) buf: [u8; 32],
+    pub(crate) buf_len: usize,
+}
+
+pub(crate) fn encode(algorithm: u8, state: &RawState) -> [u8; STATE_LEN] {
+    let mut out = [0u8; STATE_LEN];
+    out[0] = FORMAT_VERSION;
+    out[1] = algorithm;
+    for (i, lane) in state.h.iter().enumerate() {
+        out[2 + i * 8..10 + i * 8].copy_from_slice(&lane.to_le_bytes());
+    }
+    out[34..42].copy_from_slice(&state.total_len.to_le_bytes());
+    out[42..50].copy_from_slice(&state.seed.to_le_bytes());
+    out[50] = state.buf_len as u8;
+    out[51..51 + state.buf_len].copy_from_slice(&state.buf[..state.buf_len]);
+    out
+}
+
+pub(crate) fn decode(algorithm: u8, bytes: &[u8]) -> Result<RawState, StateError> {
+    if bytes.len() != STATE_LEN {
+        return Err(StateError::InvalidLength);
+    }
+    if bytes[0] != FORMAT_VERSION {
+        return Err(StateError::UnsupportedFormat(bytes[0]));
+    }
+    if bytes[1] != algorithm {
+        return Err(StateError::WrongAlgorithm {
+            expected: algorithm,
+            found: bytes[1],
+        });
+    }
+
+    let read_u64 = |at: usize| {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[at..at + 8]);
+        u64::from_le_bytes(word)
+    };
+    let total_len = read_u64(34);
+    let buf_len = usize::from(bytes[50]);
+    // Complete stripes are always absorbed, so the buffer holds exactly the
+    // bytes past the last multiple of 32
+    if buf_len as u64 != total_len % 32 {
+        return Err(StateError::Inconsistent);
+    }
+
+    let mut buf = [0u8; 32];
+    buf[..buf_len].copy_from_slice(&bytes[51..51 + buf_len]);
+    Ok(RawState {
+        h: [read_u64(2), read_u64(10), read_u64(18), read_u64(26)],
+        total_len,
+        seed: read_u64(42),
+        buf,
+        buf_len,
+    })
+}
+
+// `StreamingChibiHasher` serializes as its encoded state, so serde gets the
+// same validation and stability guarantees as `from_bytes`
+#[cfg(feature = "serde")]
+pub(crate) fn serialize<S: serde::Serializer>(
+    bytes: &[u8; STATE_LEN],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize<'de, D: serde::Deserializer<'de>>(
+    algorithm: u8,
+    deserializer: D,
+) -> Result<RawState, D::Error> {
+    struct StateVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for StateVisitor {
+        type Value = [u8; STATE_LEN];
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} bytes of encoded hasher state", STATE_LEN)
+        }
+
+        fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(
+            self,
+            mut seq: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut out = [0u8; STATE_LEN];
+            for (i, byte) in out.iter_mut().enumerate() {
+                *byte = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+            }
+            if seq.next_element::<u8>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(STATE_LEN + 1, &self));
+            }
+            Ok(out)
+        }
+    }
+
+    let bytes = deserializer.deserialize_bytes(StateVisitor)?;
+    decode(algorithm, &bytes).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RawState {
+        let mut buf = [0u8; 32];
+        buf[..3].copy_from_slice(b"abc");
+        RawState {
+            h: [1, 2, 3, 4],
+            total_len: 35,
+            seed: 9,
+            buf,
+            buf_len: 3,
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let bytes = encode(2, &sample());
+        assert_eq!(bytes[..2], [1, 2]);
+        assert_eq!(bytes[2..10], 1u64.to_le_bytes());
+        assert_eq!(bytes[34..42], 35u64.to_le_bytes());
+        assert_eq!(bytes[42..50], 9u64.to_le_bytes());
+        assert_eq!(bytes[50], 3);
+        assert_eq!(&bytes[51..54], b"abc");
+        assert!(bytes[54..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = encode(2, &sample());
+        assert_eq!(
+            decode(2, &bytes[..STATE_LEN - 1]).err(),
+            Some(StateError::InvalidLength)
+        );
+        assert_eq!(
+            decode(1, &bytes).err(),
+            Some(StateError::WrongAlgorithm {
+                expected: 1,
+                found: 2
+            })
+        );
+
+        let mut bad = bytes;
+        bad[0] = 9;
+        assert_eq!(
+            decode(2, &bad).err(),
+            Some(StateError::UnsupportedFormat(9))
+        );
+
+        let mut bad = bytes;
+        bad[50] = 4;
+        assert_eq!(decode(2, &bad).err(), Some(StateError::Inconsistent));
+    }
+}
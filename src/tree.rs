@@ -0,0 +1,119 @@
+//! Tree mode: hashing large buffers in independent chunks
+//!
+//! The input is split into `chunk_size`-byte chunks, each chunk is hashed on
+//! its own, and the chunk digests are hashed in order into a root. Chunks can
+//! therefore be hashed on several threads with [`chibi_hash64_parallel`]
+//! (feature `rayon`), or one after another with [`chibi_hash64_tree`]; both
+//! give the same value.
+//!
+//! A tree hash is a different function from the sequential hash:
+//! `chibi_hash64_tree(data, seed, n)` never equals `chibi_hash64(data, seed)`
+//! except by chance, and the value depends on `chunk_size`. Pick a chunk size
+//! once and keep it wherever the values are compared. All hashes use `v2`.
+//!
+//! The root is `v2::hash_with_domain(b"chibihash-tree", ..)` over the chunk
+//! size and input length as `u64` little-endian, followed by every chunk
+//! digest `v2::chibi_hash64(chunk, seed)` as `u64` little-endian.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::tree::chibi_hash64_tree;
+//!
+//! let data = vec![0xAB; 1 << 20];
+//! let hash = chibi_hash64_tree(&data, 0, 64 * 1024);
+//! # #[cfg(feature = "rayon")]
+//! assert_eq!(chibihash::tree::chibi_hash64_parallel(&data, 0, 64 * 1024), hash);
+//! ```
+
+use crate::v2::{chibi_hash64, StreamingChibiHasher};
+
+const DOMAIN: &[u8] = b"chibihash-tree";
+
+/// Hashes `data` in tree mode, one chunk after another
+///
+/// Equal to [`chibi_hash64_parallel`] with the same arguments.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is zero.
+pub fn chibi_hash64_tree(data: &[u8], seed: u64, chunk_size: usize) -> u64 {
+    let mut root = root_hasher(data.len() as u64, seed, chunk_size);
+    for chunk in data.chunks(chunk_size) {
+        root.update(&chibi_hash64(chunk, seed).to_le_bytes());
+    }
+    root.finalize()
+}
+
+/// Hashes `data` in tree mode, with chunks hashed in parallel on the rayon
+/// thread pool
+///
+/// Not equal to `chibi_hash64(data, seed)`; see the [module
+/// documentation](self). Chunks of a few hundred KiB or more keep the
+/// per-chunk overhead negligible.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is zero.
+#[cfg(feature = "rayon")]
+pub fn chibi_hash64_parallel(data: &[u8], seed: u64, chunk_size: usize) -> u64 {
+    use rayon::prelude::*;
+
+    let mut root = root_hasher(data.len() as u64, seed, chunk_size);
+    let digests: Vec<u64> = data
+        .par_chunks(chunk_size)
+        .map(|chunk| chibi_hash64(chunk, seed))
+        .collect();
+    for digest in digests {
+        root.update(&digest.to_le_bytes());
+    }
+    root.finalize()
+}
+
+// The root hasher for `len` bytes of input, before the chunk digests
+pub(crate) fn root_hasher(len: u64, seed: u64, chunk_size: usize) -> StreamingChibiHasher {
+    assert!(chunk_size > 0, "chunk size must be non-zero");
+    let mut root = StreamingChibiHasher::with_domain(DOMAIN, seed);
+    root.update(&(chunk_size as u64).to_le_bytes());
+    root.update(&len.to_le_bytes());
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_layout() {
+        let data: [u8; 1000] = core::array::from_fn(|i| (i * 13) as u8);
+        let mut root = StreamingChibiHasher::with_domain(DOMAIN, 7);
+        root.update(&300u64.to_le_bytes());
+        root.update(&1000u64.to_le_bytes());
+        for chunk in data.chunks(300) {
+            root.update(&chibi_hash64(chunk, 7).to_le_bytes());
+        }
+        assert_eq!(chibi_hash64_tree(&data, 7, 300), root.finalize());
+    }
+
+    #[test]
+    fn test_tree_distinct() {
+        let data = [1u8; 100];
+        let hash = chibi_hash64_tree(&data, 0, 32);
+        assert_ne!(hash, chibi_hash64(&data, 0));
+        assert_ne!(hash, chibi_hash64_tree(&data, 0, 64));
+        assert_ne!(hash, chibi_hash64_tree(&data[..99], 0, 32));
+        assert_ne!(chibi_hash64_tree(&[], 0, 32), chibi_hash64_tree(&[], 0, 64));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_matches_tree() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i ^ (i >> 8)) as u8).collect();
+        for chunk_size in [1, 1000, 4096, 100_000, 200_000] {
+            assert_eq!(
+                chibi_hash64_parallel(&data, 3, chunk_size),
+                chibi_hash64_tree(&data, 3, chunk_size)
+            );
+        }
+    }
+}
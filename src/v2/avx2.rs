@@ -0,0 +1,110 @@
+// x86_64 AVX2 version of the stripe loop
+//
+// The state is one `__m256i`, `[h0, h1, h2, h3]`. As in the wasm version, a
+// stripe `s` with rotated words `r` updates it as
+//
+//     h = (h + s + [0, r0, r1, r2]) * K + [r3, 0, 0, 0]
+//
+// which is linear in `h` modulo 2^64. Four stripes therefore fold into
+//
+//     h = (h + t0) * K^4 + t1 * K^3 + t2 * K^2 + t3 * K + d3
+//
+// where the `t` terms depend only on the input. Only the first multiply is
+// on the dependency chain, so a 128-byte block costs about one multiply of
+// latency instead of four. AVX2 has no 64-bit multiply; `mul` builds one
+// from three 32-bit ones.
+
+use core::arch::x86_64::*;
+
+use super::K;
+
+const K2: u64 = K.wrapping_mul(K);
+const K3: u64 = K2.wrapping_mul(K);
+const K4: u64 = K3.wrapping_mul(K);
+
+/// Bytes processed per iteration
+pub(super) const BLOCK: usize = 128;
+
+// Detected at runtime with `std`, otherwise only when enabled at build time
+#[inline(always)]
+pub(super) fn available() -> bool {
+    #[cfg(feature = "std")]
+    {
+        std::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "avx2")
+    }
+}
+
+// Low 64 bits of `a * c` in every lane, with `c` split into 32-bit halves
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn mul(a: __m256i, c: (__m256i, __m256i)) -> __m256i {
+    let (c_lo, c_hi) = c;
+    let lo = _mm256_mul_epu32(a, c_lo);
+    let cross = _mm256_add_epi64(
+        _mm256_mul_epu32(_mm256_srli_epi64::<32>(a), c_lo),
+        _mm256_mul_epu32(a, c_hi),
+    );
+    _mm256_add_epi64(lo, _mm256_slli_epi64::<32>(cross))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn splat(c: u64) -> (__m256i, __m256i) {
+    (
+        _mm256_set1_epi64x(c as i64),
+        _mm256_set1_epi64x((c >> 32) as i64),
+    )
+}
+
+// Loads a stripe and returns `s + [0, r0, r1, r2]` and `[r3, 0, 0, 0]`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn stripe(p: *const u8) -> (__m256i, __m256i) {
+    let s = _mm256_loadu_si256(p as *const __m256i);
+    let r = _mm256_or_si256(_mm256_slli_epi64::<27>(s), _mm256_srli_epi64::<37>(s));
+    // [r3, r0, r1, r2]
+    let r = _mm256_permute4x64_epi64::<0b10_01_00_11>(r);
+    let zero = _mm256_setzero_si256();
+    let carry = _mm256_blend_epi32::<0b0000_0011>(r, zero);
+    let last = _mm256_blend_epi32::<0b1111_1100>(r, zero);
+    (_mm256_add_epi64(s, carry), last)
+}
+
+/// Processes whole 128-byte blocks and returns the rest
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn process_stripes<'a>(h: &mut [u64; 4], mut p: &'a [u8]) -> &'a [u8] {
+    let k = splat(K);
+    let k2 = splat(K2);
+    let k3 = splat(K3);
+    let k4 = splat(K4);
+    let mut v = _mm256_loadu_si256(h.as_ptr() as *const __m256i);
+
+    while p.len() >= BLOCK {
+        // SAFETY: `p` holds at least four 32-byte stripes
+        let (u0, d0) = stripe(p.as_ptr());
+        let (u1, d1) = stripe(p.as_ptr().add(32));
+        let (u2, d2) = stripe(p.as_ptr().add(64));
+        let (u3, d3) = stripe(p.as_ptr().add(96));
+
+        let rest = _mm256_add_epi64(
+            _mm256_add_epi64(
+                mul(_mm256_add_epi64(d0, u1), k3),
+                mul(_mm256_add_epi64(d1, u2), k2),
+            ),
+            _mm256_add_epi64(mul(_mm256_add_epi64(d2, u3), k), d3),
+        );
+        v = _mm256_add_epi64(mul(_mm256_add_epi64(v, u0), k4), rest);
+        p = &p[BLOCK..];
+    }
+
+    _mm256_storeu_si256(h.as_mut_ptr() as *mut __m256i, v);
+    p
+}
@@ -49,10 +49,13 @@
 //! println!("{}", set.contains("hello"));
 //!
 //! // Using BuildHasher as HashMap with custom seed
+//! # #[cfg(not(feature = "random-seed"))]
+//! # {
 //! let builder = ChibiHasher::new(42);
 //! let mut map: ChibiHashMap<String, i32> = ChibiHashMap::with_hasher(builder);
 //! map.insert("hello".to_string(), 42);
 //! println!("{:?}", map.get("hello"));
+//! # }
 //! ```
 
 #[cfg(not(feature = "std"))]
@@ -63,67 +66,209 @@ use hashbrown::{HashMap as BaseHashMap, HashSet as BaseHashSet};
 #[cfg(all(feature = "std", not(feature = "hashbrown")))]
 use std::collections::{HashMap as BaseHashMap, HashSet as BaseHashSet};
 
-#[cfg(all(not(feature = "std"), not(feature = "hashbrown")))]
-use core::hash::Hasher;
 #[cfg(all(not(feature = "std"), feature = "hashbrown"))]
 use core::hash::{BuildHasher, Hash, Hasher};
+#[cfg(all(not(feature = "std"), not(feature = "hashbrown")))]
+use core::hash::{BuildHasher, Hasher};
 #[cfg(feature = "std")]
 use std::hash::{BuildHasher, Hash, Hasher};
 
-#[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
-
 #[cfg(not(feature = "std"))]
 use core::convert::TryInto;
 #[cfg(feature = "std")]
 use std::convert::TryInto;
 
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::mix::{derive_multi, fold32, fold_lanes};
+use crate::state::{self, StateError, STATE_LEN};
+
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+mod multi;
+#[cfg(all(
+    target_arch = "aarch64",
+    target_feature = "neon",
+    target_endian = "little"
+))]
+mod neon;
+#[cfg(feature = "portable-simd")]
+mod portable;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm;
+
 const K: u64 = 0x2B7E151628AED2A7; // digits of e
 
 pub fn chibi_hash64(key: &[u8], seed: u64) -> u64 {
+    hash_from_state(key, initial_state(seed), seed)
+}
+
+/// Hashes `key` with a 256-bit secret instead of a 64-bit seed
+///
+/// The secret, read as four little-endian `u64`s, becomes the initial state
+/// lanes, and its seed-0 hash takes the place of the seed in the final mix. Use it
+/// when 64 bits of secret are not enough, e.g. for keys that must not be
+/// predictable across tenants. Still not a MAC: ChibiHash is not
+/// cryptographic.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::{chibi_hash64_keyed, StreamingChibiHasher};
+///
+/// let secret = [7u8; 32];
+/// let mut hasher = StreamingChibiHasher::new_keyed(&secret);
+/// hasher.update(b"tenant-42/object");
+/// assert_eq!(hasher.finalize(), chibi_hash64_keyed(b"tenant-42/object", &secret));
+/// ```
+pub fn chibi_hash64_keyed(key: &[u8], secret: &[u8; 32]) -> u64 {
+    let (h, seed) = keyed_state(secret);
+    hash_from_state(key, h, seed)
+}
+
+#[inline(always)]
+pub(crate) const fn initial_state(seed: u64) -> [u64; 4] {
     let seed2 = seed
         .wrapping_sub(K)
         .rotate_left(15)
         .wrapping_add(seed.wrapping_sub(K).rotate_left(47));
 
-    let mut h = [
+    [
         seed,
         seed.wrapping_add(K),
         seed2,
         seed2.wrapping_add(K.wrapping_mul(K) ^ K),
+    ]
+}
+
+#[inline(always)]
+const fn keyed_state(secret: &[u8; 32]) -> ([u64; 4], u64) {
+    let h = [
+        load_u64_le_const(secret, 0),
+        load_u64_le_const(secret, 8),
+        load_u64_le_const(secret, 16),
+        load_u64_le_const(secret, 24),
     ];
+    (h, chibi_hash64_const(secret, 0))
+}
+
+// Everything after state setup: `seed` only enters the final mix here
+#[inline(always)]
+fn hash_from_state(key: &[u8], mut h: [u64; 4], seed: u64) -> u64 {
+    // Process 32-byte chunks
+    let p = process_stripes(&mut h, key);
+    finalize_state(h, p, key.len() as u64, seed)
+}
 
-    let mut p = key;
+// Mixes in the tail of fewer than 32 bytes left after the stripes, and the
+// total input length
+#[inline(always)]
+pub(crate) fn finalize_state(h: [u64; 4], p: &[u8], len: u64, seed: u64) -> u64 {
+    final_mix(fold_state(h, p, len, seed).0)
+}
+
+// `K` hashes from the same state as `finalize_state`, the first equal to it
+#[inline(always)]
+fn finalize_multi_state<const K: usize>(h: [u64; 4], p: &[u8], len: u64, seed: u64) -> [u64; K] {
+    let (x, lanes) = fold_state(h, p, len, seed);
+    derive_multi(x, lanes, final_mix(x))
+}
+
+// `finalize_state` up to the final mix, and the lanes it folded for
+// `derive_multi`
+#[inline(always)]
+fn fold_state(mut h: [u64; 4], mut p: &[u8], len: u64, seed: u64) -> (u64, u64) {
+    let mut l = p.len();
+
+    // Process 8-byte chunks
+    while l >= 8 {
+        h[0] ^= load_u32_le(&p[0..]);
+        h[0] = h[0].wrapping_mul(K);
+        h[1] ^= load_u32_le(&p[4..]);
+        h[1] = h[1].wrapping_mul(K);
+        p = &p[8..];
+        l -= 8;
+    }
+
+    // Handle remaining bytes
+    if l >= 4 {
+        h[2] ^= load_u32_le(&p[0..]);
+        h[3] ^= load_u32_le(&p[l - 4..]);
+    } else if l > 0 {
+        h[2] ^= u64::from(p[0]);
+        h[3] ^= u64::from(p[l / 2]) | (u64::from(p[l - 1]) << 8);
+    }
+
+    h[0] = h[0].wrapping_add((h[2].wrapping_mul(K)).rotate_left(31) ^ (h[2] >> 31));
+    h[1] = h[1].wrapping_add((h[3].wrapping_mul(K)).rotate_left(31) ^ (h[3] >> 31));
+    h[0] = h[0].wrapping_mul(K);
+    h[0] ^= h[0] >> 31;
+    h[1] = h[1].wrapping_add(h[0]);
+
+    let mut x = len.wrapping_mul(K);
+    x ^= x.rotate_left(29);
+    x = x.wrapping_add(seed);
+    (x ^ h[1], fold_lanes(&h))
+}
+
+#[inline(always)]
+fn final_mix(mut x: u64) -> u64 {
+    x ^= x.rotate_left(15) ^ x.rotate_left(42);
+    x = x.wrapping_mul(K);
+    x ^= x.rotate_left(13) ^ x.rotate_left(31);
+    x
+}
+
+/// `const` version of [`chibi_hash64`]
+///
+/// Produces the same values and can be evaluated at compile time, e.g. for
+/// `const` items and static tables. Prefer [`chibi_hash64`] at runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::{chibi_hash64, chibi_hash64_const};
+///
+/// const GET: u64 = chibi_hash64_const(b"GET", 0);
+/// assert_eq!(GET, chibi_hash64(b"GET", 0));
+/// ```
+pub const fn chibi_hash64_const(key: &[u8], seed: u64) -> u64 {
+    let mut h = initial_state(seed);
+
+    let mut p = 0;
     let mut l = key.len();
 
     // Process 32-byte chunks
     while l >= 32 {
-        for i in 0..4 {
-            let stripe = load_u64_le(&p[i * 8..]);
+        let mut i = 0;
+        while i < 4 {
+            let stripe = load_u64_le_const(key, p + i * 8);
             h[i] = stripe.wrapping_add(h[i]).wrapping_mul(K);
             h[(i + 1) & 3] = h[(i + 1) & 3].wrapping_add(stripe.rotate_left(27));
+            i += 1;
         }
-        p = &p[32..];
+        p += 32;
         l -= 32;
     }
 
     // Process 8-byte chunks
     while l >= 8 {
-        h[0] ^= load_u32_le(&p[0..]);
+        h[0] ^= load_u32_le_const(key, p);
         h[0] = h[0].wrapping_mul(K);
-        h[1] ^= load_u32_le(&p[4..]);
+        h[1] ^= load_u32_le_const(key, p + 4);
         h[1] = h[1].wrapping_mul(K);
-        p = &p[8..];
+        p += 8;
         l -= 8;
     }
 
     // Handle remaining bytes
     if l >= 4 {
-        h[2] ^= load_u32_le(&p[0..]);
-        h[3] ^= load_u32_le(&p[l - 4..]);
+        h[2] ^= load_u32_le_const(key, p);
+        h[3] ^= load_u32_le_const(key, p + l - 4);
     } else if l > 0 {
-        h[2] ^= u64::from(p[0]);
-        h[3] ^= u64::from(p[l / 2]) | (u64::from(p[l - 1]) << 8);
+        h[2] ^= key[p] as u64;
+        h[3] ^= key[p + l / 2] as u64 | ((key[p + l - 1] as u64) << 8);
     }
 
     h[0] = h[0].wrapping_add((h[2].wrapping_mul(K)).rotate_left(31) ^ (h[2] >> 31));
@@ -149,57 +294,350 @@ fn load_u64_le(bytes: &[u8]) -> u64 {
     u64::from_le_bytes(bytes[..8].try_into().unwrap())
 }
 
+#[inline(always)]
+const fn load_u64_le_const(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes([
+        bytes[i],
+        bytes[i + 1],
+        bytes[i + 2],
+        bytes[i + 3],
+        bytes[i + 4],
+        bytes[i + 5],
+        bytes[i + 6],
+        bytes[i + 7],
+    ])
+}
+
+#[inline(always)]
+const fn load_u32_le_const(bytes: &[u8], i: usize) -> u64 {
+    u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as u64
+}
+
+// Absorbs every whole 32-byte stripe of `p` into `h` and returns the rest
+#[inline(always)]
+pub(crate) fn process_stripes<'a>(h: &mut [u64; 4], p: &'a [u8]) -> &'a [u8] {
+    // Takes every whole stripe, so the target-specific loops below see none
+    #[cfg(feature = "portable-simd")]
+    let p = portable::process_stripes(h, p);
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    let p = wasm::process_stripes(h, p);
+
+    #[cfg(all(
+        target_arch = "aarch64",
+        target_feature = "neon",
+        target_endian = "little"
+    ))]
+    // SAFETY: NEON is enabled at build time
+    let p = unsafe { neon::process_stripes(h, p) };
+
+    // Detection is only worth it when there is a whole block to process
+    #[cfg(target_arch = "x86_64")]
+    let p = if p.len() >= avx2::BLOCK && avx2::available() {
+        // SAFETY: AVX2 support was just checked
+        unsafe { avx2::process_stripes(h, p) }
+    } else {
+        p
+    };
+
+    process_stripes_scalar(h, p)
+}
+
+#[inline(always)]
+fn process_stripes_scalar<'a>(h: &mut [u64; 4], mut p: &'a [u8]) -> &'a [u8] {
+    while p.len() >= 32 {
+        for i in 0..4 {
+            let stripe = load_u64_le(&p[i * 8..]);
+            h[i] = stripe.wrapping_add(h[i]).wrapping_mul(K);
+            h[(i + 1) & 3] = h[(i + 1) & 3].wrapping_add(stripe.rotate_left(27));
+        }
+        p = &p[32..];
+    }
+    p
+}
+
+/// Hashes any [`Hash`](core::hash::Hash) value with [`ChibiHasher`]
+///
+/// Equal to writing `value` into `ChibiHasher::new(seed)` and calling
+/// `finish`, so it agrees with the hashes used by [`ChibiHashMap`]-style
+/// collections with the same seed. The bytes fed in follow the value's
+/// `Hash` impl, which for integers is native-endian and for `usize` is
+/// pointer-width, so results can differ across platforms.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::hash_one;
+///
+/// #[derive(Hash)]
+/// struct Key {
+///     tenant: u32,
+///     name: &'static str,
+/// }
+///
+/// let a = hash_one(&Key { tenant: 1, name: "a" }, 0);
+/// let b = hash_one(&(1u32, "a"), 0);
+/// assert_eq!(a, b);
+/// ```
+pub fn hash_one<T: core::hash::Hash + ?Sized>(value: &T, seed: u64) -> u64 {
+    let mut hasher = ChibiHasher::new(seed);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes `data` under a domain tag
+///
+/// The same bytes hashed under different domains give unrelated results, so
+/// subsystems sharing a seed cannot collide by accident. The domain is hashed
+/// into the seed rather than prepended to the data, so nothing is copied.
+/// Equal to `chibi_hash64(data, domain_seed(domain, seed))`.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::hash_with_domain;
+///
+/// let user = hash_with_domain(b"user", b"42", 0);
+/// let order = hash_with_domain(b"order", b"42", 0);
+/// assert_ne!(user, order);
+/// ```
+pub fn hash_with_domain(domain: &[u8], data: &[u8], seed: u64) -> u64 {
+    chibi_hash64(data, domain_seed(domain, seed))
+}
+
+/// Returns the seed [`hash_with_domain`] uses for `domain`
+///
+/// Pass it to any seeded API to hash under the domain incrementally.
+pub fn domain_seed(domain: &[u8], seed: u64) -> u64 {
+    chibi_hash64(domain, seed ^ DOMAIN_SEED)
+}
+
+// Keeps `domain_seed` distinct from a plain hash of the domain bytes
+const DOMAIN_SEED: u64 = 0xD0D0_D0D0_D0D0_D0D0;
+
 /// Configuration for the hash function
-#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+///
+/// Written bytes are absorbed by a [`StreamingChibiHasher`], so hashing
+/// never allocates and the hasher is `Copy`. The result equals
+/// [`chibi_hash64`] of all written bytes concatenated.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ChibiHasher {
-    seed: u64,
-    buffer: Vec<u8>,
+    inner: StreamingChibiHasher,
 }
 
 impl ChibiHasher {
-    pub fn new(seed: u64) -> Self {
+    pub const fn new(seed: u64) -> Self {
         Self {
-            seed,
-            buffer: Vec::new(),
+            inner: StreamingChibiHasher::new(seed),
         }
     }
 
+    /// Creates a hasher for `domain`, matching [`hash_with_domain`]
+    pub fn with_domain(domain: &[u8], seed: u64) -> Self {
+        Self::new(domain_seed(domain, seed))
+    }
+
     pub fn hash(&self, input: &[u8]) -> u64 {
-        chibi_hash64(input, self.seed)
+        chibi_hash64(input, self.inner.seed)
+    }
+}
+
+impl Default for ChibiHasher {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl ChibiHasher {
+    /// Writes `s` the way `str`'s `Hash` impl does, with a `0xFF`
+    /// terminator. See [`StreamingChibiHasher::write_str_key`].
+    #[inline]
+    pub fn write_str_key(&mut self, s: &str) {
+        self.inner.write_str_key(s);
+    }
+
+    /// Writes the bytes of `s` with no terminator. See
+    /// [`StreamingChibiHasher::write_str_raw`].
+    #[inline]
+    pub fn write_str_raw(&mut self, s: &str) {
+        self.inner.write_str_raw(s);
+    }
+
+    #[inline(always)]
+    fn write_fixed<const N: usize>(&mut self, bytes: [u8; N]) {
+        self.inner.write_fixed(bytes);
     }
 }
 
 impl Hasher for ChibiHasher {
     fn finish(&self) -> u64 {
-        // Hash the accumulated bytes with our chibi_hash64 function
-        chibi_hash64(&self.buffer, self.seed)
+        self.inner.finalize()
     }
 
     fn write(&mut self, bytes: &[u8]) {
-        // Append the new bytes to our buffer
-        self.buffer.extend_from_slice(bytes);
+        self.inner.update(bytes);
     }
+
+    int_write_fast_paths!();
 }
 
-#[cfg(any(feature = "std", feature = "hashbrown"))]
 impl BuildHasher for ChibiHasher {
     type Hasher = ChibiHasher;
 
+    fn build_hasher(&self) -> Self::Hasher {
+        ChibiHasher::new(self.inner.seed)
+    }
+}
+
+/// A `BuildHasher` that carries only a seed
+///
+/// Unlike using [`ChibiHasher`] as its own builder, this holds no hashing
+/// state. `Default` gives seed 0.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ChibiBuildHasher {
+    seed: u64,
+}
+
+impl ChibiBuildHasher {
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the seed given to every built hasher
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl core::hash::BuildHasher for ChibiBuildHasher {
+    type Hasher = ChibiHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        ChibiHasher::new(self.seed)
+    }
+}
+
+/// The hasher to name in `BuildHasherDefault<ChibiDefaultHasher>`, which
+/// builds seed-0 hashers from a zero-sized builder
+pub type ChibiDefaultHasher = ChibiHasher;
+
+/// A `BuildHasher` with a random seed per instance
+///
+/// Each `new` or `default` draws a seed from
+/// [`random_seed`](crate::random::random_seed), so colliding keys cannot be
+/// precomputed. Clones share the seed, as they must for a map's hasher.
+#[cfg(feature = "random")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChibiRandomState {
+    seed: u64,
+}
+
+#[cfg(feature = "random")]
+impl ChibiRandomState {
+    pub fn new() -> Self {
+        Self {
+            seed: crate::random::random_seed(),
+        }
+    }
+
+    /// Returns a builder with a fixed `seed`, for reproducible collections
+    pub const fn with_seed(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the seed given to every built hasher
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(feature = "random")]
+impl Default for ChibiRandomState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "random")]
+impl core::hash::BuildHasher for ChibiRandomState {
+    type Hasher = ChibiHasher;
+
     fn build_hasher(&self) -> Self::Hasher {
         ChibiHasher::new(self.seed)
     }
 }
 
+/// The `BuildHasher` of [`ChibiHashMap`] and the other collection aliases
+///
+/// [`ChibiHasher`], whose `default()` uses seed 0. With the `random-seed`
+/// feature it is `ChibiRandomState` instead, so every `default()`
+/// collection draws its own random seed.
+#[cfg(not(feature = "random-seed"))]
+pub type ChibiCollectionHasher = ChibiHasher;
+
+/// The `BuildHasher` of [`ChibiHashMap`] and the other collection aliases
+///
+/// [`ChibiRandomState`] with the `random-seed` feature, so every `default()`
+/// collection draws its own random seed; [`ChibiHasher`] at seed 0 without.
+#[cfg(feature = "random-seed")]
+pub type ChibiCollectionHasher = ChibiRandomState;
+
 /// A HashMap that uses ChibiHash by default
 #[cfg(any(feature = "std", feature = "hashbrown"))]
-pub type ChibiHashMap<K, V> = BaseHashMap<K, V, ChibiHasher>;
+pub type ChibiHashMap<K, V> = BaseHashMap<K, V, ChibiCollectionHasher>;
 
 /// A HashSet that uses ChibiHash by default
 #[cfg(any(feature = "std", feature = "hashbrown"))]
-pub type ChibiHashSet<T> = BaseHashSet<T, ChibiHasher>;
+pub type ChibiHashSet<T> = BaseHashSet<T, ChibiCollectionHasher>;
+
+/// A HashMap that uses ChibiHash with a random seed per map
+#[cfg(all(feature = "random", any(feature = "std", feature = "hashbrown")))]
+pub type ChibiHashMapRandom<K, V> = BaseHashMap<K, V, ChibiRandomState>;
+
+/// A HashSet that uses ChibiHash with a random seed per set
+#[cfg(all(feature = "random", any(feature = "std", feature = "hashbrown")))]
+pub type ChibiHashSetRandom<T> = BaseHashSet<T, ChibiRandomState>;
+
+/// An insertion-ordered `IndexMap` that uses ChibiHash
+#[cfg(feature = "indexmap")]
+pub type ChibiIndexMap<K, V> = indexmap::IndexMap<K, V, ChibiCollectionHasher>;
+
+/// An insertion-ordered `IndexSet` that uses ChibiHash
+#[cfg(feature = "indexmap")]
+pub type ChibiIndexSet<T> = indexmap::IndexSet<T, ChibiCollectionHasher>;
+
+/// A concurrent `DashMap` that uses ChibiHash
+#[cfg(feature = "dashmap")]
+pub type ChibiDashMap<K, V> = dashmap::DashMap<K, V, ChibiCollectionHasher>;
+
+/// A concurrent `DashSet` that uses ChibiHash
+#[cfg(feature = "dashmap")]
+pub type ChibiDashSet<T> = dashmap::DashSet<T, ChibiCollectionHasher>;
+
+/// An `lru::LruCache` that uses ChibiHash
+#[cfg(feature = "lru")]
+pub type ChibiLruCache<K, V> = lru::LruCache<K, V, ChibiCollectionHasher>;
+
+/// A fixed-capacity `heapless::IndexMap` that uses ChibiHash, for use
+/// without an allocator
+///
+/// `N` must be a power of two greater than one. `ChibiHeaplessMap::new()` is
+/// `const` and hashes with [`Chibi32Hasher`] at seed 0.
+#[cfg(feature = "heapless")]
+pub type ChibiHeaplessMap<K, V, const N: usize> =
+    heapless::IndexMap<K, V, hash32::BuildHasherDefault<Chibi32Hasher>, N>;
+
+/// A fixed-capacity `heapless::IndexSet` that uses ChibiHash; see
+/// [`ChibiHeaplessMap`]
+#[cfg(feature = "heapless")]
+pub type ChibiHeaplessSet<T, const N: usize> =
+    heapless::IndexSet<T, hash32::BuildHasherDefault<Chibi32Hasher>, N>;
 
 /// Streaming ChibiHasher that processes data incrementally
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy)]
 pub struct StreamingChibiHasher {
     h: [u64; 4],
     total_len: u64,
@@ -211,18 +649,24 @@ pub struct StreamingChibiHasher {
 impl StreamingChibiHasher {
     #[inline(always)]
     pub const fn new(seed: u64) -> Self {
-        let seed2 = seed
-            .wrapping_sub(K)
-            .rotate_left(15)
-            .wrapping_add(seed.wrapping_sub(K).rotate_left(47));
+        Self::from_state(initial_state(seed), seed)
+    }
 
+    /// Creates a hasher for `domain`, matching [`hash_with_domain`]
+    pub fn with_domain(domain: &[u8], seed: u64) -> Self {
+        Self::new(domain_seed(domain, seed))
+    }
+
+    /// Creates a hasher whose state lanes start at `h`
+    ///
+    /// `new(seed)` starts from the lanes derived from `seed`; any other starting point gives
+    /// an independent hash family. `seed` still enters the final mix. Lanes
+    /// produced by another hasher's midstream state let a computation
+    /// continue without re-feeding the prefix, provided that prefix was a
+    /// multiple of 32 bytes; the length counter starts again at zero.
+    pub const fn from_state(h: [u64; 4], seed: u64) -> Self {
         Self {
-            h: [
-                seed,
-                seed.wrapping_add(K),
-                seed2,
-                seed2.wrapping_add(K.wrapping_mul(K) ^ K),
-            ],
+            h,
             buf: [0; 32],
             buf_len: 0,
             total_len: 0,
@@ -230,92 +674,203 @@ impl StreamingChibiHasher {
         }
     }
 
+    /// Creates a hasher keyed with a 256-bit secret, matching
+    /// [`chibi_hash64_keyed`]
+    pub const fn new_keyed(secret: &[u8; 32]) -> Self {
+        let (h, seed) = keyed_state(secret);
+        Self::from_state(h, seed)
+    }
+
     pub fn update(&mut self, input: &[u8]) {
         let mut p = input;
-        let mut l = p.len();
 
         // If there's data in buf, try to fill it up
         if self.buf_len > 0 {
-            while l > 0 && self.buf_len < 32 {
-                self.buf[self.buf_len] = p[0];
-                self.buf_len += 1;
-                p = &p[1..];
-                l -= 1;
-            }
+            let n = p.len().min(32 - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&p[..n]);
+            self.buf_len += n;
+            p = &p[n..];
 
             // Flush if filled
             if self.buf_len == 32 {
-                for i in 0..4 {
-                    let stripe = load_u64_le(&self.buf[i * 8..]);
-                    self.h[i] = stripe.wrapping_add(self.h[i]).wrapping_mul(K);
-                    self.h[(i + 1) & 3] = self.h[(i + 1) & 3].wrapping_add(stripe.rotate_left(27));
-                }
+                process_stripes(&mut self.h, &self.buf);
                 self.buf_len = 0;
             }
         }
 
         // Process 32-byte chunks
-        while l >= 32 {
-            for i in 0..4 {
-                let stripe = load_u64_le(&p[i * 8..]);
-                self.h[i] = stripe.wrapping_add(self.h[i]).wrapping_mul(K);
-                self.h[(i + 1) & 3] = self.h[(i + 1) & 3].wrapping_add(stripe.rotate_left(27));
-            }
-            p = &p[32..];
-            l -= 32;
-        }
+        p = process_stripes(&mut self.h, p);
+        let l = p.len();
 
         // Store remaining bytes in buffer
-        while l > 0 {
-            self.buf[self.buf_len] = p[0];
-            self.buf_len += 1;
-            p = &p[1..];
-            l -= 1;
-        }
+        self.buf[self.buf_len..self.buf_len + l].copy_from_slice(p);
+        self.buf_len += l;
 
         self.total_len += input.len() as u64;
     }
 
-    pub fn finalize(&self) -> u64 {
-        let mut h = self.h;
-        let mut p = &self.buf[..self.buf_len];
-        let mut l = self.buf_len;
-
-        // Process 8-byte chunks
-        while l >= 8 {
-            h[0] ^= load_u32_le(&p[0..]);
-            h[0] = h[0].wrapping_mul(K);
-            h[1] ^= load_u32_le(&p[4..]);
-            h[1] = h[1].wrapping_mul(K);
-            p = &p[8..];
-            l -= 8;
-        }
+    /// Writes `s` the way `str`'s `Hash` impl does: its bytes followed by
+    /// a `0xFF` terminator, so `("ab", "c")` and `("a", "bc")` differ.
+    /// Gives the same result as `s.hash(&mut hasher)`.
+    #[inline]
+    pub fn write_str_key(&mut self, s: &str) {
+        self.update(s.as_bytes());
+        self.write_fixed([0xFF]);
+    }
+
+    /// Writes the bytes of `s` with no terminator
+    ///
+    /// Cheaper than [`write_str_key`](Self::write_str_key), but not
+    /// prefix-free: use it only when the string is the whole key or its
+    /// length is written separately.
+    #[inline]
+    pub fn write_str_raw(&mut self, s: &str) {
+        self.update(s.as_bytes());
+    }
+
+    /// Returns the midstream state: the four lanes, the number of bytes
+    /// written so far and the bytes buffered since the last 32-byte stripe
+    ///
+    /// The lanes have absorbed every complete stripe; the buffered bytes
+    /// are only mixed in by [`finalize`](Self::finalize).
+    pub fn state(&self) -> ([u64; 4], u64, &[u8]) {
+        (self.h, self.total_len, &self.buf[..self.buf_len])
+    }
+
+    /// Returns the seed used in the final mix
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Encodes the midstream state for resuming later with
+    /// [`from_bytes`](Self::from_bytes)
+    ///
+    /// The encoding is stable across platforms and crate versions; see
+    /// [`crate::state`] for the layout.
+    pub fn to_bytes(&self) -> [u8; STATE_LEN] {
+        state::encode(
+            2,
+            &state::RawState {
+                h: self.h,
+                total_len: self.total_len,
+                seed: self.seed,
+                buf: self.buf,
+                buf_len: self.buf_len,
+            },
+        )
+    }
 
-        // Handle remaining bytes
-        if l >= 4 {
-            h[2] ^= load_u32_le(&p[0..]);
-            h[3] ^= load_u32_le(&p[l - 4..]);
-        } else if l > 0 {
-            h[2] ^= u64::from(p[0]);
-            h[3] ^= u64::from(p[l / 2]) | (u64::from(p[l - 1]) << 8);
+    /// Restores a hasher encoded by [`to_bytes`](Self::to_bytes)
+    ///
+    /// Fails if the input is malformed or was produced by the other
+    /// algorithm version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StateError> {
+        let raw = state::decode(2, bytes)?;
+        Ok(Self {
+            h: raw.h,
+            total_len: raw.total_len,
+            seed: raw.seed,
+            buf: raw.buf,
+            buf_len: raw.buf_len,
+        })
+    }
+
+    // Fast path for the fixed-size writes of the integer `Hasher` methods.
+    // Leaves the state exactly as `update` would.
+    #[inline(always)]
+    fn write_fixed<const N: usize>(&mut self, bytes: [u8; N]) {
+        if self.buf_len + N < 32 {
+            self.buf[self.buf_len..self.buf_len + N].copy_from_slice(&bytes);
+            self.buf_len += N;
+            self.total_len += N as u64;
+        } else {
+            self.update(&bytes);
         }
+    }
 
-        h[0] = h[0].wrapping_add((h[2].wrapping_mul(K)).rotate_left(31) ^ (h[2] >> 31));
-        h[1] = h[1].wrapping_add((h[3].wrapping_mul(K)).rotate_left(31) ^ (h[3] >> 31));
-        h[0] = h[0].wrapping_mul(K);
-        h[0] ^= h[0] >> 31;
-        h[1] = h[1].wrapping_add(h[0]);
+    pub fn finalize(&self) -> u64 {
+        finalize_state(self.h, &self.buf[..self.buf_len], self.total_len, self.seed)
+    }
+
+    /// Returns `K` hashes of the input so far, as [`chibi_hash64_multi`]
+    /// would; the first equals [`finalize`](Self::finalize)
+    pub fn finalize_multi<const K: usize>(&self) -> [u64; K] {
+        finalize_multi_state(self.h, &self.buf[..self.buf_len], self.total_len, self.seed)
+    }
+}
+
+// Bytes past `buf_len` are stale and must not affect equality
+impl PartialEq for StreamingChibiHasher {
+    fn eq(&self, other: &Self) -> bool {
+        self.h == other.h
+            && self.total_len == other.total_len
+            && self.seed == other.seed
+            && self.buf[..self.buf_len] == other.buf[..other.buf_len]
+    }
+}
+
+impl Eq for StreamingChibiHasher {}
+
+impl core::hash::Hash for StreamingChibiHasher {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.h.hash(state);
+        self.total_len.hash(state);
+        self.seed.hash(state);
+        self.buf[..self.buf_len].hash(state);
+    }
+}
+
+/// Hashes formatted text without building a `String`
+///
+/// `write!(hasher, "{}:{}", a, b)` feeds exactly the bytes `format!` would
+/// produce, with no terminator, so the result equals `chibi_hash64` of the
+/// formatted string.
+impl core::fmt::Write for StreamingChibiHasher {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.update(s.as_bytes());
+        Ok(())
+    }
+}
 
-        let mut x = (self.total_len).wrapping_mul(K);
-        x ^= x.rotate_left(29);
-        x = x.wrapping_add(self.seed);
-        x ^= h[1];
+/// Lets the hasher be the sink of [`std::io::copy`] and other writers
+///
+/// Every write is accepted in full. `Hasher` has a `write` method too, so
+/// with both traits in scope call `update` or use fully qualified syntax.
+#[cfg(feature = "std")]
+impl std::io::Write for StreamingChibiHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.update(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
 
-        x ^= x.rotate_left(15) ^ x.rotate_left(42);
-        x = x.wrapping_mul(K);
-        x ^= x.rotate_left(13) ^ x.rotate_left(31);
+#[cfg(feature = "serde")]
+impl serde::Serialize for StreamingChibiHasher {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        state::serialize(&self.to_bytes(), serializer)
+    }
+}
 
-        x
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StreamingChibiHasher {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = state::deserialize(2, deserializer)?;
+        Ok(Self {
+            h: raw.h,
+            total_len: raw.total_len,
+            seed: raw.seed,
+            buf: raw.buf,
+            buf_len: raw.buf_len,
+        })
     }
 }
 
@@ -327,6 +882,279 @@ impl Hasher for StreamingChibiHasher {
     fn write(&mut self, bytes: &[u8]) {
         self.update(bytes);
     }
+
+    int_write_fast_paths!();
+}
+
+/// Hashes many independent keys with the same seed
+///
+/// Returns `chibi_hash64(input, seed)` for every input, in order. See
+/// [`hash_many_into`] to reuse an output buffer.
+///
+/// Keys are hashed several at a time with their state interleaved, so short
+/// keys are processed in SIMD registers: 4 at a time, or 8 on x86_64 CPUs
+/// with AVX2 when `std` is enabled for runtime detection.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::{chibi_hash64, hash_many};
+///
+/// let hashes = hash_many(&[b"a", b"bb", b"ccc"], 0);
+/// assert_eq!(hashes[1], chibi_hash64(b"bb", 0));
+/// ```
+pub fn hash_many(inputs: &[&[u8]], seed: u64) -> Vec<u64> {
+    let mut out = vec![0; inputs.len()];
+    hash_many_into(inputs, seed, &mut out);
+    out
+}
+
+/// Hashes many independent keys into `out`, without allocating
+///
+/// # Panics
+///
+/// Panics if `out` and `inputs` differ in length.
+pub fn hash_many_into(inputs: &[&[u8]], seed: u64, out: &mut [u64]) {
+    assert_eq!(
+        inputs.len(),
+        out.len(),
+        "output length must match the number of inputs"
+    );
+    multi::hash_many_into(inputs, seed, out);
+}
+
+/// Returns an iterator over the hashes of every prefix of `key`
+///
+/// The `n`-th item is `chibi_hash64(&key[..n + 1], seed)`. All hashes are
+/// computed in a single pass by snapshotting the streaming state, which is
+/// handy for building tries or longest-prefix match tables.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::{chibi_hash64, prefix_hashes};
+///
+/// let hashes: Vec<u64> = prefix_hashes(b"abc", 0).collect();
+/// assert_eq!(hashes, [
+///     chibi_hash64(b"a", 0),
+///     chibi_hash64(b"ab", 0),
+///     chibi_hash64(b"abc", 0),
+/// ]);
+/// ```
+pub fn prefix_hashes(key: &[u8], seed: u64) -> PrefixHashes<'_> {
+    prefix_hashes_by(key, seed, 1)
+}
+
+/// Like [`prefix_hashes`], but only for prefixes whose length is a multiple
+/// of `step`, plus the whole key
+///
+/// A `step` of 32 yields one hash per stripe boundary.
+///
+/// # Panics
+///
+/// Panics if `step` is zero.
+pub fn prefix_hashes_by(key: &[u8], seed: u64, step: usize) -> PrefixHashes<'_> {
+    assert!(step > 0, "step must be non-zero");
+    PrefixHashes {
+        hasher: StreamingChibiHasher::new(seed),
+        rest: key,
+        step,
+    }
+}
+
+/// Iterator returned by [`prefix_hashes`] and [`prefix_hashes_by`]
+#[derive(Debug, Clone)]
+pub struct PrefixHashes<'a> {
+    hasher: StreamingChibiHasher,
+    rest: &'a [u8],
+    step: usize,
+}
+
+impl Iterator for PrefixHashes<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (chunk, rest) = self.rest.split_at(self.step.min(self.rest.len()));
+        self.hasher.update(chunk);
+        self.rest = rest;
+        Some(self.hasher.finalize())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rest.len().div_ceil(self.step);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for PrefixHashes<'_> {}
+
+/// 32-bit ChibiHash of `key`
+///
+/// The 64-bit hash is folded down rather than truncated, so all of its bits
+/// contribute to the result.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::chibi_hash32;
+///
+/// let hash: u32 = chibi_hash32(b"sensor-7", 0);
+/// assert_ne!(hash, chibi_hash32(b"sensor-8", 0));
+/// ```
+pub fn chibi_hash32(key: &[u8], seed: u64) -> u32 {
+    fold32(chibi_hash64(key, seed))
+}
+
+/// `K` 64-bit hashes of `key` from a single pass
+///
+/// The first is `chibi_hash64(key, seed)` and the others are derived from
+/// the same final state, so they cost a few multiplies each instead of
+/// hashing `key` again with another seed. For filters and other structures
+/// that need several hashes per key. The others also mix in the four lanes
+/// before they are folded together, so two keys that collide on the first
+/// hash usually still differ on the rest.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::{chibi_hash64, chibi_hash64_multi};
+///
+/// let [h1, h2, h3] = chibi_hash64_multi::<3>(b"key", 0);
+/// assert_eq!(h1, chibi_hash64(b"key", 0));
+/// assert!(h1 != h2 && h2 != h3);
+/// ```
+pub fn chibi_hash64_multi<const K: usize>(key: &[u8], seed: u64) -> [u64; K] {
+    let mut h = initial_state(seed);
+    let p = process_stripes(&mut h, key);
+    finalize_multi_state(h, p, key.len() as u64, seed)
+}
+
+/// A [`Hasher`] producing 32-bit ChibiHash values
+///
+/// Bytes are hashed incrementally without allocating. [`Hasher::finish`]
+/// returns the 32-bit result zero-extended to `u64`; use
+/// [`finish32`](Chibi32Hasher::finish32) to get it as a `u32`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chibi32Hasher {
+    inner: StreamingChibiHasher,
+}
+
+impl Chibi32Hasher {
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            inner: StreamingChibiHasher::new(seed),
+        }
+    }
+
+    /// Returns the 32-bit hash of the bytes written so far
+    pub fn finish32(&self) -> u32 {
+        fold32(self.inner.finalize())
+    }
+}
+
+impl Default for Chibi32Hasher {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Chibi32Hasher {
+    #[inline(always)]
+    fn write_fixed<const N: usize>(&mut self, bytes: [u8; N]) {
+        self.inner.write_fixed(bytes);
+    }
+}
+
+impl Hasher for Chibi32Hasher {
+    fn finish(&self) -> u64 {
+        u64::from(self.finish32())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    int_write_fast_paths!();
+}
+
+#[cfg(feature = "heapless")]
+impl hash32::Hasher for Chibi32Hasher {
+    fn finish32(&self) -> u32 {
+        Chibi32Hasher::finish32(self)
+    }
+}
+
+/// Extendable-output hasher producing digests of any length
+///
+/// Input is absorbed with [`update`](Self::update) and any number of output
+/// bytes is read with [`squeeze`](Self::squeeze). Output block `i` is the
+/// hash of the absorbed input followed by `i` as 8 little-endian bytes, so
+/// the first 8 bytes differ from [`chibi_hash64`] of the input alone.
+///
+/// Successive squeezes continue the same output stream. Calling `update`
+/// after squeezing restarts the stream from the beginning for the extended
+/// input.
+///
+/// Like the rest of ChibiHash this is not a cryptographic XOF.
+///
+/// # Examples
+///
+/// ```rust
+/// use chibihash::v2::ChibiXof;
+///
+/// let mut out = [0u8; 20];
+/// ChibiXof::new(7).update(b"user:42").squeeze(&mut out);
+///
+/// let mut split = [0u8; 20];
+/// let mut xof = ChibiXof::new(7);
+/// xof.update(b"user:42");
+/// xof.squeeze(&mut split[..3]);
+/// xof.squeeze(&mut split[3..]);
+/// assert_eq!(out, split);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChibiXof {
+    state: StreamingChibiHasher,
+    counter: u64,
+    block: [u8; 8],
+    block_pos: usize,
+}
+
+impl ChibiXof {
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: StreamingChibiHasher::new(seed),
+            counter: 0,
+            block: [0; 8],
+            block_pos: 8,
+        }
+    }
+
+    /// Absorbs `input` and resets the output stream
+    pub fn update(&mut self, input: &[u8]) -> &mut Self {
+        self.state.update(input);
+        self.counter = 0;
+        self.block_pos = 8;
+        self
+    }
+
+    /// Fills `out` with the next bytes of the output stream
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        for byte in out {
+            if self.block_pos == 8 {
+                let mut state = self.state;
+                state.update(&self.counter.to_le_bytes());
+                self.block = state.finalize().to_le_bytes();
+                self.counter = self.counter.wrapping_add(1);
+                self.block_pos = 0;
+            }
+            *byte = self.block[self.block_pos];
+            self.block_pos += 1;
+        }
+    }
 }
 
 #[inline(always)]
@@ -340,6 +1168,8 @@ mod tests {
 
     #[cfg(all(not(feature = "std"), feature = "hashbrown"))]
     use alloc::string::{String, ToString};
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
 
     // Keep only internal implementation tests here
     #[test]
@@ -437,4 +1267,476 @@ mod tests {
             "Split streaming should match expected hash"
         );
     }
+
+    #[test]
+    fn test_prefix_hashes() {
+        let key: Vec<u8> = (0..100).collect();
+        let hashes: Vec<u64> = prefix_hashes(&key, 7).collect();
+        assert_eq!(hashes.len(), key.len());
+        for (i, hash) in hashes.iter().enumerate() {
+            assert_eq!(*hash, chibi_hash64(&key[..i + 1], 7), "prefix {}", i + 1);
+        }
+        assert_eq!(prefix_hashes(b"", 7).count(), 0);
+    }
+
+    #[test]
+    fn test_prefix_hashes_by() {
+        let key: Vec<u8> = (0..100).collect();
+        let iter = prefix_hashes_by(&key, 7, 32);
+        assert_eq!(iter.len(), 4);
+        let expected: Vec<u64> = [32, 64, 96, 100]
+            .iter()
+            .map(|&n| chibi_hash64(&key[..n], 7))
+            .collect();
+        assert_eq!(iter.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_process_stripes_matches_scalar() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + i / 13) as u8).collect();
+        for len in [0, 31, 32, 33, 64, 100, 128, 129, 1000] {
+            let mut h = [1, 2, 3, 4];
+            let mut expected = h;
+            let rest = process_stripes(&mut h, &data[..len]);
+            let expected_rest = process_stripes_scalar(&mut expected, &data[..len]);
+            assert_eq!(h, expected, "len {}", len);
+            assert_eq!(rest, expected_rest);
+        }
+    }
+
+    #[test]
+    fn test_chibi_hash32() {
+        let hash = chibi_hash64(b"hello", 3);
+        assert_eq!(chibi_hash32(b"hello", 3), (hash ^ (hash >> 32)) as u32);
+
+        let mut hasher = Chibi32Hasher::new(3);
+        hasher.write(b"hel");
+        hasher.write(b"lo");
+        assert_eq!(hasher.finish32(), chibi_hash32(b"hello", 3));
+        assert_eq!(hasher.finish(), u64::from(hasher.finish32()));
+    }
+
+    #[test]
+    fn test_chibi_hash64_multi() {
+        let data: [u8; 100] = core::array::from_fn(|i| i as u8);
+        for len in [0, 1, 7, 31, 32, 33, 64, 100] {
+            let key = &data[..len];
+            let hashes = chibi_hash64_multi::<4>(key, 9);
+            assert_eq!(hashes[0], chibi_hash64(key, 9));
+            for i in 0..4 {
+                for j in 0..i {
+                    assert_ne!(hashes[i], hashes[j]);
+                }
+            }
+
+            let mut hasher = StreamingChibiHasher::new(9);
+            for chunk in key.chunks(5) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize_multi::<4>(), hashes);
+            assert_eq!(hasher.finalize_multi::<2>(), hashes[..2]);
+        }
+        assert!(chibi_hash64_multi::<0>(b"key", 0).is_empty());
+    }
+
+    #[test]
+    fn test_xof() {
+        let mut out = [0u8; 24];
+        ChibiXof::new(5).update(b"input").squeeze(&mut out);
+
+        let mut block = StreamingChibiHasher::new(5);
+        block.update(b"input");
+        block.update(&1u64.to_le_bytes());
+        assert_eq!(out[8..16], block.finalize().to_le_bytes());
+
+        // Updating restarts the stream
+        let mut xof = ChibiXof::new(5);
+        xof.update(b"in");
+        xof.squeeze(&mut [0u8; 5]);
+        xof.update(b"put");
+        let mut again = [0u8; 24];
+        xof.squeeze(&mut again);
+        assert_eq!(out, again);
+    }
+
+    #[test]
+    fn test_const_matches_runtime() {
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 37 + 11) as u8).collect();
+        for len in 0..data.len() {
+            for seed in [0, 1, 0xDEADBEEF] {
+                assert_eq!(
+                    chibi_hash64_const(&data[..len], seed),
+                    chibi_hash64(&data[..len], seed),
+                    "len {} seed {}",
+                    len,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_hasher_matches_direct() {
+        let mut hasher = ChibiHasher::new(9);
+        let copy = hasher;
+        hasher.write(b"Hello, ");
+        hasher.write(&[b'w'; 40]);
+        hasher.write(b"orld!");
+
+        let mut expected = b"Hello, ".to_vec();
+        expected.extend_from_slice(&[b'w'; 40]);
+        expected.extend_from_slice(b"orld!");
+        assert_eq!(hasher.finish(), chibi_hash64(&expected, 9));
+        assert_eq!(copy.finish(), chibi_hash64(b"", 9));
+    }
+
+    #[test]
+    fn test_hasher_eq_ignores_chunking() {
+        let mut a = ChibiHasher::new(0);
+        a.write(&[1; 40]);
+        a.write(b"xy");
+        let mut b = ChibiHasher::new(0);
+        b.write(&[1; 32]);
+        b.write(&[1; 8]);
+        b.write(b"x");
+        b.write(b"y");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_integer_writes_match_bytes() {
+        let mut hasher = ChibiHasher::new(4);
+        let mut streaming = StreamingChibiHasher::new(4);
+        let mut expected = Vec::new();
+        for i in 0..20u64 {
+            hasher.write_u8(i as u8);
+            hasher.write_u64(i);
+            hasher.write_i32(-(i as i32));
+            hasher.write_u128(u128::from(i) << 64);
+            hasher.write_usize(i as usize);
+            streaming.write_u64(i);
+            expected.push(i as u8);
+            expected.extend_from_slice(&i.to_ne_bytes());
+            expected.extend_from_slice(&(-(i as i32)).to_ne_bytes());
+            expected.extend_from_slice(&(u128::from(i) << 64).to_ne_bytes());
+            expected.extend_from_slice(&(i as usize).to_ne_bytes());
+        }
+        assert_eq!(hasher.finish(), chibi_hash64(&expected, 4));
+
+        let words: Vec<u8> = (0..20u64).flat_map(|i| i.to_ne_bytes()).collect();
+        assert_eq!(streaming.finish(), chibi_hash64(&words, 4));
+    }
+
+    #[test]
+    fn test_write_str_matches_hash() {
+        let mut expected = ChibiHasher::new(1);
+        core::hash::Hash::hash(&("ab", "c"), &mut expected);
+        let mut hasher = ChibiHasher::new(1);
+        hasher.write_str_key("ab");
+        hasher.write_str_key("c");
+        assert_eq!(hasher.finish(), expected.finish());
+
+        let mut raw = StreamingChibiHasher::new(1);
+        raw.write_str_raw("hello ");
+        raw.write_str_raw("world, this is longer than one stripe");
+        assert_eq!(
+            raw.finalize(),
+            chibi_hash64(b"hello world, this is longer than one stripe", 1)
+        );
+    }
+
+    #[test]
+    fn test_build_hasher() {
+        use core::hash::{BuildHasher, BuildHasherDefault};
+
+        let builder = ChibiBuildHasher::new(42);
+        let mut hasher = builder.build_hasher();
+        hasher.write(b"key");
+        assert_eq!(hasher.finish(), chibi_hash64(b"key", 42));
+
+        let mut hasher = BuildHasherDefault::<ChibiDefaultHasher>::default().build_hasher();
+        hasher.write(b"key");
+        assert_eq!(hasher.finish(), chibi_hash64(b"key", 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_build_hasher_default_map() {
+        use std::collections::HashMap;
+        use std::hash::BuildHasherDefault;
+
+        let mut map: HashMap<&str, u32, BuildHasherDefault<ChibiDefaultHasher>> =
+            HashMap::default();
+        map.insert("a", 1);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(core::mem::size_of_val(map.hasher()), 0);
+
+        let mut map = HashMap::with_hasher(ChibiBuildHasher::new(7));
+        map.insert("b", 2);
+        assert_eq!(map.get("b"), Some(&2));
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_random_state() {
+        use core::hash::BuildHasher;
+
+        let a = ChibiRandomState::new();
+        let b = ChibiRandomState::new();
+        assert_ne!(a.seed(), b.seed());
+
+        let mut hasher = a.build_hasher();
+        hasher.write(b"key");
+        assert_eq!(hasher.finish(), chibi_hash64(b"key", a.seed()));
+    }
+
+    #[cfg(all(feature = "getrandom", any(feature = "std", feature = "hashbrown")))]
+    #[test]
+    fn test_random_map() {
+        let mut map: ChibiHashMapRandom<u32, u32> = ChibiHashMapRandom::default();
+        map.insert(1, 2);
+        assert_eq!(map.get(&1), Some(&2));
+    }
+
+    #[test]
+    fn test_keyed() {
+        let secret: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let cases: [(&[u8], u64); 4] = [
+            (b"", 0x86083945F1970F02),
+            (b"abcdefgh", 0x39C7708A41992A89),
+            (b"hello world", 0x806A46273DAC9188),
+            (&[0x5A; 100], 0xCB5F8473176DC946),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(chibi_hash64_keyed(input, &secret), expected);
+            let mut hasher = StreamingChibiHasher::new_keyed(&secret);
+            for chunk in input.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), chibi_hash64_keyed(input, &secret));
+        }
+
+        // Every byte of the secret matters
+        let base = chibi_hash64_keyed(b"key", &secret);
+        for i in 0..32 {
+            let mut other = secret;
+            other[i] ^= 1;
+            assert_ne!(chibi_hash64_keyed(b"key", &other), base);
+        }
+    }
+
+    #[test]
+    fn test_domain() {
+        let a = hash_with_domain(b"a", b"data", 1);
+        assert_eq!(a, chibi_hash64(b"data", domain_seed(b"a", 1)));
+        assert_ne!(a, hash_with_domain(b"b", b"data", 1));
+        assert_ne!(a, hash_with_domain(b"a", b"data", 2));
+        assert_ne!(a, chibi_hash64(b"data", 1));
+
+        let mut hasher = ChibiHasher::with_domain(b"a", 1);
+        hasher.write(b"da");
+        hasher.write(b"ta");
+        assert_eq!(hasher.finish(), a);
+
+        let mut streaming = StreamingChibiHasher::with_domain(b"a", 1);
+        streaming.update(b"data");
+        assert_eq!(streaming.finalize(), a);
+
+        // Domain boundaries are not just concatenation
+        assert_ne!(
+            hash_with_domain(b"ab", b"c", 0),
+            hash_with_domain(b"a", b"bc", 0)
+        );
+    }
+
+    #[test]
+    fn test_from_state() {
+        let mut a = StreamingChibiHasher::from_state(initial_state(5), 5);
+        a.update(b"hello");
+        assert_eq!(a.finalize(), chibi_hash64(b"hello", 5));
+
+        let mut b = StreamingChibiHasher::from_state([1, 2, 3, 4], 5);
+        b.update(b"hello");
+        assert_ne!(b.finalize(), a.finalize());
+    }
+
+    #[test]
+    fn test_state() {
+        let mut hasher = StreamingChibiHasher::new(3);
+        let (h, total_len, buffered) = hasher.state();
+        assert_eq!(h, StreamingChibiHasher::from_state(h, 3).state().0);
+        assert_eq!((total_len, buffered), (0, &[][..]));
+
+        let input: Vec<u8> = (0..40).collect();
+        hasher.update(&input);
+        let (h, total_len, buffered) = hasher.state();
+        assert_eq!(total_len, 40);
+        assert_eq!(buffered, &input[32..]);
+        assert_eq!(hasher.seed(), 3);
+
+        // The lanes after one stripe match a hasher fed only that stripe
+        let mut stripe = StreamingChibiHasher::new(3);
+        stripe.update(&input[..32]);
+        assert_eq!(stripe.state().0, h);
+    }
+
+    #[test]
+    fn test_state_bytes_roundtrip() {
+        let input: Vec<u8> = (0..100).collect();
+        for split in [0, 1, 31, 32, 33, 64, 99] {
+            let mut hasher = StreamingChibiHasher::new(11);
+            hasher.update(&input[..split]);
+            let bytes = hasher.to_bytes();
+            let mut resumed = StreamingChibiHasher::from_bytes(&bytes).unwrap();
+            assert_eq!(resumed, hasher);
+            resumed.update(&input[split..]);
+            assert_eq!(resumed.finalize(), chibi_hash64(&input, 11));
+        }
+
+        let other = crate::v1::StreamingChibiHasher::new(0).to_bytes();
+        assert!(matches!(
+            StreamingChibiHasher::from_bytes(&other),
+            Err(StateError::WrongAlgorithm { .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut hasher = StreamingChibiHasher::new(5);
+        hasher.update(b"some bytes past one 32-byte stripe, midstream");
+        let json = serde_json::to_string(&hasher).unwrap();
+        let restored: StreamingChibiHasher = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, hasher);
+        assert!(serde_json::from_str::<StreamingChibiHasher>("[1, 2]").is_err());
+
+        let mut hasher = ChibiHasher::new(5);
+        hasher.write(b"key");
+        let json = serde_json::to_string(&hasher).unwrap();
+        let restored: ChibiHasher = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.finish(), hasher.finish());
+
+        let builder = ChibiBuildHasher::new(9);
+        let json = serde_json::to_string(&builder).unwrap();
+        assert_eq!(json, r#"{"seed":9}"#);
+        assert_eq!(
+            serde_json::from_str::<ChibiBuildHasher>(&json).unwrap(),
+            builder
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io_write() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut hasher = StreamingChibiHasher::new(2);
+        let copied = std::io::copy(&mut &data[..], &mut hasher).unwrap();
+        assert_eq!(copied, 1000);
+        assert_eq!(hasher.finalize(), chibi_hash64(&data, 2));
+
+        let mut hasher = StreamingChibiHasher::new(2);
+        std::io::Write::write_all(&mut hasher, b"abc").unwrap();
+        std::io::Write::flush(&mut hasher).unwrap();
+        assert_eq!(hasher.finalize(), chibi_hash64(b"abc", 2));
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        use core::fmt::Write;
+
+        let (id, name) = (42, "user");
+        let mut hasher = StreamingChibiHasher::new(8);
+        write!(hasher, "{}:{}", id, name).unwrap();
+        assert_eq!(hasher.finalize(), chibi_hash64(b"42:user", 8));
+    }
+
+    #[test]
+    fn test_hash_one() {
+        let mut hasher = ChibiHasher::new(4);
+        core::hash::Hash::hash(&(1u8, "two", [3u16]), &mut hasher);
+        assert_eq!(hash_one(&(1u8, "two", [3u16]), 4), hasher.finish());
+        assert_ne!(hash_one("a", 0), hash_one("b", 0));
+        assert_eq!(
+            hash_one(&[1u8, 2][..], 0),
+            hash_one(&Vec::from([1u8, 2]), 0)
+        );
+    }
+
+    #[test]
+    fn test_hash_many() {
+        let inputs: [&[u8]; 4] = [b"", b"a", b"hello world", &[7; 100]];
+        let hashes = hash_many(&inputs, 9);
+        assert_eq!(hashes.len(), 4);
+        for (input, hash) in inputs.iter().zip(&hashes) {
+            assert_eq!(*hash, chibi_hash64(input, 9));
+        }
+        assert!(hash_many(&[], 9).is_empty());
+
+        let mut out = [0u64; 4];
+        hash_many_into(&inputs, 9, &mut out);
+        assert_eq!(out[..], hashes[..]);
+    }
+
+    #[test]
+    fn test_hash_many_matches_scalar() {
+        let data: Vec<u8> = (0..200u32).map(|i| (i * 37 + 11) as u8).collect();
+        // Every tail length, a few stripe counts, and ragged batch sizes
+        let inputs: Vec<&[u8]> = (0..100).map(|i| &data[i % 7..i % 7 + i]).collect();
+        for n in [0, 1, 3, 4, 5, 8, 9, 17, 100] {
+            let mut out = vec![0; n];
+            hash_many_into(&inputs[..n], 21, &mut out);
+            for (input, hash) in inputs[..n].iter().zip(&out) {
+                assert_eq!(*hash, chibi_hash64(input, 21), "len {}", input.len());
+            }
+        }
+
+        let mut out = [0u64; 8];
+        multi::hash_lanes::<8>(&inputs[24..32], 5, &mut out);
+        for (input, hash) in inputs[24..32].iter().zip(&out) {
+            assert_eq!(*hash, chibi_hash64(input, 5));
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_avx2_stripes_match_scalar() {
+        if !avx2::available() {
+            return;
+        }
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 31 + i / 7) as u8).collect();
+        let mut seeds = 0x1234_5678_9ABC_DEF0u64;
+        for len in [0, 31, 127, 128, 129, 255, 256, 300, 1024, 2000] {
+            seeds = crate::mix::moremur(seeds);
+            let mut h = initial_state(seeds);
+            let mut expected = h;
+            // SAFETY: AVX2 support was checked above
+            let rest = unsafe { avx2::process_stripes(&mut h, &data[..len]) };
+            let rest = process_stripes_scalar(&mut h, rest);
+            let expected_rest = process_stripes_scalar(&mut expected, &data[..len]);
+            assert_eq!(h, expected, "len {}", len);
+            assert_eq!(rest, expected_rest);
+        }
+    }
+
+    #[cfg(all(
+        target_arch = "aarch64",
+        target_feature = "neon",
+        target_endian = "little"
+    ))]
+    #[test]
+    fn test_neon_stripes_match_scalar() {
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 31 + i / 7) as u8).collect();
+        let mut seeds = 0x1234_5678_9ABC_DEF0u64;
+        for len in [0, 31, 127, 128, 129, 255, 256, 300, 1024, 2000] {
+            seeds = crate::mix::moremur(seeds);
+            let mut h = initial_state(seeds);
+            let mut expected = h;
+            // SAFETY: NEON is enabled at build time
+            let rest = unsafe { neon::process_stripes(&mut h, &data[..len]) };
+            let rest = process_stripes_scalar(&mut h, rest);
+            let expected_rest = process_stripes_scalar(&mut expected, &data[..len]);
+            assert_eq!(h, expected, "len {}", len);
+            assert_eq!(rest, expected_rest);
+        }
+    }
 }
@@ -0,0 +1,107 @@
+// Multi-buffer hashing: `LANES` independent keys processed in lockstep
+//
+// Every step after the stripe loop is written as a loop over lanes, with the
+// state stored word-major (`h[word][lane]`), so LLVM can keep one state word
+// of all lanes in a single SSE/AVX (or NEON, simd128) register. Key tails are
+// copied into zero-padded buffers so each lane can load unconditionally, and
+// per-lane length differences become selects instead of branches.
+//
+// Keys of 32 bytes or more run their stripes through the regular stripe loop
+// one lane at a time; the batch is aimed at short keys, where per-call
+// overhead and the serial finalizer dominate.
+
+use super::{chibi_hash64, initial_state, load_u32_le, process_stripes, K};
+
+pub(super) fn hash_many_into(inputs: &[&[u8]], seed: u64, out: &mut [u64]) {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: AVX2 support was just detected
+        return unsafe { hash_many_avx2(inputs, seed, out) };
+    }
+    hash_many_lanes::<4>(inputs, seed, out);
+}
+
+// Same code with 256-bit registers available, so twice the lanes fit
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn hash_many_avx2(inputs: &[&[u8]], seed: u64, out: &mut [u64]) {
+    hash_many_lanes::<8>(inputs, seed, out);
+}
+
+#[inline(always)]
+fn hash_many_lanes<const LANES: usize>(inputs: &[&[u8]], seed: u64, out: &mut [u64]) {
+    let mut keys = inputs.chunks_exact(LANES);
+    let mut hashes = out.chunks_exact_mut(LANES);
+    for (keys, hashes) in (&mut keys).zip(&mut hashes) {
+        hash_lanes::<LANES>(keys, seed, hashes);
+    }
+    for (key, hash) in keys.remainder().iter().zip(hashes.into_remainder()) {
+        *hash = chibi_hash64(key, seed);
+    }
+}
+
+// Hashes exactly `LANES` keys; equal to `chibi_hash64` on each
+#[inline(always)]
+pub(super) fn hash_lanes<const LANES: usize>(keys: &[&[u8]], seed: u64, out: &mut [u64]) {
+    let init = initial_state(seed);
+    let mut h = [[0u64; LANES]; 4];
+    let mut tails = [[0u8; 32]; LANES];
+    let mut tail_lens = [0usize; LANES];
+    let mut key_lens = [0u64; LANES];
+
+    for j in 0..LANES {
+        let mut state = init;
+        let tail = process_stripes(&mut state, keys[j]);
+        for (word, lanes) in h.iter_mut().enumerate() {
+            lanes[j] = state[word];
+        }
+        tails[j][..tail.len()].copy_from_slice(tail);
+        tail_lens[j] = tail.len();
+        key_lens[j] = keys[j].len() as u64;
+    }
+
+    // 8-byte chunks, at most three per tail
+    for round in 0..3 {
+        for j in 0..LANES {
+            let active = round < tail_lens[j] / 8;
+            let a = (h[0][j] ^ load_u32_le(&tails[j][round * 8..])).wrapping_mul(K);
+            let b = (h[1][j] ^ load_u32_le(&tails[j][round * 8 + 4..])).wrapping_mul(K);
+            h[0][j] = if active { a } else { h[0][j] };
+            h[1][j] = if active { b } else { h[1][j] };
+        }
+    }
+
+    // Remaining 0 to 7 bytes
+    for j in 0..LANES {
+        let t = &tails[j];
+        let o = tail_lens[j] & !7;
+        let l = tail_lens[j] & 7;
+        if l >= 4 {
+            h[2][j] ^= load_u32_le(&t[o..]);
+            h[3][j] ^= load_u32_le(&t[o + l - 4..]);
+        } else if l > 0 {
+            h[2][j] ^= u64::from(t[o]);
+            h[3][j] ^= u64::from(t[o + l / 2]) | (u64::from(t[o + l - 1]) << 8);
+        }
+    }
+
+    for j in 0..LANES {
+        let (h2, h3) = (h[2][j], h[3][j]);
+        let mut h0 = h[0][j].wrapping_add((h2.wrapping_mul(K)).rotate_left(31) ^ (h2 >> 31));
+        let h1 = h[1][j].wrapping_add((h3.wrapping_mul(K)).rotate_left(31) ^ (h3 >> 31));
+        h0 = h0.wrapping_mul(K);
+        h0 ^= h0 >> 31;
+        let h1 = h1.wrapping_add(h0);
+
+        let mut x = key_lens[j].wrapping_mul(K);
+        x ^= x.rotate_left(29);
+        x = x.wrapping_add(seed);
+        x ^= h1;
+
+        x ^= x.rotate_left(15) ^ x.rotate_left(42);
+        x = x.wrapping_mul(K);
+        x ^= x.rotate_left(13) ^ x.rotate_left(31);
+
+        out[j] = x;
+    }
+}
@@ -0,0 +1,109 @@
+// aarch64 NEON version of the stripe loop
+//
+// The state is kept in two `uint64x2_t` registers, `[h0, h1]` and `[h2, h3]`,
+// and four stripes are folded into one update with only one multiply on the
+// dependency chain, as described in the AVX2 version. NEON has no 64-bit
+// multiply either; `mul` builds one from widening 32-bit ones.
+
+use core::arch::aarch64::*;
+
+use super::K;
+
+const K2: u64 = K.wrapping_mul(K);
+const K3: u64 = K2.wrapping_mul(K);
+const K4: u64 = K3.wrapping_mul(K);
+
+/// Bytes processed per iteration
+pub(super) const BLOCK: usize = 128;
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn rotate_left_27(v: uint64x2_t) -> uint64x2_t {
+    vorrq_u64(vshlq_n_u64::<27>(v), vshrq_n_u64::<37>(v))
+}
+
+// Low 64 bits of `a * c` in both lanes, with `c` split into 32-bit halves
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn mul(a: uint64x2_t, c: (uint32x2_t, uint32x2_t)) -> uint64x2_t {
+    let (c_lo, c_hi) = c;
+    let a_lo = vmovn_u64(a);
+    let a_hi = vshrn_n_u64::<32>(a);
+    let cross = vmlal_u32(vmull_u32(a_hi, c_lo), a_lo, c_hi);
+    vaddq_u64(vmull_u32(a_lo, c_lo), vshlq_n_u64::<32>(cross))
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn split(c: u64) -> (uint32x2_t, uint32x2_t) {
+    (vdup_n_u32(c as u32), vdup_n_u32((c >> 32) as u32))
+}
+
+// A stripe as `s + [0, r0, r1, r2]` in two halves, plus `[r3, 0]` for h0
+struct Stripe {
+    lo: uint64x2_t,
+    hi: uint64x2_t,
+    last: uint64x2_t,
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn stripe(p: &[u8]) -> Stripe {
+    // SAFETY: `p` holds at least 32 bytes and `vld1q_u8` has no alignment
+    // requirement. The target is little-endian, matching `load_u64_le`.
+    let s_lo = vreinterpretq_u64_u8(vld1q_u8(p.as_ptr()));
+    let s_hi = vreinterpretq_u64_u8(vld1q_u8(p.as_ptr().add(16)));
+    let zero = vdupq_n_u64(0);
+    let r_lo = rotate_left_27(s_lo);
+    let r_hi = rotate_left_27(s_hi);
+    Stripe {
+        // [0, r0] and [r1, r2]
+        lo: vaddq_u64(s_lo, vextq_u64::<1>(zero, r_lo)),
+        hi: vaddq_u64(s_hi, vextq_u64::<1>(r_lo, r_hi)),
+        // [r3, 0]
+        last: vextq_u64::<1>(r_hi, zero),
+    }
+}
+
+/// Processes whole 128-byte blocks and returns the rest
+///
+/// # Safety
+///
+/// The CPU must support NEON.
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn process_stripes<'a>(h: &mut [u64; 4], mut p: &'a [u8]) -> &'a [u8] {
+    let k = split(K);
+    let k2 = split(K2);
+    let k3 = split(K3);
+    let k4 = split(K4);
+    let mut lo = vcombine_u64(vcreate_u64(h[0]), vcreate_u64(h[1]));
+    let mut hi = vcombine_u64(vcreate_u64(h[2]), vcreate_u64(h[3]));
+
+    while p.len() >= BLOCK {
+        let s0 = stripe(p);
+        let s1 = stripe(&p[32..]);
+        let s2 = stripe(&p[64..]);
+        let s3 = stripe(&p[96..]);
+
+        // Only h0 receives the `last` terms
+        let rest_lo = vaddq_u64(
+            vaddq_u64(
+                mul(vaddq_u64(s0.last, s1.lo), k3),
+                mul(vaddq_u64(s1.last, s2.lo), k2),
+            ),
+            vaddq_u64(mul(vaddq_u64(s2.last, s3.lo), k), s3.last),
+        );
+        let rest_hi = vaddq_u64(vaddq_u64(mul(s1.hi, k3), mul(s2.hi, k2)), mul(s3.hi, k));
+        lo = vaddq_u64(mul(vaddq_u64(lo, s0.lo), k4), rest_lo);
+        hi = vaddq_u64(mul(vaddq_u64(hi, s0.hi), k4), rest_hi);
+        p = &p[BLOCK..];
+    }
+
+    *h = [
+        vgetq_lane_u64::<0>(lo),
+        vgetq_lane_u64::<1>(lo),
+        vgetq_lane_u64::<0>(hi),
+        vgetq_lane_u64::<1>(hi),
+    ];
+    p
+}
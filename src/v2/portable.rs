@@ -0,0 +1,36 @@
+// `core::simd` version of the stripe loop, behind the nightly-only
+// `portable-simd` feature
+//
+// The state is one `u64x4`, `[h0, h1, h2, h3]`. In the scalar loop lane `i`
+// absorbs the rotated stripe of lane `i - 1` before its multiply, except
+// lane 0, which absorbs lane 3's after it. Rotating the lanes by one and
+// masking off lane 0 expresses both without scalar work, and the compiler
+// lowers it to whatever vector instructions the target has.
+
+use core::simd::u64x4;
+
+use super::{load_u64_le, K};
+
+#[inline(always)]
+pub(super) fn process_stripes<'a>(h: &mut [u64; 4], mut p: &'a [u8]) -> &'a [u8] {
+    let k = u64x4::splat(K);
+    let first = u64x4::from_array([!0, 0, 0, 0]);
+    let mut v = u64x4::from_array(*h);
+
+    while p.len() >= 32 {
+        let s = u64x4::from_array([
+            load_u64_le(p),
+            load_u64_le(&p[8..]),
+            load_u64_le(&p[16..]),
+            load_u64_le(&p[24..]),
+        ]);
+        let r = (s << u64x4::splat(27)) | (s >> u64x4::splat(37));
+        // [r3, r0, r1, r2]
+        let r = r.rotate_elements_right::<1>();
+        v = (v + s + (r & !first)) * k + (r & first);
+        p = &p[32..];
+    }
+
+    *h = v.to_array();
+    p
+}
@@ -0,0 +1,54 @@
+// wasm32 `simd128` version of the stripe loop
+//
+// The state is kept in two `v128` registers, `[h0, h1]` and `[h2, h3]`. In
+// the scalar loop lane `i` absorbs the rotated stripe of lane `i - 1` before
+// its multiply, except lane 0, which absorbs lane 3's after it. Both are
+// expressed as lane shuffles so a stripe is processed without scalar work.
+
+use core::arch::wasm32::*;
+
+use super::K;
+
+#[inline(always)]
+fn rotate_left_27(v: v128) -> v128 {
+    v128_or(i64x2_shl(v, 27), u64x2_shr(v, 37))
+}
+
+#[inline(always)]
+pub(super) fn process_stripes<'a>(h: &mut [u64; 4], mut p: &'a [u8]) -> &'a [u8] {
+    let k = u64x2_splat(K);
+    let zero = u64x2_splat(0);
+    let mut lo = u64x2(h[0], h[1]);
+    let mut hi = u64x2(h[2], h[3]);
+
+    while p.len() >= 32 {
+        // SAFETY: `p` holds at least 32 bytes and `v128_load` allows
+        // unaligned loads. wasm is little-endian, matching `load_u64_le`.
+        let (s_lo, s_hi) = unsafe {
+            (
+                v128_load(p.as_ptr() as *const v128),
+                v128_load(p.as_ptr().add(16) as *const v128),
+            )
+        };
+        let r_lo = rotate_left_27(s_lo);
+        let r_hi = rotate_left_27(s_hi);
+
+        // [0, r0] and [r1, r2]
+        let carry_lo = i64x2_shuffle::<0, 2>(zero, r_lo);
+        let carry_hi = i64x2_shuffle::<1, 2>(r_lo, r_hi);
+        lo = i64x2_mul(i64x2_add(i64x2_add(lo, s_lo), carry_lo), k);
+        hi = i64x2_mul(i64x2_add(i64x2_add(hi, s_hi), carry_hi), k);
+
+        // [r3, 0]
+        lo = i64x2_add(lo, i64x2_shuffle::<1, 2>(r_hi, zero));
+        p = &p[32..];
+    }
+
+    *h = [
+        u64x2_extract_lane::<0>(lo),
+        u64x2_extract_lane::<1>(lo),
+        u64x2_extract_lane::<0>(hi),
+        u64x2_extract_lane::<1>(hi),
+    ];
+    p
+}
@@ -0,0 +1,336 @@
+//! Algorithm versions, at the type level and at runtime
+//!
+//! [`V1`] and [`V2`] are zero-sized types that let other types record which
+//! version produced a value, e.g.
+//! [`ChibiHash64<V1>`](crate::hash::ChibiHash64), so digests of different
+//! versions cannot be mixed up.
+//!
+//! [`Version`] selects the version at runtime instead, for applications that
+//! store it next to persisted hashes:
+//!
+//! ```rust
+//! use chibihash::version::{chibi_hash64_versioned, Version, VersionedStreamingHasher};
+//!
+//! // Read back from storage alongside the hash
+//! let version: Version = "v1".parse().unwrap();
+//! let stored = chibihash::v1::chibi_hash64(b"record", 0);
+//! assert_eq!(chibi_hash64_versioned(b"record", 0, version), stored);
+//!
+//! let mut hasher = VersionedStreamingHasher::new(version, 0);
+//! hasher.update(b"rec");
+//! hasher.update(b"ord");
+//! assert_eq!(hasher.finalize(), stored);
+//! ```
+
+use core::fmt;
+use core::hash::Hasher;
+use core::str::FromStr;
+
+use crate::algorithm::ChibiAlgorithm;
+use crate::{v1, v2};
+
+/// An algorithm version selected at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Version {
+    /// The `v1` algorithm, used by the crate-root re-exports unless the
+    /// `default-v2` feature is enabled
+    V1,
+    /// The `v2` algorithm
+    V2,
+}
+
+impl Version {
+    /// Every version, oldest first
+    pub const ALL: [Version; 2] = [Version::V1, Version::V2];
+
+    /// Returns the version number, 1 or 2
+    ///
+    /// This is also the algorithm byte of the [`state`](crate::state)
+    /// encoding.
+    pub const fn number(self) -> u8 {
+        match self {
+            Version::V1 => 1,
+            Version::V2 => 2,
+        }
+    }
+
+    /// Returns the version with the given number, if there is one
+    pub const fn from_number(number: u8) -> Option<Self> {
+        match number {
+            1 => Some(Version::V1),
+            2 => Some(Version::V2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Version::V1 => "v1",
+            Version::V2 => "v2",
+        })
+    }
+}
+
+/// Error returned when parsing a [`Version`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseVersionError;
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid version, expected v1 or v2")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseVersionError {}
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    /// Parses `v1`, `v2`, `1` or `2`, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = match s.as_bytes() {
+            [b'v' | b'V', n] | [n] => *n,
+            _ => return Err(ParseVersionError),
+        };
+        number
+            .checked_sub(b'0')
+            .and_then(Version::from_number)
+            .ok_or(ParseVersionError)
+    }
+}
+
+/// Hashes `data` with the algorithm `version`
+///
+/// Equal to `v1::chibi_hash64` or `v2::chibi_hash64`.
+pub fn chibi_hash64_versioned(data: &[u8], seed: u64, version: Version) -> u64 {
+    match version {
+        Version::V1 => v1::chibi_hash64(data, seed),
+        Version::V2 => v2::chibi_hash64(data, seed),
+    }
+}
+
+/// A streaming hasher for a [`Version`] selected at runtime
+///
+/// Wraps `v1::StreamingChibiHasher` or `v2::StreamingChibiHasher`, and
+/// produces the same hashes.
+#[derive(Debug, Clone)]
+pub struct VersionedStreamingHasher {
+    inner: Inner,
+}
+
+#[derive(Debug, Clone)]
+enum Inner {
+    V1(v1::StreamingChibiHasher),
+    V2(v2::StreamingChibiHasher),
+}
+
+impl VersionedStreamingHasher {
+    /// Creates a hasher for `version` with `seed`
+    pub fn new(version: Version, seed: u64) -> Self {
+        let inner = match version {
+            Version::V1 => Inner::V1(v1::StreamingChibiHasher::new(seed)),
+            Version::V2 => Inner::V2(v2::StreamingChibiHasher::new(seed)),
+        };
+        Self { inner }
+    }
+
+    /// Returns the version this hasher computes
+    pub fn version(&self) -> Version {
+        match self.inner {
+            Inner::V1(_) => Version::V1,
+            Inner::V2(_) => Version::V2,
+        }
+    }
+
+    /// Feeds `input` into the hash
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::V1(hasher) => hasher.update(input),
+            Inner::V2(hasher) => hasher.update(input),
+        }
+    }
+
+    /// Returns the hash of everything fed so far
+    pub fn finalize(&self) -> u64 {
+        match &self.inner {
+            Inner::V1(hasher) => hasher.finalize(),
+            Inner::V2(hasher) => hasher.finalize(),
+        }
+    }
+}
+
+impl Hasher for VersionedStreamingHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.finalize()
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for VersionedStreamingHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Zero-sized marker for the `v1` algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct V1;
+
+/// Zero-sized marker for the `v2` algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct V2;
+
+pub(crate) mod sealed {
+    use core::hash::{Hash, Hasher};
+
+    use crate::{v1, v2};
+
+    pub trait Sealed {}
+    impl Sealed for super::V1 {}
+    impl Sealed for super::V2 {}
+
+    // The operations `generic::StreamingChibiHasher` forwards to
+    pub trait Streaming: Hasher + Copy + Eq + Hash + core::fmt::Debug {
+        fn new(seed: u64) -> Self;
+        fn update(&mut self, input: &[u8]);
+        fn finalize(&self) -> u64;
+    }
+
+    macro_rules! impl_streaming {
+        ($($hasher:ty),*) => {
+            $(
+                impl Streaming for $hasher {
+                    #[inline]
+                    fn new(seed: u64) -> Self {
+                        <$hasher>::new(seed)
+                    }
+
+                    #[inline]
+                    fn update(&mut self, input: &[u8]) {
+                        <$hasher>::update(self, input)
+                    }
+
+                    #[inline]
+                    fn finalize(&self) -> u64 {
+                        <$hasher>::finalize(self)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_streaming!(v1::StreamingChibiHasher, v2::StreamingChibiHasher);
+}
+
+/// Implemented by [`V1`] and [`V2`]; sealed
+///
+/// The algorithm's steps and one-shot `hash64` come from the
+/// [`ChibiAlgorithm`] supertrait.
+pub trait VersionMarker:
+    ChibiAlgorithm + sealed::Sealed + Copy + Default + Eq + Ord + core::hash::Hash + core::fmt::Debug
+{
+    /// The version number, 1 or 2
+    const VERSION: u8;
+
+    /// This version's streaming hasher, wrapped by
+    /// [`generic::StreamingChibiHasher`](crate::generic::StreamingChibiHasher)
+    #[doc(hidden)]
+    type Streaming: sealed::Streaming;
+
+    /// Fills `out` from this version's `ChibiXof`
+    fn xof(key: &[u8], seed: u64, out: &mut [u8]);
+}
+
+impl VersionMarker for V1 {
+    const VERSION: u8 = 1;
+
+    type Streaming = crate::v1::StreamingChibiHasher;
+
+    fn xof(key: &[u8], seed: u64, out: &mut [u8]) {
+        crate::v1::ChibiXof::new(seed).update(key).squeeze(out);
+    }
+}
+
+impl VersionMarker for V2 {
+    const VERSION: u8 = 2;
+
+    type Streaming = crate::v2::StreamingChibiHasher;
+
+    fn xof(key: &[u8], seed: u64, out: &mut [u8]) {
+        crate::v2::ChibiXof::new(seed).update(key).squeeze(out);
+    }
+}
+
+impl From<V1> for Version {
+    fn from(_: V1) -> Self {
+        Version::V1
+    }
+}
+
+impl From<V2> for Version {
+    fn from(_: V2) -> Self {
+        Version::V2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        for (s, version) in [("v1", Version::V1), ("V2", Version::V2), ("2", Version::V2)] {
+            assert_eq!(s.parse::<Version>(), Ok(version));
+        }
+        for s in ["", "v", "v3", "v12", "x1", "0"] {
+            assert_eq!(s.parse::<Version>(), Err(ParseVersionError), "{:?}", s);
+        }
+        for version in Version::ALL {
+            assert_eq!(Version::from_number(version.number()), Some(version));
+        }
+    }
+
+    #[test]
+    fn test_versioned_matches_modules() {
+        let data = [0x5Au8; 100];
+        assert_eq!(
+            chibi_hash64_versioned(&data, 7, Version::V1),
+            v1::chibi_hash64(&data, 7)
+        );
+        assert_eq!(
+            chibi_hash64_versioned(&data, 7, Version::V2),
+            v2::chibi_hash64(&data, 7)
+        );
+
+        for version in Version::ALL {
+            let mut hasher = VersionedStreamingHasher::new(version, 7);
+            hasher.update(&data[..33]);
+            hasher.update(&data[33..]);
+            assert_eq!(hasher.version(), version);
+            assert_eq!(hasher.finalize(), chibi_hash64_versioned(&data, 7, version));
+        }
+    }
+
+    #[test]
+    fn test_marker_hash64() {
+        // `ChibiAlgorithm` and `VersionMarker` are both in scope here
+        fn hash<V: VersionMarker>(key: &[u8]) -> u64 {
+            V::hash64(key, 7)
+        }
+        assert_eq!(V1::hash64(b"key", 7), v1::chibi_hash64(b"key", 7));
+        assert_eq!(hash::<V2>(b"key"), v2::chibi_hash64(b"key", 7));
+    }
+}
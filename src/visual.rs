@@ -0,0 +1,200 @@
+//! Visual fingerprints of hash digests
+//!
+//! Comparing two 16-character hex strings by eye is error prone. The helpers
+//! here render a digest as a picture, so a mismatch is obvious at a glance
+//! when pairing devices or eyeballing config diffs.
+//!
+//! - [`randomart`]: OpenSSH-style "drunken bishop" ASCII art.
+//! - [`identicon_svg`]: a small, horizontally symmetric 5x5 SVG identicon.
+//!
+//! Both use the digest bytes in big-endian order, matching the way a digest
+//! is printed with `{:016x}`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use chibihash::v2::chibi_hash64;
+//! use chibihash::visual::{identicon_svg, randomart};
+//!
+//! let hash = chibi_hash64(b"config.toml contents", 0);
+//! println!("{}", randomart(hash));
+//!
+//! let svg = identicon_svg(hash, 64);
+//! assert!(svg.starts_with("<svg"));
+//! ```
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use core::fmt::Write;
+
+const WIDTH: usize = 17;
+const HEIGHT: usize = 9;
+const SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^";
+const START: u8 = b'S';
+const END: u8 = b'E';
+
+/// Renders `hash` as OpenSSH-style randomart titled `ChibiHash`
+pub fn randomart(hash: u64) -> String {
+    randomart_with_title(hash, "ChibiHash")
+}
+
+/// Renders `hash` as OpenSSH-style randomart with a custom title
+///
+/// Titles longer than the 17-column field are truncated. Columns are
+/// counted in `char`s, so wide characters may still misalign the border.
+pub fn randomart_with_title(hash: u64, title: &str) -> String {
+    let mut field = [[0usize; WIDTH]; HEIGHT];
+    let (mut x, mut y) = (WIDTH / 2, HEIGHT / 2);
+    let start = (x, y);
+
+    for byte in hash.to_be_bytes() {
+        let mut b = byte;
+        for _ in 0..4 {
+            x = if b & 1 != 0 {
+                (x + 1).min(WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if b & 2 != 0 {
+                (y + 1).min(HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            field[y][x] += 1;
+            b >>= 2;
+        }
+    }
+
+    let mut out = String::with_capacity((WIDTH + 3) * (HEIGHT + 2));
+    push_border(&mut out, title);
+    for (row, cells) in field.iter().enumerate() {
+        out.push('|');
+        for (col, &count) in cells.iter().enumerate() {
+            let symbol = if (col, row) == start {
+                START
+            } else if (col, row) == (x, y) {
+                END
+            } else {
+                SYMBOLS[count.min(SYMBOLS.len() - 1)]
+            };
+            out.push(symbol as char);
+        }
+        out.push_str("|\n");
+    }
+    push_border(&mut out, "64");
+    out.pop();
+    out
+}
+
+fn push_border(out: &mut String, title: &str) {
+    let title = title.chars().take(WIDTH - 2);
+    let label = title.clone().count() + 2;
+    let left = (WIDTH - label) / 2;
+    out.push('+');
+    out.extend(core::iter::repeat_n('-', left));
+    out.push('[');
+    out.extend(title);
+    out.push(']');
+    out.extend(core::iter::repeat_n('-', WIDTH - label - left));
+    out.push_str("+\n");
+}
+
+/// Renders `hash` as a 5x5 horizontally symmetric SVG identicon
+///
+/// `size` is the width and height of the image in pixels. The cell pattern
+/// uses the low 15 bits of the digest, the colour is derived from the
+/// remaining bits.
+pub fn identicon_svg(hash: u64, size: u32) -> String {
+    let hue = (hash >> 16) % 360;
+    let saturation = 45 + (hash >> 32) % 30;
+    let lightness = 35 + (hash >> 40) % 20;
+
+    let mut out = String::with_capacity(1024);
+    // Writing to a String never fails
+    let _ = write!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+         viewBox=\"0 0 5 5\" shape-rendering=\"crispEdges\">\
+         <rect width=\"5\" height=\"5\" fill=\"#f0f0f0\"/>"
+    );
+    for row in 0..5 {
+        for col in 0..5 {
+            // Columns 3 and 4 mirror columns 1 and 0
+            let source = if col > 2 { 4 - col } else { col };
+            if hash >> (source * 5 + row) & 1 != 0 {
+                let _ = write!(
+                    out,
+                    "<rect x=\"{col}\" y=\"{row}\" width=\"1\" height=\"1\" \
+                     fill=\"hsl({hue},{saturation}%,{lightness}%)\"/>"
+                );
+            }
+        }
+    }
+    out.push_str("</svg>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_randomart_shape() {
+        let art = randomart(0x0123456789ABCDEF);
+        let lines: Vec<&str> = art.lines().collect();
+        assert_eq!(lines.len(), HEIGHT + 2);
+        for line in &lines {
+            assert_eq!(line.len(), WIDTH + 2, "bad line {:?}", line);
+        }
+        assert_eq!(lines[0], "+---[ChibiHash]---+");
+        assert_eq!(lines[HEIGHT + 1], "+------[64]-------+");
+        assert_eq!(art.matches('S').count(), 1);
+    }
+
+    #[test]
+    fn test_randomart_differs() {
+        assert_eq!(randomart(1), randomart(1));
+        assert_ne!(randomart(1), randomart(2));
+    }
+
+    #[test]
+    fn test_randomart_long_title() {
+        let art = randomart_with_title(0, "a very long title indeed");
+        assert_eq!(art.lines().next().unwrap().len(), WIDTH + 2);
+    }
+
+    #[test]
+    fn test_randomart_non_ascii_title() {
+        let art = randomart_with_title(0, "ключ");
+        assert_eq!(art.lines().next().unwrap(), "+-----[ключ]------+");
+
+        let art = randomart_with_title(0, "éééééééééééééééééééé");
+        let border = art.lines().next().unwrap();
+        assert_eq!(border.chars().count(), WIDTH + 2);
+        assert_eq!(border, "+[ééééééééééééééé]+");
+    }
+
+    #[test]
+    fn test_identicon_symmetric() {
+        // Only the cells of the first column set
+        let svg = identicon_svg(0b11111, 50);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("width=\"50\""));
+        assert_eq!(svg.matches("<rect x=\"0\"").count(), 5);
+        assert_eq!(svg.matches("<rect x=\"4\"").count(), 5);
+        assert_eq!(svg.matches("<rect x=\"2\"").count(), 0);
+    }
+
+    #[test]
+    fn test_identicon_differs() {
+        assert_ne!(identicon_svg(1, 32), identicon_svg(2, 32));
+    }
+}
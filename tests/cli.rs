@@ -0,0 +1,337 @@
+//! Tests for the `chibihash` binary
+//! Each test runs the built binary and checks its output and exit code
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use chibihash::{v1, v2};
+
+fn chibihash(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_chibihash"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+// A file under the temp directory, removed when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("chibihash-cli-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        TempFile(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn test_hash_stdin() {
+    let output = chibihash(&[], b"hello");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!("{:016x}  -\n", v2::chibi_hash64(b"hello", 0))
+    );
+
+    let output = chibihash(&["--algorithm", "v1", "-s", "0x10", "-"], b"hello");
+    assert_eq!(
+        stdout(&output),
+        format!("{:016x}  -\n", v1::chibi_hash64(b"hello", 16))
+    );
+}
+
+#[test]
+fn test_hash_files() {
+    let a = TempFile::new("a", b"alpha");
+    let b = TempFile::new("b", &[7u8; 100_000]);
+    let output = chibihash(&["--seed=42", a.path(), b.path()], b"");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{:016x}  {}\n{:016x}  {}\n",
+            v2::chibi_hash64(b"alpha", 42),
+            a.path(),
+            v2::chibi_hash64(&[7u8; 100_000], 42),
+            b.path()
+        )
+    );
+}
+
+#[test]
+fn test_errors() {
+    let a = TempFile::new("errors", b"alpha");
+    let output = chibihash(&["chibihash-no-such-file", a.path()], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).ends_with(&format!("  {}\n", a.path())));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("chibihash-no-such-file"));
+
+    assert_eq!(chibihash(&["--seed", "nope"], b"").status.code(), Some(2));
+    assert_eq!(
+        chibihash(&["--algorithm", "v9"], b"").status.code(),
+        Some(2)
+    );
+    assert_eq!(chibihash(&["--bogus"], b"").status.code(), Some(2));
+    assert!(chibihash(&["--help"], b"").status.success());
+}
+
+#[test]
+fn test_check() {
+    let a = TempFile::new("check-a", b"alpha");
+    let b = TempFile::new("check-b", b"beta");
+    let list = chibihash(&["-s", "3", a.path(), b.path()], b"");
+    let list = TempFile::new("check-list", &list.stdout);
+
+    let output = chibihash(&["-s", "3", "--check", list.path()], b"");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!("{}: OK\n{}: OK\n", a.path(), b.path())
+    );
+
+    // Wrong seed, and the list read from standard input
+    let output = chibihash(&["-c", "--quiet"], &std::fs::read(&list.0).unwrap());
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!("{}: FAILED\n{}: FAILED\n", a.path(), b.path())
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 computed checksums did NOT match"));
+}
+
+#[test]
+fn test_check_malformed_and_missing() {
+    let a = TempFile::new("check-ok", b"alpha");
+    let list = format!(
+        "{:016x} *{}\nnot a checksum line\n{:016x}  chibihash-no-such-file\n",
+        v2::chibi_hash64(b"alpha", 0),
+        a.path(),
+        0
+    );
+    let output = chibihash(&["--check"], list.as_bytes());
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}: OK\nchibihash-no-such-file: FAILED open or read\n",
+            a.path()
+        )
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("-: 2: improperly formatted line"));
+    assert!(stderr.contains("1 listed file could not be read"));
+}
+
+#[test]
+fn test_recursive() {
+    let dir = std::env::temp_dir().join(format!("chibihash-cli-{}-tree", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("b/c")).unwrap();
+    std::fs::write(dir.join("z"), b"z").unwrap();
+    std::fs::write(dir.join("a"), b"a").unwrap();
+    std::fs::write(dir.join("b/c/d"), b"d").unwrap();
+    std::fs::write(dir.join("b/e"), b"e").unwrap();
+    let root = dir.to_str().unwrap();
+
+    let expected: String = ["a", "b/c/d", "b/e", "z"]
+        .iter()
+        .map(|name| {
+            let contents = std::fs::read(dir.join(name)).unwrap();
+            format!(
+                "{:016x}  {}\n",
+                v2::chibi_hash64(&contents, 0),
+                dir.join(name).display()
+            )
+        })
+        .collect();
+    for jobs in ["1", "3"] {
+        let output = chibihash(&["-r", "-j", jobs, root], b"");
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), expected);
+    }
+
+    // Without -r a directory cannot be hashed
+    assert_eq!(chibihash(&[root], b"").status.code(), Some(1));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_formats() {
+    let a = TempFile::new("format-a", b"alpha");
+    let hash = v2::chibi_hash64(b"alpha", 0);
+
+    let output = chibihash(&["--format", "bsd", a.path()], b"");
+    assert_eq!(
+        stdout(&output),
+        format!("CHIBIHASH64-V2 ({}) = {:016x}\n", a.path(), hash)
+    );
+
+    let output = chibihash(&["-f", "json-lines", "-a", "v1", "-s", "7", "-"], b"alpha");
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{{\"path\":\"-\",\"algorithm\":\"v1\",\"seed\":7,\"hash\":\"{:016x}\"}}\n",
+            v1::chibi_hash64(b"alpha", 7)
+        )
+    );
+
+    let output = chibihash(&["-f", "json", a.path(), "chibihash-no-such-file"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    let json = stdout(&output);
+    assert!(json.starts_with("[\n  {"));
+    assert!(json.ends_with("}\n]\n"));
+    assert!(json.contains(&format!("\"hash\":\"{:016x}\"", hash)));
+    assert!(json.contains(
+        "{\"path\":\"chibihash-no-such-file\",\"algorithm\":\"v2\",\"seed\":0,\"error\":\""
+    ));
+
+    let output = chibihash(&["-z", a.path()], b"");
+    assert_eq!(stdout(&output), format!("{:016x}  {}\0", hash, a.path()));
+
+    assert_eq!(chibihash(&["-f", "xml"], b"").status.code(), Some(2));
+}
+
+#[test]
+fn test_check_formats() {
+    let a = TempFile::new("check-format-a", b"alpha");
+    let b = TempFile::new("check-format-b", b"beta");
+
+    // BSD lines carry the algorithm in their tag
+    let list = chibihash(&["-f", "bsd", "-a", "v1", a.path(), b.path()], b"");
+    let output = chibihash(&["-c", "-"], &list.stdout);
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    let list = chibihash(&["-z", a.path(), b.path()], b"");
+    let output = chibihash(&["-c", "-z"], &list.stdout);
+    assert_eq!(
+        stdout(&output),
+        format!("{}: OK\n{}: OK\n", a.path(), b.path())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_escaped_names() {
+    let file = TempFile::new("escape\\a\nb", b"alpha");
+    let hash = v2::chibi_hash64(b"alpha", 0);
+    let escaped = file.path().replace('\\', "\\\\").replace('\n', "\\n");
+
+    let output = chibihash(&[file.path()], b"");
+    assert_eq!(stdout(&output), format!("\\{:016x}  {}\n", hash, escaped));
+    let output = chibihash(&["-c", "-"], &output.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("\\{}: OK\n", escaped));
+
+    let output = chibihash(&["-f", "bsd", file.path()], b"");
+    assert!(stdout(&output).starts_with("\\CHIBIHASH"));
+    assert!(chibihash(&["-c", "-"], &output.stdout).status.success());
+
+    // NUL-terminated lists are written and read as-is
+    let output = chibihash(&["-z", file.path()], b"");
+    assert_eq!(
+        output.stdout,
+        format!("{:016x}  {}\0", hash, file.path()).into_bytes()
+    );
+}
+
+#[test]
+fn test_bench() {
+    let output = chibihash(&["bench", "--size", "1K", "-a", "v1", "--time", "10"], b"");
+    assert!(output.status.success());
+    let out = stdout(&output);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("algorithm"));
+    let fields: Vec<&str> = lines[1].split_whitespace().collect();
+    assert_eq!(fields[..2], ["v1", "1024"]);
+    assert!(fields[2].parse::<f64>().unwrap() > 0.0);
+
+    assert_eq!(
+        chibihash(&["bench", "--size", "lots"], b"").status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        chibihash(&["bench", "--time", "0"], b"").status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_diff() {
+    let root = std::env::temp_dir().join(format!("chibihash-cli-{}-diff", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for side in ["a", "b"] {
+        std::fs::create_dir_all(root.join(side).join("sub")).unwrap();
+        std::fs::write(root.join(side).join("same"), b"same").unwrap();
+        std::fs::write(root.join(side).join("sub/same"), b"same").unwrap();
+    }
+    let (a, b) = (root.join("a"), root.join("b"));
+    let (a_str, b_str) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let output = chibihash(&["diff", a_str, b_str], b"");
+    assert!(output.status.success());
+    assert!(stdout(&output).ends_with("match (2 files)\n"));
+
+    std::fs::write(a.join("sub/same"), b"edited").unwrap();
+    std::fs::write(a.join("gone"), b"gone").unwrap();
+    std::fs::write(b.join("new"), b"new").unwrap();
+    let output = chibihash(&["diff", "-j", "2", a_str, b_str], b"");
+    assert_eq!(output.status.code(), Some(1));
+    let out = stdout(&output);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines[..3],
+        ["missing: gone", "added: new", "changed: sub/same"]
+    );
+    assert!(lines[3].ends_with("differ: 1 changed, 1 missing, 1 added"));
+
+    // Two files
+    let same = chibihash(
+        &[
+            "diff",
+            a.join("same").to_str().unwrap(),
+            b.join("same").to_str().unwrap(),
+        ],
+        b"",
+    );
+    assert!(same.status.success());
+    let differ = chibihash(
+        &[
+            "diff",
+            a.join("gone").to_str().unwrap(),
+            b.join("new").to_str().unwrap(),
+        ],
+        b"",
+    );
+    assert_eq!(differ.status.code(), Some(1));
+    assert!(stdout(&differ).ends_with(" differ\n"));
+
+    assert_eq!(
+        chibihash(&["diff", a_str, b.join("new").to_str().unwrap()], b"")
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(chibihash(&["diff", a_str], b"").status.code(), Some(2));
+    std::fs::remove_dir_all(&root).unwrap();
+}
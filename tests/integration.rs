@@ -1,6 +1,7 @@
 //! Integration tests for the crate
 //! This ensures that the default implementation works as expected
-//! It will use the `v1` version of the algorithm
+//! It will use the `v1` version of the algorithm, or `v2` with the
+//! `default-v2` feature
 use chibihash::{chibi_hash64, ChibiHasher, StreamingChibiHasher};
 #[cfg(any(feature = "std", feature = "hashbrown"))]
 use chibihash::{ChibiHashMap, ChibiHashSet};
@@ -47,6 +48,7 @@ fn test_default_hasher_trait() {
 }
 
 #[test]
+#[cfg(not(feature = "default-v2"))]
 fn test_default_streaming_matches_direct() {
     // Helper function to test streaming vs direct and verify known values
     fn test_streaming(input: &[u8], seed: u64, expected: u64) {
@@ -107,3 +109,17 @@ fn test_default_chibi_hash_set() {
     set.insert("hello".to_string());
     assert!(set.contains("hello"));
 }
+
+#[test]
+fn test_default_version() {
+    #[cfg(not(feature = "default-v2"))]
+    use chibihash::v1 as default;
+    #[cfg(feature = "default-v2")]
+    use chibihash::v2 as default;
+
+    let data = b"The quick brown fox jumps over the lazy dog";
+    assert_eq!(chibi_hash64(data, 5), default::chibi_hash64(data, 5));
+    let mut streaming = StreamingChibiHasher::new(5);
+    streaming.update(data);
+    assert_eq!(streaming.finalize(), default::chibi_hash64(data, 5));
+}